@@ -6,6 +6,7 @@ use geojson::feature::Id;
 use geojson::{Feature, FeatureCollection};
 use serde::Serialize;
 use serde_json::Map;
+use uom::si::length::foot;
 use vatsim_parser::adaptation::maps::active::RunwayIdentifier;
 use vatsim_parser::adaptation::sectors::Sectors;
 use vatsim_parser::ese::Ese;
@@ -57,8 +58,14 @@ fn main() {
             id: Some(Id::String(id)),
             geometry: Some((&v.lateral_border).into()),
             properties: Some(Map::from_iter(vec![
-                ("lower_level".to_string(), v.lower_level.into()),
-                ("upper_level".to_string(), v.upper_level.into()),
+                (
+                    "lower_level".to_string(),
+                    v.lower_level.get::<foot>().into(),
+                ),
+                (
+                    "upper_level".to_string(),
+                    v.upper_level.get::<foot>().into(),
+                ),
             ])),
             ..Default::default()
         })
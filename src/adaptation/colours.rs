@@ -1,12 +1,13 @@
 use std::{collections::HashMap, num::TryFromIntError, sync::OnceLock};
 
-use bevy_reflect::Reflect;
+use bevy_derive::Deref;
+use bevy_reflect::{PartialReflect, Reflect, ReflectMut};
 use regex::Regex;
 use serde::{de::Visitor, Deserialize, Serialize};
 
 use crate::{
     sct::Sct,
-    symbology::Symbology,
+    symbology::{Symbology, SymbologyAttribute, SymbologyFolder},
     topsky::{Topsky, DEFAULT_COLOURS},
 };
 
@@ -37,11 +38,30 @@ impl Colour {
         i32::from(self.r) + i32::from(self.g) * 256 + i32::from(self.b) * 256 * 256
     }
 
-    fn from_symbology(symbology: &Symbology, key: &(String, String), default: Colour) -> Self {
+    /// Linear interpolation from `self` (`t` = 0) to `other` (`t` = 1), `t` is clamped.
+    #[must_use]
+    pub fn interpolate(self, other: Colour, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |from: u8, to: u8| {
+            (f32::from(from) + (f32::from(to) - f32::from(from)) * t).round() as u8
+        };
+
+        Self {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: channel(self.a, other.a),
+        }
+    }
+
+    fn from_symbology(
+        symbology: &Symbology,
+        folder: SymbologyFolder,
+        attribute: SymbologyAttribute,
+        default: Colour,
+    ) -> Self {
         symbology
-            .items
-            .0
-            .get(key)
+            .item(folder, attribute)
             .map_or(default, |item| item.colour)
     }
 
@@ -110,7 +130,7 @@ impl Serialize for Colour {
 }
 
 /// Colours used in the ASR map
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Reflect, Serialize, Deserialize)]
 pub struct MapColours {
     pub fix_symbol: Colour,
     pub fix_name: Colour,
@@ -167,122 +187,146 @@ impl MapColours {
         Self {
             fix_symbol: Colour::from_symbology(
                 symbology,
-                &("Fixes".to_string(), "symbol".to_string()),
+                SymbologyFolder::Fixes,
+                SymbologyAttribute::Symbol,
                 Self::DEFAULT_FIX_SYMBOL,
             ),
             fix_name: Colour::from_symbology(
                 symbology,
-                &("Fixes".to_string(), "name".to_string()),
+                SymbologyFolder::Fixes,
+                SymbologyAttribute::Name,
                 Self::DEFAULT_FIX_NAME,
             ),
             airport_symbol: Colour::from_symbology(
                 symbology,
-                &("Airports".to_string(), "symbol".to_string()),
+                SymbologyFolder::Airports,
+                SymbologyAttribute::Symbol,
                 Self::DEFAULT_AIRPORT_SYMBOL,
             ),
             airport_name: Colour::from_symbology(
                 symbology,
-                &("Airports".to_string(), "name".to_string()),
+                SymbologyFolder::Airports,
+                SymbologyAttribute::Name,
                 Self::DEFAULT_AIRPORT_NAME,
             ),
             ndb_symbol: Colour::from_symbology(
                 symbology,
-                &("NDBs".to_string(), "symbol".to_string()),
+                SymbologyFolder::Ndbs,
+                SymbologyAttribute::Symbol,
                 Self::DEFAULT_NDB_SYMBOL,
             ),
             ndb_name: Colour::from_symbology(
                 symbology,
-                &("NDBs".to_string(), "name".to_string()),
+                SymbologyFolder::Ndbs,
+                SymbologyAttribute::Name,
                 Self::DEFAULT_NDB_NAME,
             ),
             ndb_frequency: Colour::from_symbology(
                 symbology,
-                &("NDBs".to_string(), "frequency".to_string()),
+                SymbologyFolder::Ndbs,
+                SymbologyAttribute::Frequency,
                 Self::DEFAULT_NDB_FREQUENCY,
             ),
             vor_symbol: Colour::from_symbology(
                 symbology,
-                &("VORs".to_string(), "symbol".to_string()),
+                SymbologyFolder::Vors,
+                SymbologyAttribute::Symbol,
                 Self::DEFAULT_VOR_SYMBOL,
             ),
             vor_name: Colour::from_symbology(
                 symbology,
-                &("VORs".to_string(), "name".to_string()),
+                SymbologyFolder::Vors,
+                SymbologyAttribute::Name,
                 Self::DEFAULT_VOR_NAME,
             ),
             vor_frequency: Colour::from_symbology(
                 symbology,
-                &("VORs".to_string(), "frequency".to_string()),
+                SymbologyFolder::Vors,
+                SymbologyAttribute::Frequency,
                 Self::DEFAULT_VOR_FREQUENCY,
             ),
             low_airway_line: Colour::from_symbology(
                 symbology,
-                &("Low airways".to_string(), "line".to_string()),
+                SymbologyFolder::LowAirways,
+                SymbologyAttribute::Line,
                 Self::DEFAULT_LOW_AIRWAY_LINE,
             ),
             low_airway_name: Colour::from_symbology(
                 symbology,
-                &("Low airways".to_string(), "name".to_string()),
+                SymbologyFolder::LowAirways,
+                SymbologyAttribute::Name,
                 Self::DEFAULT_LOW_AIRWAY_NAME,
             ),
             high_airway_line: Colour::from_symbology(
                 symbology,
-                &("High airways".to_string(), "line".to_string()),
+                SymbologyFolder::HighAirways,
+                SymbologyAttribute::Line,
                 Self::DEFAULT_HIGH_AIRWAY_LINE,
             ),
             high_airway_name: Colour::from_symbology(
                 symbology,
-                &("High airways".to_string(), "line".to_string()),
+                SymbologyFolder::HighAirways,
+                SymbologyAttribute::Name,
                 Self::DEFAULT_HIGH_AIRWAY_NAME,
             ),
             sid: Colour::from_symbology(
                 symbology,
-                &("Sids".to_string(), "line".to_string()),
+                SymbologyFolder::Sids,
+                SymbologyAttribute::Line,
                 Self::DEFAULT_SID,
             ),
             star: Colour::from_symbology(
                 symbology,
-                &("Stars".to_string(), "line".to_string()),
+                SymbologyFolder::Stars,
+                SymbologyAttribute::Line,
                 Self::DEFAULT_STAR,
             ),
             artcc_boundary: Colour::from_symbology(
                 symbology,
-                &("ARTCC boundary".to_string(), "line".to_string()),
+                SymbologyFolder::ArtccBoundary,
+                SymbologyAttribute::Line,
                 Self::DEFAULT_ARTCC_BOUNDARY,
             ),
             artcc_low_boundary: Colour::from_symbology(
                 symbology,
-                &("ARTCC low boundary".to_string(), "line".to_string()),
+                SymbologyFolder::ArtccLowBoundary,
+                SymbologyAttribute::Line,
                 Self::DEFAULT_ARTCC_LOW_BOUNDARY,
             ),
             artcc_high_boundary: Colour::from_symbology(
                 symbology,
-                &("ARTCC high boundary".to_string(), "line".to_string()),
+                SymbologyFolder::ArtccHighBoundary,
+                SymbologyAttribute::Line,
                 Self::DEFAULT_ARTCC_HIGH_BOUNDARY,
             ),
             geo: Colour::from_symbology(
                 symbology,
-                &("Geo".to_string(), "line".to_string()),
+                SymbologyFolder::Geo,
+                SymbologyAttribute::Line,
                 Self::DEFAULT_GEO,
             ),
             runway_centreline: Colour::from_symbology(
                 symbology,
-                &("Runways".to_string(), "centerline".to_string()),
+                SymbologyFolder::Runways,
+                SymbologyAttribute::Centerline,
                 Self::DEFAULT_RUNWAY_CENTRELINE,
             ),
             runway_extended_centreline: Colour::from_symbology(
                 symbology,
-                &("Runways".to_string(), "extended centerline".to_string()),
+                SymbologyFolder::Runways,
+                SymbologyAttribute::ExtendedCenterline,
                 Self::DEFAULT_RUNWAY_EXTENDED_CENTRELINE,
             ),
             runway_name: Colour::from_symbology(
                 symbology,
-                &("Runways".to_string(), "name".to_string()),
+                SymbologyFolder::Runways,
+                SymbologyAttribute::Name,
                 Self::DEFAULT_RUNWAY_NAME,
             ),
             free_text: Colour::from_symbology(
                 symbology,
-                &("Other".to_string(), "freetext".to_string()),
+                SymbologyFolder::Other,
+                SymbologyAttribute::Freetext,
                 Self::DEFAULT_FREE_TEXT,
             ),
         }
@@ -319,7 +363,7 @@ impl Default for MapColours {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Reflect, Serialize, Deserialize)]
 pub struct SectorColours {
     pub active_background: Colour,
     pub inactive_background: Colour,
@@ -332,15 +376,14 @@ impl SectorColours {
         Self {
             active_background: Colour::from_symbology(
                 symbology,
-                &("Sector".to_string(), "active sector background".to_string()),
+                SymbologyFolder::Sector,
+                SymbologyAttribute::ActiveSectorBackground,
                 Self::DEFAULT_ACTIVE_BACKGROUND,
             ),
             inactive_background: Colour::from_symbology(
                 symbology,
-                &(
-                    "Sector".to_string(),
-                    "inactive sector background".to_string(),
-                ),
+                SymbologyFolder::Sector,
+                SymbologyAttribute::InactiveSectorBackground,
                 Self::DEFAULT_INACTIVE_BACKGROUND,
             ),
         }
@@ -356,7 +399,7 @@ impl Default for SectorColours {
 }
 
 // TODO Euroscope colour fallback?
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Reflect, Serialize, Deserialize)]
 pub struct TrackColours {
     pub assumed: Colour,
     pub advanced: Colour,
@@ -455,7 +498,7 @@ impl Default for TrackColours {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Reflect, Serialize, Deserialize)]
 pub struct UIColours {
     pub foreground: Colour,
     pub background: Colour,
@@ -530,8 +573,23 @@ impl Default for UIColours {
     }
 }
 
+/// Applies `f` to every `Colour` field of a reflected colour struct.
+fn map_reflected_colours(colours: &mut dyn PartialReflect, f: &impl Fn(Colour) -> Colour) {
+    if let ReflectMut::Struct(colours) = colours.reflect_mut() {
+        for i in 0..colours.field_len() {
+            if let Some(colour) = colours
+                .field_at_mut(i)
+                .and_then(|field| field.try_downcast_mut::<Colour>())
+            {
+                *colour = f(*colour);
+            }
+        }
+    }
+}
+
+/// One complete set of colours, i.e. the day or the night colours of a vACC.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct Colours {
+pub struct ColourTheme {
     pub track: TrackColours,
     pub sector: SectorColours,
     pub map: MapColours,
@@ -539,15 +597,7 @@ pub struct Colours {
     other: HashMap<String, Colour>,
 }
 
-impl Colours {
-    // topsky and sct colours referenced by name in maps
-    pub fn get(&self, name: &str, settings: &Settings) -> Option<Colour> {
-        self.other
-            .get(name)
-            .copied()
-            .or_else(|| Colour::from_topsky_default(settings, name))
-    }
-
+impl ColourTheme {
     pub fn from_euroscope(
         symbology: &Symbology,
         sct: &Sct,
@@ -576,4 +626,221 @@ impl Colours {
                 .collect(),
         }
     }
+
+    /// Copy with `f` applied to every colour of the theme.
+    #[must_use]
+    pub fn map_colours(&self, f: impl Fn(Colour) -> Colour) -> Self {
+        let mut theme = self.clone();
+        map_reflected_colours(&mut theme.track, &f);
+        map_reflected_colours(&mut theme.sector, &f);
+        map_reflected_colours(&mut theme.map, &f);
+        map_reflected_colours(&mut theme.ui, &f);
+        theme
+            .other
+            .values_mut()
+            .for_each(|colour| *colour = f(*colour));
+        theme
+    }
+}
+
+fn default_brightness() -> f32 {
+    1.0
+}
+
+/// Colours of the active theme, dereferences to its `ColourTheme` with the current
+/// brightness applied. Themes are switched with `set_theme` without reloading the
+/// adaptation, the colours of the profile itself are the `DEFAULT_THEME`.
+#[derive(Clone, Debug, Deref, Serialize, Deserialize)]
+#[serde(from = "SerialisedColours", into = "SerialisedColours")]
+pub struct Colours {
+    #[deref]
+    active: ColourTheme,
+    themes: HashMap<String, ColourTheme>,
+    active_theme: String,
+    brightness: f32,
+}
+
+/// The active theme is written flattened, without brightness, and not again in
+/// `themes`. Overlays editing e.g. `colours.map.geo` thereby edit the theme itself
+/// and the edits survive switching themes or brightness.
+#[derive(Serialize, Deserialize)]
+struct SerialisedColours {
+    #[serde(flatten)]
+    active: ColourTheme,
+    #[serde(default)]
+    themes: HashMap<String, ColourTheme>,
+    #[serde(default = "Colours::default_theme_name")]
+    active_theme: String,
+    #[serde(default = "default_brightness")]
+    brightness: f32,
+}
+
+impl From<Colours> for SerialisedColours {
+    fn from(mut colours: Colours) -> Self {
+        Self {
+            active: colours
+                .themes
+                .remove(&colours.active_theme)
+                .unwrap_or(colours.active),
+            themes: colours.themes,
+            active_theme: colours.active_theme,
+            brightness: colours.brightness,
+        }
+    }
+}
+
+impl From<SerialisedColours> for Colours {
+    fn from(serialised: SerialisedColours) -> Self {
+        let mut colours = Self {
+            active: serialised.active.clone(),
+            themes: serialised.themes,
+            active_theme: serialised.active_theme,
+            brightness: serialised.brightness,
+        };
+        colours
+            .themes
+            .insert(colours.active_theme.clone(), serialised.active);
+        colours.apply();
+        colours
+    }
+}
+
+impl Default for Colours {
+    fn default() -> Self {
+        Self::new(ColourTheme::default())
+    }
+}
+
+impl Colours {
+    pub const DEFAULT_THEME: &'static str = "default";
+
+    fn default_theme_name() -> String {
+        Self::DEFAULT_THEME.to_string()
+    }
+
+    fn new(default_theme: ColourTheme) -> Self {
+        Self {
+            active: default_theme.clone(),
+            themes: HashMap::from([(Self::default_theme_name(), default_theme)]),
+            active_theme: Self::default_theme_name(),
+            brightness: default_brightness(),
+        }
+    }
+
+    // topsky and sct colours referenced by name in maps
+    pub fn get(&self, name: &str, settings: &Settings) -> Option<Colour> {
+        self.other
+            .get(name)
+            .copied()
+            .or_else(|| Colour::from_topsky_default(settings, name))
+    }
+
+    pub fn from_euroscope(
+        symbology: &Symbology,
+        sct: &Sct,
+        topsky: &Option<Topsky>,
+        settings: &Settings,
+    ) -> Self {
+        Self::new(ColourTheme::from_euroscope(
+            symbology, sct, topsky, settings,
+        ))
+    }
+
+    /// Adds or replaces the theme `name`, re-applies it if it is the active theme.
+    pub fn add_theme(&mut self, name: impl Into<String>, theme: ColourTheme) {
+        let name = name.into();
+        let is_active = name == self.active_theme;
+        self.themes.insert(name, theme);
+        if is_active {
+            self.apply();
+        }
+    }
+
+    pub fn themes(&self) -> impl Iterator<Item = &str> {
+        self.themes.keys().map(String::as_str)
+    }
+
+    pub fn active_theme(&self) -> &str {
+        &self.active_theme
+    }
+
+    /// Switches to the theme `name`, `false` if there is no such theme.
+    pub fn set_theme(&mut self, name: &str) -> bool {
+        if !self.themes.contains_key(name) {
+            return false;
+        }
+        self.active_theme = name.to_string();
+        self.apply();
+        true
+    }
+
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+
+    /// Dims the active theme towards black, 1 is the theme's own colours and 0 black.
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.brightness = brightness.clamp(0.0, 1.0);
+        self.apply();
+    }
+
+    fn apply(&mut self) {
+        let black = Colour::from_rgb(0, 0, 0);
+        let brightness = self.brightness;
+        if let Some(theme) = self.themes.get(&self.active_theme) {
+            self.active = theme.map_colours(|colour| Colour {
+                a: colour.a,
+                ..black.interpolate(colour, brightness)
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Colour, ColourTheme, Colours};
+
+    fn theme(geo: Colour) -> ColourTheme {
+        let mut theme = ColourTheme::default();
+        theme.map.geo = geo;
+        theme
+    }
+
+    /// edits `colours.map.geo` the way a jsonnet overlay does
+    fn overlay(colours: &Colours, geo: &str) -> Colours {
+        let mut json = serde_json::to_value(colours).unwrap();
+        json["map"]["geo"] = geo.into();
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_overlay_then_set_brightness() {
+        let colours = Colours::new(theme(Colour::from_rgb(10, 10, 10)));
+        let mut colours = overlay(&colours, "#c8c8c8");
+        assert_eq!(colours.map.geo, Colour::from_rgb(200, 200, 200));
+
+        colours.set_brightness(0.5);
+        assert_eq!(colours.map.geo, Colour::from_rgb(100, 100, 100));
+        colours.set_brightness(1.0);
+        assert_eq!(colours.map.geo, Colour::from_rgb(200, 200, 200));
+    }
+
+    #[test]
+    fn test_overlay_with_theme_switching() {
+        let mut colours = Colours::new(theme(Colour::from_rgb(10, 10, 10)));
+        colours.add_theme("night", theme(Colour::from_rgb(20, 20, 20)));
+        assert!(colours.set_theme("night"));
+        colours.set_brightness(0.5);
+
+        // the overlay edits the active theme, at its own brightness
+        let mut colours = overlay(&colours, "#646464");
+        assert_eq!(colours.active_theme(), "night");
+        assert_eq!(colours.map.geo, Colour::from_rgb(50, 50, 50));
+
+        assert!(colours.set_theme(Colours::DEFAULT_THEME));
+        colours.set_brightness(1.0);
+        assert_eq!(colours.map.geo, Colour::from_rgb(10, 10, 10));
+        assert!(colours.set_theme("night"));
+        assert_eq!(colours.map.geo, Colour::from_rgb(100, 100, 100));
+    }
 }
@@ -1,8 +1,14 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
 use tracing::debug;
+use uom::si::f32::Length;
 
+use super::sectors::sector_fir;
+use super::settings::ResolvedCoordinationTiming;
+use super::Adaptation;
 use crate::ese::{Constraint, Ese};
+use crate::level::IndicatedLevel;
 
 pub(super) fn extract_constraints(
     ese: &Ese,
@@ -24,3 +30,222 @@ pub(super) fn extract_constraints(
     let keyed = |v: Vec<Constraint>| v.into_iter().map(|c| (c.key(), c)).collect();
     (keyed(departure), keyed(destination))
 }
+
+/// A flight's path through the sectors, as needed to find its COPX constraints
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trajectory {
+    /// route points in flown order, including the departure and destination airports
+    pub fixes: Vec<String>,
+    pub departure_runway: Option<String>,
+    pub arrival_runway: Option<String>,
+    /// sectors in flown order, each change is a transition (exit sector, entry sector)
+    pub sectors: Vec<String>,
+}
+
+/// Constraint matching a sector transition of a `Trajectory`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ApplicableConstraint<'a> {
+    pub constraint: &'a Constraint,
+    pub timing: ResolvedCoordinationTiming,
+    /// `Constraint::climb_level` as an altitude or flight level
+    pub climb_level: Option<IndicatedLevel>,
+    /// `Constraint::descent_level` as an altitude or flight level
+    pub descent_level: Option<IndicatedLevel>,
+}
+
+impl Trajectory {
+    /// Whether the fixes and runways of `constraint` match, wildcards match anything.
+    fn matches(&self, constraint: &Constraint) -> bool {
+        let position = |fix: &Option<String>| match fix {
+            Some(fix) => self.fixes.iter().position(|f| f == fix).map(Some),
+            None => Some(None),
+        };
+        let runway_matches =
+            |runway: &Option<String>, filed: &Option<String>| runway.is_none() || runway == filed;
+
+        let (Some(previous), Some(fix), Some(subsequent)) = (
+            position(&constraint.previous_fix),
+            position(&constraint.fix),
+            position(&constraint.subsequent_fix),
+        ) else {
+            return false;
+        };
+        let in_order = |before: Option<usize>, after: Option<usize>| match (before, after) {
+            (Some(before), Some(after)) => before < after,
+            _ => true,
+        };
+
+        in_order(previous, fix)
+            && in_order(fix, subsequent)
+            && in_order(previous, subsequent)
+            && runway_matches(&constraint.departure_runway, &self.departure_runway)
+            && runway_matches(&constraint.arrival_runway, &self.arrival_runway)
+    }
+}
+
+/// Precedence of a matching constraint, like EuroScope the most specific one wins: a
+/// named coordination fix before runways before the previous and subsequent fixes.
+fn specificity(constraint: &Constraint) -> (bool, usize, usize) {
+    let runways = [&constraint.departure_runway, &constraint.arrival_runway];
+    let neighbours = [&constraint.previous_fix, &constraint.subsequent_fix];
+
+    (
+        constraint.fix.is_some(),
+        runways.iter().filter(|runway| runway.is_some()).count(),
+        neighbours.iter().filter(|fix| fix.is_some()).count(),
+    )
+}
+
+impl Adaptation {
+    /// The COPX constraint of each sector transition of `trajectory`, in flown order.
+    /// Timing is resolved with the FIR override of the entry or, failing that, the exit
+    /// sector's FIR.
+    pub fn applicable_constraints(&self, trajectory: &Trajectory) -> Vec<ApplicableConstraint<'_>> {
+        let coordination = &self.settings.coordination;
+
+        trajectory
+            .sectors
+            .windows(2)
+            .filter(|transition| transition[0] != transition[1])
+            .filter_map(|transition| {
+                let (exit, entry) = (&transition[0], &transition[1]);
+                let constraint = self
+                    .departure_constraints
+                    .values()
+                    .chain(self.destination_constraints.values())
+                    .filter(|constraint| {
+                        constraint.exit_sector == *exit && constraint.entry_sector == *entry
+                    })
+                    .filter(|constraint| trajectory.matches(constraint))
+                    // reversed key to pick the lowest key among equally specific ones
+                    .max_by(|a, b| {
+                        specificity(a)
+                            .cmp(&specificity(b))
+                            .then_with(|| b.key().cmp(&a.key()))
+                    })?;
+
+                let fir = [sector_fir(entry), sector_fir(exit)]
+                    .into_iter()
+                    .find(|fir| coordination.fir_overrides.contains_key(*fir));
+                // departure constraints are usually published relative to the
+                // departure airport, arrival ones to the destination
+                let indicated = |level: Option<Length>, airport: Option<&String>| {
+                    level.map(|level| {
+                        self.settings.transitions.indicated_limit(
+                            level,
+                            airport.map(String::as_str),
+                            Some(sector_fir(exit)),
+                        )
+                    })
+                };
+                Some(ApplicableConstraint {
+                    constraint,
+                    timing: coordination.resolve(fir, constraint.coord_timing.as_ref()),
+                    climb_level: indicated(constraint.climb_level, trajectory.fixes.first()),
+                    descent_level: indicated(constraint.descent_level, trajectory.fixes.last()),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uom::si::f32::Time;
+    use uom::si::time::minute;
+
+    use crate::adaptation::settings::CoordinationTiming;
+    use crate::adaptation::Adaptation;
+    use crate::ese::Constraint;
+    use crate::level::{feet, IndicatedLevel};
+
+    use super::Trajectory;
+
+    const ALB: &str = "EDMM\u{b7}EDMMALB\u{b7}245\u{b7}315";
+    const BBG: &str = "EDMM\u{b7}EDMMBBG\u{b7}245\u{b7}295";
+    const DONAU: &str = "LOVV\u{b7}LOVVN\u{b7}245\u{b7}660";
+
+    fn constraint(previous_fix: Option<&str>, fix: Option<&str>, entry_sector: &str) -> Constraint {
+        Constraint {
+            previous_fix: previous_fix.map(str::to_string),
+            departure_runway: None,
+            subsequent_fix: None,
+            arrival_runway: None,
+            fix: fix.map(str::to_string),
+            exit_sector: ALB.to_string(),
+            entry_sector: entry_sector.to_string(),
+            climb_level: Some(feet(25_000.0)),
+            descent_level: None,
+            description: String::new(),
+            coord_timing: None,
+        }
+    }
+
+    #[test]
+    fn test_applicable_constraints() {
+        let mut runway_specific = constraint(Some("EDDM"), Some("UPALA"), BBG);
+        runway_specific.departure_runway = Some("08L".to_string());
+        runway_specific.climb_level = Some(feet(23_000.0));
+        let mut timed = constraint(None, None, DONAU);
+        timed.coord_timing = Some(CoordinationTiming {
+            act_time: Some(Time::new::<minute>(10.0)),
+            abi_time: None,
+            rev_time: None,
+        });
+        let mut adaptation = Adaptation {
+            departure_constraints: [
+                constraint(None, None, BBG),
+                constraint(Some("EDDM"), Some("UPALA"), BBG),
+                constraint(Some("EDDM"), Some("DOSIS"), BBG),
+                runway_specific,
+                timed,
+            ]
+            .into_iter()
+            .map(|constraint| (constraint.key(), constraint))
+            .collect(),
+            ..Default::default()
+        };
+        adaptation.settings.coordination.fir_overrides.insert(
+            "LOVV".to_string(),
+            CoordinationTiming {
+                act_time: Some(Time::new::<minute>(20.0)),
+                abi_time: Some(Time::new::<minute>(40.0)),
+                rev_time: None,
+            },
+        );
+
+        let mut trajectory = Trajectory {
+            fixes: ["EDDM", "UPALA", "TENLO", "EDDH"]
+                .map(str::to_string)
+                .to_vec(),
+            departure_runway: Some("26R".to_string()),
+            arrival_runway: None,
+            sectors: [ALB, ALB, BBG].map(str::to_string).to_vec(),
+        };
+        let applicable = adaptation.applicable_constraints(&trajectory);
+        assert_eq!(applicable.len(), 1);
+        assert_eq!(applicable[0].constraint.fix.as_deref(), Some("UPALA"));
+        assert_eq!(applicable[0].constraint.climb_level, Some(feet(25_000.0)));
+        assert_eq!(
+            applicable[0].climb_level,
+            Some(IndicatedLevel::FlightLevel(250))
+        );
+        assert_eq!(
+            applicable[0].timing,
+            adaptation.settings.coordination.resolve(None, None)
+        );
+
+        trajectory.departure_runway = Some("08L".to_string());
+        let applicable = adaptation.applicable_constraints(&trajectory);
+        assert_eq!(applicable[0].constraint.climb_level, Some(feet(23_000.0)));
+
+        trajectory.fixes = ["EDDM", "TENLO", "EDDH"].map(str::to_string).to_vec();
+        let applicable = adaptation.applicable_constraints(&trajectory);
+        assert_eq!(applicable[0].constraint.fix, None);
+
+        trajectory.sectors = [ALB, DONAU].map(str::to_string).to_vec();
+        let applicable = adaptation.applicable_constraints(&trajectory);
+        assert_eq!(applicable[0].timing.act_time, Time::new::<minute>(10.0));
+        assert_eq!(applicable[0].timing.abi_time, Time::new::<minute>(40.0));
+    }
+}
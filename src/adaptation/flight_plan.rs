@@ -0,0 +1,656 @@
+use std::{fmt::Display, ops::Range};
+
+use serde::{Deserialize, Serialize};
+use uom::si::{f32::Length, length::foot};
+
+use super::constraints::Trajectory;
+use super::locations::route::{ExpandedRoute, Route, RouteDiagnostic, RouteElement};
+use super::Adaptation;
+use crate::level::{self, feet};
+
+/// The parts of a filed flight plan needed to validate its route
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlightPlan {
+    pub departure: String,
+    pub destination: String,
+    /// ICAO item 15 route
+    pub route: String,
+    /// requested cruising level
+    #[serde(with = "level::as_feet")]
+    pub requested_level: Length,
+    pub departure_runway: Option<String>,
+    pub arrival_runway: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum RouteFindingKind {
+    Diagnostic(RouteDiagnostic),
+    /// airway flown against its direction of flight
+    WrongDirection {
+        airway: String,
+        from: String,
+        to: String,
+    },
+    BelowMinimumLevel {
+        airway: String,
+        from: String,
+        to: String,
+        level: Length,
+        minimum_level: Length,
+    },
+    AboveMaximumLevel {
+        airway: String,
+        from: String,
+        to: String,
+        level: Length,
+        maximum_level: Length,
+    },
+    /// no SID of the airport ends at the route
+    NoSidConnection(String),
+    /// no STAR of the airport starts at the route
+    NoStarConnection(String),
+    /// requested level below the COPX climb level, at its coordination fix if any
+    BelowClimbLevel {
+        fix: Option<String>,
+        climb_level: Length,
+        requested_level: Length,
+    },
+    /// requested level below the COPX descent level, at its coordination fix if any
+    BelowDescentLevel {
+        fix: Option<String>,
+        descent_level: Length,
+        requested_level: Length,
+    },
+}
+
+impl Display for RouteFindingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ft = |level: &Length| level.get::<foot>().round();
+
+        match self {
+            Self::Diagnostic(RouteDiagnostic::UnknownPoint(point)) => {
+                write!(f, "unknown point {point}")
+            }
+            Self::Diagnostic(RouteDiagnostic::UnknownSid(sid)) => write!(f, "unknown SID {sid}"),
+            Self::Diagnostic(RouteDiagnostic::UnknownStar(star)) => {
+                write!(f, "unknown STAR {star}")
+            }
+            Self::Diagnostic(RouteDiagnostic::UnknownAirwaySegment { airway, from, to }) => {
+                write!(f, "{airway} does not connect {from} and {to}")
+            }
+            Self::Diagnostic(RouteDiagnostic::DanglingAirway(airway)) => {
+                write!(f, "{airway} not followed by a point")
+            }
+            Self::Diagnostic(RouteDiagnostic::UnknownToken(token)) => {
+                write!(f, "unknown item {token}")
+            }
+            Self::Diagnostic(RouteDiagnostic::AmbiguousPoint(point)) => {
+                write!(f, "ambiguous point {point}, assumed the closest one")
+            }
+            Self::Diagnostic(RouteDiagnostic::AmbiguousProcedure(procedure)) => {
+                write!(
+                    f,
+                    "{procedure} exists at several airports, assumed the first one"
+                )
+            }
+            Self::WrongDirection { airway, from, to } => {
+                write!(f, "{airway} {from}-{to} against the direction of flight")
+            }
+            Self::BelowMinimumLevel {
+                airway,
+                from,
+                to,
+                level,
+                minimum_level,
+            } => write!(
+                f,
+                "{airway} {from}-{to} at {} ft below minimum {} ft",
+                ft(level),
+                ft(minimum_level)
+            ),
+            Self::AboveMaximumLevel {
+                airway,
+                from,
+                to,
+                level,
+                maximum_level,
+            } => write!(
+                f,
+                "{airway} {from}-{to} at {} ft above maximum {} ft",
+                ft(level),
+                ft(maximum_level)
+            ),
+            Self::NoSidConnection(airport) => write!(f, "no SID of {airport} connects"),
+            Self::NoStarConnection(airport) => write!(f, "no STAR of {airport} connects"),
+            Self::BelowClimbLevel {
+                fix,
+                climb_level,
+                requested_level,
+            } => {
+                write!(
+                    f,
+                    "requested {} ft below climb level {} ft",
+                    ft(requested_level),
+                    ft(climb_level)
+                )?;
+                fix.iter().try_for_each(|fix| write!(f, " at {fix}"))
+            }
+            Self::BelowDescentLevel {
+                fix,
+                descent_level,
+                requested_level,
+            } => {
+                write!(
+                    f,
+                    "requested {} ft below descent level {} ft",
+                    ft(requested_level),
+                    ft(descent_level)
+                )?;
+                fix.iter().try_for_each(|fix| write!(f, " at {fix}"))
+            }
+        }
+    }
+}
+
+/// Problem with a flight plan route, located in the route string where possible
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RouteFinding {
+    pub kind: RouteFindingKind,
+    /// byte range in `FlightPlan::route` causing the finding
+    pub span: Option<Range<usize>>,
+    /// route item causing the finding, i.e. `Y101`
+    pub token: Option<String>,
+}
+
+impl Display for RouteFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.token {
+            Some(token) => write!(f, "{token}: {}", self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl Adaptation {
+    /// Checks the route of `flight_plan` for unknown points, airway direction and level
+    /// restrictions, SID/STAR connections and COPX levels.
+    pub fn validate_route(&self, flight_plan: &FlightPlan) -> Vec<RouteFinding> {
+        let route = Route::parse(&flight_plan.route);
+        let expanded = self.locations.expand_route(
+            &route,
+            (
+                Some(&flight_plan.departure),
+                flight_plan.departure_runway.as_deref(),
+            ),
+            (
+                Some(&flight_plan.destination),
+                flight_plan.arrival_runway.as_deref(),
+            ),
+        );
+
+        let mut findings: Vec<(Option<usize>, RouteFindingKind)> = expanded
+            .diagnostics
+            .iter()
+            .map(|(element, diagnostic)| {
+                (
+                    Some(*element),
+                    RouteFindingKind::Diagnostic(diagnostic.clone()),
+                )
+            })
+            .collect();
+        findings.extend(self.airway_findings(&expanded, flight_plan));
+        findings.extend(self.procedure_findings(&route, flight_plan));
+        findings.extend(self.constraint_findings(&expanded, flight_plan));
+
+        findings.sort_by_key(|(element, _)| element.unwrap_or(usize::MAX));
+        findings
+            .into_iter()
+            .map(|(element, kind)| {
+                let span = element
+                    .and_then(|element| route.spans.get(element))
+                    .cloned();
+                RouteFinding {
+                    kind,
+                    token: span
+                        .clone()
+                        .and_then(|span| flight_plan.route.get(span))
+                        .map(str::to_string),
+                    span,
+                }
+            })
+            .collect()
+    }
+
+    fn airway_findings(
+        &self,
+        expanded: &ExpandedRoute,
+        flight_plan: &FlightPlan,
+    ) -> Vec<(Option<usize>, RouteFindingKind)> {
+        let mut findings = Vec::new();
+        for pair in expanded.fixes.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            let Some(airway) = &to.via else {
+                continue;
+            };
+            let Some(segment) =
+                self.locations
+                    .airways
+                    .segment(airway, &from.fix.designator, &to.fix.designator)
+            else {
+                continue;
+            };
+            let level = match from.speed_level {
+                Some(speed_level) => speed_level.level.length(),
+                None => Some(flight_plan.requested_level),
+            };
+            // the whole airway is reported at its route item, not at its endpoint
+            let (airway, from, to, element) = (
+                airway.clone(),
+                from.fix.designator.clone(),
+                to.fix.designator.clone(),
+                to.via_element,
+            );
+
+            if !segment.valid_direction {
+                findings.push((
+                    element,
+                    RouteFindingKind::WrongDirection {
+                        airway: airway.clone(),
+                        from: from.clone(),
+                        to: to.clone(),
+                    },
+                ));
+            }
+            let Some(level) = level else {
+                continue;
+            };
+            let segment_level = |level: Option<u32>| level.map(|level| feet(level as f32));
+            if let Some(minimum_level) =
+                segment_level(segment.minimum_level).filter(|&minimum| level < minimum)
+            {
+                findings.push((
+                    element,
+                    RouteFindingKind::BelowMinimumLevel {
+                        airway: airway.clone(),
+                        from: from.clone(),
+                        to: to.clone(),
+                        level,
+                        minimum_level,
+                    },
+                ));
+            }
+            if let Some(maximum_level) =
+                segment_level(segment.maximum_level).filter(|&maximum| level > maximum)
+            {
+                findings.push((
+                    element,
+                    RouteFindingKind::AboveMaximumLevel {
+                        airway,
+                        from,
+                        to,
+                        level,
+                        maximum_level,
+                    },
+                ));
+            }
+        }
+
+        findings
+    }
+
+    fn procedure_findings(
+        &self,
+        route: &Route,
+        flight_plan: &FlightPlan,
+    ) -> Vec<(Option<usize>, RouteFindingKind)> {
+        let locations = &self.locations;
+        let element_of = |name: Option<&str>| {
+            name.and_then(|name| {
+                route.elements.iter().position(|element| match element {
+                    RouteElement::Sid(procedure) | RouteElement::Star(procedure) => {
+                        procedure == name
+                    }
+                    RouteElement::Point { designator, .. } => designator == name,
+                    _ => false,
+                })
+            })
+        };
+        let mut findings = Vec::new();
+
+        let has_sids = locations
+            .sids
+            .get(&flight_plan.departure)
+            .is_some_and(|sids| !sids.is_empty());
+        let sid_connects = match &flight_plan.departure_runway {
+            Some(runway) => locations
+                .suggest_sid(&flight_plan.departure, runway, route)
+                .is_some(),
+            None => locations
+                .sids
+                .get(&flight_plan.departure)
+                .into_iter()
+                .flat_map(|sids| sids.iter_all().flat_map(|(_, sids)| sids))
+                .any(|sid| {
+                    route.sid() == Some(sid.name.as_str())
+                        || sid.waypoints.last().map(|fix| fix.designator.as_str())
+                            == route.first_point()
+                }),
+        };
+        if has_sids && !sid_connects {
+            findings.push((
+                element_of(route.sid().or(route.first_point())),
+                RouteFindingKind::NoSidConnection(flight_plan.departure.clone()),
+            ));
+        }
+
+        let has_stars = locations
+            .stars
+            .get(&flight_plan.destination)
+            .is_some_and(|stars| !stars.is_empty());
+        let star_connects = match &flight_plan.arrival_runway {
+            Some(runway) => locations
+                .suggest_star(&flight_plan.destination, runway, route)
+                .is_some(),
+            None => locations
+                .stars
+                .get(&flight_plan.destination)
+                .into_iter()
+                .flat_map(|stars| stars.iter_all().flat_map(|(_, stars)| stars))
+                .any(|star| {
+                    route.star() == Some(star.name.as_str())
+                        || star.waypoints.first().map(|fix| fix.designator.as_str())
+                            == route.last_point()
+                }),
+        };
+        if has_stars && !star_connects {
+            findings.push((
+                element_of(route.star().or(route.last_point())),
+                RouteFindingKind::NoStarConnection(flight_plan.destination.clone()),
+            ));
+        }
+
+        findings
+    }
+
+    /// COPX levels above the requested level at the sector transitions the route crosses.
+    fn constraint_findings(
+        &self,
+        expanded: &ExpandedRoute,
+        flight_plan: &FlightPlan,
+    ) -> Vec<(Option<usize>, RouteFindingKind)> {
+        let requested_level = flight_plan.requested_level;
+        let path: Vec<_> = expanded
+            .fixes
+            .iter()
+            .map(|route_fix| {
+                let level = route_fix
+                    .speed_level
+                    .and_then(|speed_level| speed_level.level.length())
+                    .unwrap_or(requested_level);
+                (route_fix.fix.coordinate, level)
+            })
+            .collect();
+        let trajectory = Trajectory {
+            fixes: expanded
+                .fixes
+                .iter()
+                .map(|route_fix| route_fix.fix.designator.clone())
+                .collect(),
+            departure_runway: flight_plan.departure_runway.clone(),
+            arrival_runway: flight_plan.arrival_runway.clone(),
+            sectors: self
+                .sector_sequence(&path)
+                .into_iter()
+                .map(|crossing| crossing.sector)
+                .collect(),
+        };
+        let element_of = |fix: &Option<String>| {
+            fix.as_ref().and_then(|fix| {
+                expanded
+                    .fixes
+                    .iter()
+                    .find(|route_fix| route_fix.fix.designator == *fix)
+                    .and_then(|route_fix| route_fix.element)
+            })
+        };
+
+        let mut findings = Vec::new();
+        for applicable in self.applicable_constraints(&trajectory) {
+            let constraint = applicable.constraint;
+            let element = element_of(&constraint.fix);
+            if let Some(climb_level) = constraint
+                .climb_level
+                .filter(|&climb_level| requested_level < climb_level)
+            {
+                findings.push((
+                    element,
+                    RouteFindingKind::BelowClimbLevel {
+                        fix: constraint.fix.clone(),
+                        climb_level,
+                        requested_level,
+                    },
+                ));
+            }
+            if let Some(descent_level) = constraint
+                .descent_level
+                .filter(|&descent_level| requested_level < descent_level)
+            {
+                findings.push((
+                    element,
+                    RouteFindingKind::BelowDescentLevel {
+                        fix: constraint.fix.clone(),
+                        descent_level,
+                        requested_level,
+                    },
+                ));
+            }
+        }
+
+        findings.dedup();
+        findings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use geo::line_string;
+
+    use crate::adaptation::locations::{
+        airways::{AirwayFix, AirwayGraph, AirwayType},
+        route::RouteDiagnostic,
+        test_fixtures::{airport, fix},
+        GraphPosition, Locations, SID,
+    };
+    use crate::adaptation::sectors::{Sector, Sectors, Volume};
+    use crate::adaptation::Adaptation;
+    use crate::ese::Constraint;
+    use crate::level::feet;
+
+    use super::{FlightPlan, RouteFindingKind};
+
+    /// Sector with a single volume from 10.0 to 13.0 east and `south` to `north`.
+    fn sector(id: &str, south: f64, north: f64) -> (Sector, Volume) {
+        let border = line_string![
+            (x: 10.0, y: south),
+            (x: 13.0, y: south),
+            (x: 13.0, y: north),
+            (x: 10.0, y: north),
+            (x: 10.0, y: south),
+        ];
+        let sector = Sector {
+            id: id.to_string(),
+            position_priority: vec![id.to_string()],
+            runway_filter: vec![],
+            volumes: [id.to_string()].into_iter().collect(),
+            departure_aerodromes: std::collections::HashSet::default(),
+            arrival_aerodromes: std::collections::HashSet::default(),
+        };
+        let volume = Volume::new(id.to_string(), feet(0.0), feet(66_000.0), border);
+        (sector, volume)
+    }
+
+    fn adaptation() -> Adaptation {
+        let mersi = fix("MERSI", 11.0, 48.0);
+        let bamur = fix("BAMUR", 11.5, 48.5);
+        let armut = fix("ARMUT", 12.0, 49.0);
+        let mut airways = AirwayGraph::default();
+        for (from, to) in [(&mersi, &bamur), (&bamur, &armut)] {
+            airways.insert_or_update_segment(
+                "Y101",
+                &from.designator,
+                GraphPosition(from.coordinate),
+                &AirwayFix {
+                    fix: to.clone(),
+                    valid_direction: true,
+                    minimum_level: Some(10_000),
+                    maximum_level: Some(36_000),
+                },
+                AirwayType::Both,
+            );
+        }
+        let sids = [SID {
+            name: "MERSI1S".to_string(),
+            airport: "EDDM".to_string(),
+            runway: Some("26R".to_string()),
+            waypoints: vec![fix("DM010", 11.7, 48.3), mersi.clone()],
+            legs: vec![],
+        }]
+        .into_iter()
+        .map(|sid| (sid.name.clone(), sid))
+        .collect();
+        let constraint = |entry_sector: &str, climb_level: f32| Constraint {
+            previous_fix: Some("EDDM".to_string()),
+            departure_runway: None,
+            subsequent_fix: None,
+            arrival_runway: None,
+            fix: Some("ARMUT".to_string()),
+            exit_sector: "EDMM".to_string(),
+            entry_sector: entry_sector.to_string(),
+            climb_level: Some(feet(climb_level)),
+            descent_level: None,
+            description: String::new(),
+            coord_timing: None,
+        };
+        // EDUU north of EDMM, LOVV is never entered
+        let (sectors, volumes): (HashMap<_, _>, HashMap<_, _>) =
+            [sector("EDMM", 47.0, 48.8), sector("EDUU", 48.8, 50.0)]
+                .into_iter()
+                .map(|(sector, volume)| ((sector.id.clone(), sector), (volume.id.clone(), volume)))
+                .unzip();
+
+        let mut adaptation = Adaptation {
+            locations: Locations {
+                fixes: [mersi, bamur, armut]
+                    .into_iter()
+                    .map(|fix| (fix.designator.clone(), fix))
+                    .collect(),
+                airports: [airport("EDDM", 11.8, 48.4), airport("EDDN", 11.1, 49.5)]
+                    .into_iter()
+                    .collect(),
+                sids: [("EDDM".to_string(), sids)].into_iter().collect(),
+                airways,
+                ..Default::default()
+            },
+            departure_constraints: [constraint("EDUU", 25_000.0), constraint("LOVV", 30_000.0)]
+                .into_iter()
+                .map(|constraint| (constraint.key(), constraint))
+                .collect(),
+            sectors: Sectors(sectors),
+            volumes,
+            ..Default::default()
+        };
+        adaptation.rebuild_sector_index();
+        adaptation
+    }
+
+    fn flight_plan(route: &str, requested_level: f32) -> FlightPlan {
+        FlightPlan {
+            departure: "EDDM".to_string(),
+            destination: "EDDN".to_string(),
+            route: route.to_string(),
+            requested_level: feet(requested_level),
+            departure_runway: Some("26R".to_string()),
+            arrival_runway: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_route() {
+        let adaptation = adaptation();
+
+        assert_eq!(
+            adaptation.validate_route(&flight_plan("N0450F350 MERSI1S MERSI Y101 ARMUT", 35_000.0)),
+            vec![]
+        );
+
+        let findings =
+            adaptation.validate_route(&flight_plan("N0250A080 ARMUT Y101 MERSI XYZ", 8_000.0));
+        assert_eq!(
+            findings
+                .iter()
+                .map(|finding| (finding.token.as_deref(), &finding.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    Some("ARMUT"),
+                    &RouteFindingKind::NoSidConnection("EDDM".to_string())
+                ),
+                (
+                    Some("ARMUT"),
+                    &RouteFindingKind::BelowClimbLevel {
+                        fix: Some("ARMUT".to_string()),
+                        climb_level: feet(25_000.0),
+                        requested_level: feet(8_000.0),
+                    }
+                ),
+                (
+                    Some("Y101"),
+                    &RouteFindingKind::WrongDirection {
+                        airway: "Y101".to_string(),
+                        from: "ARMUT".to_string(),
+                        to: "BAMUR".to_string(),
+                    }
+                ),
+                (
+                    Some("Y101"),
+                    &RouteFindingKind::BelowMinimumLevel {
+                        airway: "Y101".to_string(),
+                        from: "ARMUT".to_string(),
+                        to: "BAMUR".to_string(),
+                        level: feet(8_000.0),
+                        minimum_level: feet(10_000.0),
+                    }
+                ),
+                (
+                    Some("Y101"),
+                    &RouteFindingKind::WrongDirection {
+                        airway: "Y101".to_string(),
+                        from: "BAMUR".to_string(),
+                        to: "MERSI".to_string(),
+                    }
+                ),
+                (
+                    Some("Y101"),
+                    &RouteFindingKind::BelowMinimumLevel {
+                        airway: "Y101".to_string(),
+                        from: "BAMUR".to_string(),
+                        to: "MERSI".to_string(),
+                        level: feet(8_000.0),
+                        minimum_level: feet(10_000.0),
+                    }
+                ),
+                (
+                    Some("XYZ"),
+                    &RouteFindingKind::Diagnostic(RouteDiagnostic::UnknownPoint("XYZ".to_string()))
+                ),
+            ]
+        );
+        assert_eq!(findings[2].span, Some(16..20));
+        assert_eq!(
+            findings[2].to_string(),
+            "Y101: Y101 ARMUT-BAMUR against the direction of flight"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::voice_channels;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum FrequencyError {
+    #[error("invalid frequency format: {0}")]
+    Format(String),
+    #[error("frequency outside of the VHF COM band: {0}")]
+    OutOfRange(String),
+    #[error("frequency is no valid 25/8.33 kHz channel: {0}")]
+    Channel(String),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChannelSpacing {
+    Khz25,
+    Khz8_33,
+}
+
+/// A VHF COM channel, stored as its channel name in kHz, i.e. 122.8 and 122.800 are
+/// both `Frequency(122_800)`. For 8.33 kHz channels the channel name differs from
+/// the carrier frequency, see `Frequency::carrier_khz`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Frequency(u32);
+
+impl Frequency {
+    const MIN_KHZ: u32 = 118_000;
+    const MAX_KHZ: u32 = 136_990;
+    const BLOCK_KHZ: u32 = 25;
+
+    /// channel name in kHz
+    pub fn khz(self) -> u32 {
+        self.0
+    }
+
+    pub fn spacing(self) -> ChannelSpacing {
+        if self.0.is_multiple_of(Self::BLOCK_KHZ) {
+            ChannelSpacing::Khz25
+        } else {
+            ChannelSpacing::Khz8_33
+        }
+    }
+
+    /// Actual carrier frequency in kHz, 132.235 is transmitted on 132.2333 MHz.
+    pub fn carrier_khz(self) -> f64 {
+        let offset = self.0 % Self::BLOCK_KHZ;
+        let block = f64::from(self.0 - offset);
+        match offset {
+            0 => block,
+            _ => block + f64::from(offset / 5 - 1) * f64::from(Self::BLOCK_KHZ) / 3.0,
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = FrequencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format_err = || FrequencyError::Format(s.to_string());
+        let (mhz, fraction) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+        if mhz.is_empty()
+            || !mhz.bytes().all(|b| b.is_ascii_digit())
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+            || fraction.trim_end_matches('0').len() > 3
+        {
+            return Err(format_err());
+        }
+        let mhz: u32 = mhz.parse().map_err(|_| format_err())?;
+        let khz: u32 = format!("{:0<3}", &fraction[..fraction.len().min(3)])
+            .parse()
+            .map_err(|_| format_err())?;
+        let channel = mhz
+            .checked_mul(1000)
+            .and_then(|mhz| mhz.checked_add(khz))
+            .ok_or_else(format_err)?;
+
+        if !(Self::MIN_KHZ..=Self::MAX_KHZ).contains(&channel) {
+            return Err(FrequencyError::OutOfRange(s.to_string()));
+        }
+        if channel % Self::BLOCK_KHZ % 5 != 0 || channel % Self::BLOCK_KHZ > 15 {
+            return Err(FrequencyError::Channel(s.to_string()));
+        }
+
+        Ok(Self(channel))
+    }
+}
+
+impl TryFrom<String> for Frequency {
+    type Error = FrequencyError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Frequency> for String {
+    fn from(frequency: Frequency) -> Self {
+        frequency.to_string()
+    }
+}
+
+impl Display for Frequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:03}", self.0 / 1000, self.0 % 1000)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoiceChannel {
+    pub name: String,
+    pub frequency: Frequency,
+    pub server: Option<String>,
+    pub channel: Option<String>,
+}
+
+impl VoiceChannel {
+    pub(super) fn from_euroscope(
+        voice_channels: Vec<voice_channels::VoiceChannel>,
+    ) -> Vec<VoiceChannel> {
+        voice_channels
+            .into_iter()
+            .filter_map(|channel| match channel.frequency.parse() {
+                Ok(frequency) => Some(VoiceChannel {
+                    name: channel.name,
+                    frequency,
+                    server: channel.server,
+                    channel: channel.channel,
+                }),
+                Err(e) => {
+                    warn!("Voice channel {}: {e}", channel.name);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChannelSpacing, Frequency, FrequencyError};
+
+    #[test]
+    fn test_frequency_normalisation() {
+        let frequency: Frequency = "122.8".parse().unwrap();
+        assert_eq!(frequency, "122.800".parse().unwrap());
+        assert_eq!(frequency, "122.80".parse().unwrap());
+        assert_eq!(frequency.to_string(), "122.800");
+        assert_eq!(frequency.spacing(), ChannelSpacing::Khz25);
+        assert_eq!(
+            serde_json::to_string(&frequency).unwrap(),
+            "\"122.800\"".to_string()
+        );
+        assert_eq!(
+            serde_json::from_str::<Frequency>("\"122.8\"").unwrap(),
+            frequency
+        );
+    }
+
+    #[test]
+    fn test_frequency_channels() {
+        let frequency: Frequency = "132.235".parse().unwrap();
+        assert_eq!(frequency.spacing(), ChannelSpacing::Khz8_33);
+        assert!((frequency.carrier_khz() - 132_233.333_333).abs() < 0.001);
+        let frequency: Frequency = "118.005".parse().unwrap();
+        assert!((frequency.carrier_khz() - 118_000.0).abs() < f64::EPSILON);
+        let frequency: Frequency = "136.990".parse().unwrap();
+        assert!((frequency.carrier_khz() - 136_991.666_667).abs() < 0.001);
+
+        assert_eq!(
+            "132.220".parse::<Frequency>(),
+            Err(FrequencyError::Channel("132.220".to_string()))
+        );
+        assert_eq!(
+            "132.233".parse::<Frequency>(),
+            Err(FrequencyError::Channel("132.233".to_string()))
+        );
+        assert_eq!(
+            "199.998".parse::<Frequency>(),
+            Err(FrequencyError::OutOfRange("199.998".to_string()))
+        );
+        assert_eq!(
+            "122.8001".parse::<Frequency>(),
+            Err(FrequencyError::Format("122.8001".to_string()))
+        );
+        assert_eq!(
+            "".parse::<Frequency>(),
+            Err(FrequencyError::Format(String::new()))
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+
+use geo::{Closest, ClosestPoint as _, Distance as _, Geodesic, Line, LineString, Point};
+use itertools::Itertools as _;
+use serde::{Deserialize, Serialize};
+use uom::si::f64::Length;
+use uom::si::length::meter;
+
+use crate::adaptation::lazy_index::LazyIndex;
+use crate::adaptation::locations::approach::TurnDirection;
+use crate::adaptation::shortest_path::shortest_path;
+use crate::adaptation::Quantize as _;
+use crate::asr::AsrMapGroundNetworkType;
+use crate::ground::GroundNetwork;
+
+/// in metres, stands and holding points further from any taxiway stay unconnected
+const MAX_ATTACH_DISTANCE: f64 = 300.0;
+/// in metres, taxiway ends closer to another taxiway join it
+const JUNCTION_TOLERANCE: f64 = 1.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stand {
+    pub airport: String,
+    pub name: String,
+    pub coordinate: Point,
+    /// in metres
+    pub radius: Option<u32>,
+}
+
+/// Runway holding point
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HoldingPoint {
+    pub airport: String,
+    pub runway: String,
+    pub name: String,
+    pub coordinate: Point,
+}
+
+/// Taxiway, terminal taxiway or runway exit
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Taxiway {
+    /// airport of the closest stand or holding point of its ground network, as
+    /// EuroScope taxiway lines do not name one
+    pub airport: String,
+    pub kind: AsrMapGroundNetworkType,
+    pub name: String,
+    /// runway of exits
+    pub runway: Option<String>,
+    /// turn off direction of exits
+    pub direction: Option<TurnDirection>,
+    /// in knots
+    pub max_speed: Option<u32>,
+    pub points: LineString,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TaxiRoute {
+    /// names of the taxiways taxied along in order
+    pub taxiways: Vec<String>,
+    pub path: LineString,
+    pub distance: Length,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TaxiEdge {
+    to: usize,
+    /// index into `GroundLayout::taxiways`, `None` for connections of stands and
+    /// holding points to the network
+    taxiway: Option<usize>,
+    distance: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct TaxiNode {
+    position: Point,
+    edges: Vec<TaxiEdge>,
+}
+
+/// Taxiway segment between two nodes
+#[derive(Clone, Copy, Debug)]
+struct TaxiSegment {
+    from: usize,
+    to: usize,
+    /// index into `GroundLayout::taxiways`
+    taxiway: usize,
+}
+
+/// Taxiways of one airport joined where they share points or where one ends on
+/// another, with stands and holding points connected to the closest point of a
+/// taxiway within `MAX_ATTACH_DISTANCE`
+#[derive(Clone, Debug, Default)]
+struct TaxiGraph {
+    nodes: Vec<TaxiNode>,
+    segments: Vec<TaxiSegment>,
+    stands: HashMap<String, usize>,
+    holding_points: HashMap<String, usize>,
+}
+
+/// Closest point to `point` on the segment between `from` and `to`
+fn closest_on_segment(from: Point, to: Point, point: Point) -> Option<Point> {
+    match Line::new(from.0, to.0).closest_point(&point) {
+        Closest::Intersection(closest) | Closest::SinglePoint(closest) => Some(closest),
+        Closest::Indeterminate => None,
+    }
+}
+
+impl TaxiGraph {
+    fn node(&mut self, positions: &mut HashMap<(i64, i64), usize>, position: Point) -> usize {
+        *positions.entry(position.quantize()).or_insert_with(|| {
+            self.nodes.push(TaxiNode {
+                position,
+                edges: vec![],
+            });
+            self.nodes.len() - 1
+        })
+    }
+
+    fn connect(&mut self, from: usize, to: usize, taxiway: Option<usize>) {
+        let distance = Geodesic.distance(self.nodes[from].position, self.nodes[to].position);
+        self.nodes[from].edges.push(TaxiEdge {
+            to,
+            taxiway,
+            distance,
+        });
+        self.nodes[to].edges.push(TaxiEdge {
+            to: from,
+            taxiway,
+            distance,
+        });
+    }
+
+    fn add_segment(&mut self, from: usize, to: usize, taxiway: usize) {
+        if from != to {
+            self.connect(from, to, Some(taxiway));
+            self.segments.push(TaxiSegment { from, to, taxiway });
+        }
+    }
+
+    /// Splits `segment` at `position`, returning the node there
+    fn split(
+        &mut self,
+        positions: &mut HashMap<(i64, i64), usize>,
+        segment: usize,
+        position: Point,
+    ) -> usize {
+        let TaxiSegment { from, to, taxiway } = self.segments[segment];
+        let node = self.node(positions, position);
+        if node != from && node != to {
+            self.nodes[from]
+                .edges
+                .retain(|edge| edge.to != to || edge.taxiway != Some(taxiway));
+            self.nodes[to]
+                .edges
+                .retain(|edge| edge.to != from || edge.taxiway != Some(taxiway));
+            self.segments.swap_remove(segment);
+            self.add_segment(from, node, taxiway);
+            self.add_segment(node, to, taxiway);
+        }
+        node
+    }
+
+    /// Joins the taxiway end at `node` to the segments it lies on
+    fn join(&mut self, positions: &mut HashMap<(i64, i64), usize>, node: usize) {
+        let position = self.nodes[node].position;
+        while let Some(segment) = self.segments.iter().position(|segment| {
+            segment.from != node
+                && segment.to != node
+                && closest_on_segment(
+                    self.nodes[segment.from].position,
+                    self.nodes[segment.to].position,
+                    position,
+                )
+                .is_some_and(|closest| Geodesic.distance(position, closest) < JUNCTION_TOLERANCE)
+        }) {
+            self.split(positions, segment, position);
+        }
+    }
+
+    /// Adds a node at `position` connected to the closest point of a taxiway, if
+    /// within `MAX_ATTACH_DISTANCE`
+    fn attach(&mut self, positions: &mut HashMap<(i64, i64), usize>, position: Point) -> usize {
+        let closest = self
+            .segments
+            .iter()
+            .enumerate()
+            .filter_map(|(index, segment)| {
+                let closest = closest_on_segment(
+                    self.nodes[segment.from].position,
+                    self.nodes[segment.to].position,
+                    position,
+                )?;
+                Some((index, closest, Geodesic.distance(position, closest)))
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+            .filter(|(_, _, distance)| *distance <= MAX_ATTACH_DISTANCE);
+
+        let node = self.nodes.len();
+        self.nodes.push(TaxiNode {
+            position,
+            edges: vec![],
+        });
+        if let Some((segment, closest, _)) = closest {
+            let junction = self.split(positions, segment, closest);
+            self.connect(node, junction, None);
+        }
+        node
+    }
+
+    fn build(layout: &GroundLayout, airport: &str) -> Self {
+        let mut graph = Self::default();
+        let mut positions = HashMap::new();
+        let mut ends = vec![];
+        for (index, taxiway) in layout.taxiways.iter().enumerate() {
+            if taxiway.airport != airport {
+                continue;
+            }
+            let nodes = taxiway
+                .points
+                .points()
+                .map(|position| graph.node(&mut positions, position))
+                .collect::<Vec<_>>();
+            ends.extend(nodes.first().into_iter().chain(nodes.last()));
+            for (from, to) in nodes.into_iter().tuple_windows() {
+                graph.add_segment(from, to, index);
+            }
+        }
+        for node in ends {
+            graph.join(&mut positions, node);
+        }
+
+        for stand in layout
+            .stands
+            .iter()
+            .filter(|stand| stand.airport == airport)
+        {
+            let node = graph.attach(&mut positions, stand.coordinate);
+            graph.stands.insert(stand.name.clone(), node);
+        }
+        for holding_point in layout
+            .holding_points
+            .iter()
+            .filter(|holding_point| holding_point.airport == airport)
+        {
+            let node = graph.attach(&mut positions, holding_point.coordinate);
+            graph
+                .holding_points
+                .insert(holding_point.name.clone(), node);
+        }
+
+        graph
+    }
+
+    /// Shortest path from `start` to `end` by A*, as each node after `start` with the
+    /// edge taken to reach it
+    fn shortest_path(&self, start: usize, end: usize) -> Option<Vec<(usize, TaxiEdge)>> {
+        let end_position = self.nodes[end].position;
+        let remaining = |node: usize| Geodesic.distance(self.nodes[node].position, end_position);
+        let neighbours = |node: usize| {
+            self.nodes[node]
+                .edges
+                .iter()
+                .map(|edge| (edge.to, *edge, edge.distance))
+        };
+
+        shortest_path(start, end, neighbours, remaining)
+    }
+}
+
+/// Taxi graphs of a `GroundLayout` by airport.
+///
+/// A `LazyIndex`, call `GroundLayout::rebuild_graph` after mutating the layout.
+#[derive(Clone, Debug, Default)]
+pub struct TaxiGraphIndex(LazyIndex<HashMap<String, TaxiGraph>>);
+
+/// Stands, taxiways and holding points of all airports
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GroundLayout {
+    pub stands: Vec<Stand>,
+    pub taxiways: Vec<Taxiway>,
+    pub holding_points: Vec<HoldingPoint>,
+    /// Not part of the actual data and excluded from serde.
+    #[serde(skip)]
+    pub graph: TaxiGraphIndex,
+}
+
+impl GroundLayout {
+    pub fn from_networks(networks: impl IntoIterator<Item = GroundNetwork>) -> Self {
+        networks
+            .into_iter()
+            .fold(Self::default(), |mut layout, network| {
+                layout.stands.extend(network.stands);
+                layout.taxiways.extend(network.taxiways);
+                layout.holding_points.extend(network.holding_points);
+                layout
+            })
+    }
+
+    fn build_graphs(&self) -> HashMap<String, TaxiGraph> {
+        self.taxiways
+            .iter()
+            .map(|taxiway| &taxiway.airport)
+            .chain(self.stands.iter().map(|stand| &stand.airport))
+            .chain(self.holding_points.iter().map(|hp| &hp.airport))
+            .unique()
+            .map(|airport| (airport.clone(), TaxiGraph::build(self, airport)))
+            .collect()
+    }
+
+    fn graph(&self, airport: &str) -> Option<&TaxiGraph> {
+        self.graph
+            .0
+            .get_or_build(|| self.build_graphs())
+            .get(airport)
+    }
+
+    /// Rebuilds the taxi graphs, only needed after mutating the layout once it has
+    /// been routed on.
+    pub fn rebuild_graph(&mut self) {
+        let graphs = self.build_graphs();
+        self.graph.0.rebuild(graphs);
+    }
+
+    /// Holding points of `runway` at `airport`
+    pub fn holding_points_of(&self, airport: &str, runway: &str) -> Vec<&HoldingPoint> {
+        self.holding_points
+            .iter()
+            .filter(|hp| hp.airport == airport && hp.runway == runway)
+            .collect()
+    }
+
+    /// Shortest taxi route from `stand` to the holding point `holding_point` at
+    /// `airport`. `None` if either is unknown or they are not connected.
+    pub fn taxi_route(&self, airport: &str, stand: &str, holding_point: &str) -> Option<TaxiRoute> {
+        let graph = self.graph(airport)?;
+        let start = graph.stands.get(stand)?;
+        let end = graph.holding_points.get(holding_point)?;
+        let path = graph.shortest_path(*start, *end)?;
+
+        Some(TaxiRoute {
+            taxiways: path
+                .iter()
+                .filter_map(|(_, edge)| edge.taxiway)
+                .dedup()
+                .map(|taxiway| self.taxiways[taxiway].name.clone())
+                .dedup()
+                .collect(),
+            distance: Length::new::<meter>(path.iter().map(|(_, edge)| edge.distance).sum()),
+            path: std::iter::once(*start)
+                .chain(path.iter().map(|(node, _)| *node))
+                .map(|node| graph.nodes[node].position)
+                .collect(),
+        })
+    }
+
+    /// Shortest taxi route from `stand` to any holding point of `runway`
+    pub fn taxi_route_to_runway(
+        &self,
+        airport: &str,
+        stand: &str,
+        runway: &str,
+    ) -> Option<(&HoldingPoint, TaxiRoute)> {
+        self.holding_points_of(airport, runway)
+            .into_iter()
+            .filter_map(|hp| Some((hp, self.taxi_route(airport, stand, &hp.name)?)))
+            .min_by(|(_, a), (_, b)| {
+                a.distance
+                    .get::<meter>()
+                    .total_cmp(&b.distance.get::<meter>())
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uom::si::length::meter;
+
+    use crate::ground::parse_ground_network;
+
+    use super::GroundLayout;
+
+    #[test]
+    fn test_taxi_route() {
+        // A runs north from the stands, K east, with both B1 and B2 leading from K to
+        // holding points of 26R, B2 via the shorter way
+        let layout = GroundLayout::from_networks([parse_ground_network(
+            b"STAND:EDDM:101:N048.21.00.000:E011.47.00.000
+STAND:EDDM:102:N048.21.25.000:E011.48.00.000
+TAXI:A:
+COORD:N048.21.00.000:E011.47.05.000
+COORD:N048.21.30.000:E011.47.05.000
+COORD:N048.22.00.000:E011.47.05.000
+TAXI:K:
+COORD:N048.21.30.000:E011.47.05.000
+COORD:N048.21.30.000:E011.48.05.000
+TAXI:B1:
+COORD:N048.22.00.000:E011.47.05.000
+COORD:N048.22.10.000:E011.47.05.000
+TAXI:B2:
+COORD:N048.21.30.000:E011.48.05.000
+COORD:N048.21.40.000:E011.48.05.000
+HOLD:EDDM:26R:B1:N048.22.10.000:E011.47.06.000
+HOLD:EDDM:26R:B2:N048.21.40.000:E011.48.06.000
+HOLD:EDDM:08L:Z1:N048.30.00.000:E011.30.00.000
+",
+        )
+        .unwrap()]);
+
+        let route = layout.taxi_route("EDDM", "101", "B1").unwrap();
+        assert_eq!(route.taxiways, ["A", "B1"]);
+        assert_eq!(route.path.0.len(), 6);
+
+        let route = layout.taxi_route("EDDM", "101", "B2").unwrap();
+        assert_eq!(route.taxiways, ["A", "K", "B2"]);
+
+        let (holding_point, route) = layout.taxi_route_to_runway("EDDM", "102", "26R").unwrap();
+        // 102 is next to K, close to where B2 leaves it
+        assert_eq!(holding_point.name, "B2");
+        assert_eq!(route.taxiways, ["K", "B2"]);
+        assert!(route.distance.get::<meter>() > 0.0);
+
+        assert!(layout.taxi_route("EDDM", "999", "B1").is_none());
+        assert!(layout.taxi_route_to_runway("EDDM", "101", "08R").is_none());
+    }
+
+    #[test]
+    fn test_taxi_route_junctions_and_airports() {
+        // K ends halfway along A without A having a point there
+        let munich = parse_ground_network(
+            b"STAND:EDDM:101:N048.21.00.000:E011.47.00.000
+TAXI:A:
+COORD:N048.21.00.000:E011.47.05.000
+COORD:N048.22.00.000:E011.47.05.000
+TAXI:K:
+COORD:N048.21.30.000:E011.47.05.000
+COORD:N048.21.30.000:E011.48.05.000
+HOLD:EDDM:26R:K1:N048.21.30.000:E011.48.06.000
+HOLD:EDDM:08L:Z1:N048.30.00.000:E011.30.00.000
+",
+        )
+        .unwrap();
+        // V1 lies next to A at EDDM, but belongs to EDDF
+        let frankfurt = parse_ground_network(
+            b"STAND:EDDF:V1:N048.21.45.000:E011.47.06.000
+TAXI:L:
+COORD:N050.02.00.000:E008.33.00.000
+COORD:N050.02.30.000:E008.33.00.000
+HOLD:EDDF:25C:L1:N050.02.30.000:E008.33.01.000
+",
+        )
+        .unwrap();
+        let layout = GroundLayout::from_networks([munich, frankfurt]);
+
+        let route = layout.taxi_route("EDDM", "101", "K1").unwrap();
+        assert_eq!(route.taxiways, ["A", "K"]);
+
+        // too far from any taxiway
+        assert!(layout.taxi_route("EDDM", "101", "Z1").is_none());
+        // not attached to taxiways of other airports
+        assert!(layout.taxi_route("EDDF", "V1", "L1").is_none());
+        assert!(layout.taxi_route("EDDM", "V1", "K1").is_none());
+    }
+}
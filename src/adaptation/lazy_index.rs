@@ -0,0 +1,37 @@
+use std::sync::OnceLock;
+
+/// Index derived from adaptation data, i.e. a spatial tree, built lazily on the first
+/// query and kept by clones.
+///
+/// If the data is mutated *after* the index has already been queried once, the cached
+/// index goes stale, its owner offers a `rebuild_*` method to call after such a
+/// mutation.
+#[derive(Debug)]
+pub struct LazyIndex<T>(OnceLock<T>);
+
+impl<T> Default for LazyIndex<T> {
+    fn default() -> Self {
+        Self(OnceLock::new())
+    }
+}
+
+impl<T: Clone> Clone for LazyIndex<T> {
+    fn clone(&self) -> Self {
+        let cell = OnceLock::new();
+        if let Some(index) = self.0.get() {
+            cell.set(index.clone()).ok();
+        }
+        Self(cell)
+    }
+}
+
+impl<T> LazyIndex<T> {
+    pub(crate) fn get_or_build(&self, build: impl FnOnce() -> T) -> &T {
+        self.0.get_or_init(build)
+    }
+
+    /// Forces a rebuild, overwriting any already-cached index.
+    pub(crate) fn rebuild(&mut self, index: T) {
+        self.0 = OnceLock::from(index);
+    }
+}
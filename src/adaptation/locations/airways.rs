@@ -1,13 +1,15 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::{Debug, Display},
     hash::Hash,
     sync::Arc,
 };
 
-use serde::{Deserialize, Serialize};
+use geo::{Distance as _, Haversine};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::adaptation::locations::Locations;
+use crate::adaptation::shortest_path::shortest_path;
 
 use super::{Fix, GraphPosition};
 
@@ -24,6 +26,50 @@ pub struct AirwayFix {
     pub fix: Fix,
     pub valid_direction: bool,
     pub minimum_level: Option<u32>,
+    /// only set via overlays, airway.txt carries no maximum levels
+    pub maximum_level: Option<u32>,
+}
+
+/// Segment between two neighbouring fixes of an airway
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AirwaySegment {
+    pub from: Fix,
+    pub to: Fix,
+    pub airway_type: AirwayType,
+    pub minimum_level: Option<u32>,
+    /// only set via overlays, airway.txt carries no maximum levels
+    pub maximum_level: Option<u32>,
+    /// usable from `from` to `to`
+    pub valid_direction: bool,
+    /// usable from `to` to `from`
+    pub valid_reverse_direction: bool,
+}
+
+/// One leg of a route through the airway network
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct AirwayLeg {
+    pub airway: String,
+    /// fix at the end of the leg
+    pub fix: AirwayFix,
+}
+
+/// Restrictions for `AirwayGraph::route`, levels are in feet like `minimum_level`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoutingConstraints {
+    /// lowest level only using high airways, below it only low airways are used
+    pub high_airway_level: u32,
+    pub avoid_airways: HashSet<String>,
+    pub avoid_fixes: HashSet<String>,
+}
+
+impl Default for RoutingConstraints {
+    fn default() -> Self {
+        Self {
+            high_airway_level: 24_500,
+            avoid_airways: HashSet::new(),
+            avoid_fixes: HashSet::new(),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
@@ -41,6 +87,19 @@ impl PartialEq for AirwayEdge {
     }
 }
 
+impl AirwayEdge {
+    fn is_usable(&self, level: u32, constraints: &RoutingConstraints) -> bool {
+        self.valid_direction
+            && self.minimum_level.is_none_or(|minimum| level >= minimum)
+            && self.maximum_level.is_none_or(|maximum| level <= maximum)
+            && match self.airway_type {
+                AirwayType::High => level >= constraints.high_airway_level,
+                AirwayType::Low => level < constraints.high_airway_level,
+                AirwayType::Both | AirwayType::Unknown => true,
+            }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 struct AirwayId(usize);
 
@@ -50,9 +109,13 @@ impl Display for AirwayId {
     }
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 struct FixId(usize);
 
+fn on_airway(fixes_by_airway: &HashMap<AirwayId, Vec<FixId>>, airway: AirwayId) -> &[FixId] {
+    fixes_by_airway.get(&airway).map_or(&[], Vec::as_slice)
+}
+
 impl Display for FixId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -61,8 +124,9 @@ impl Display for FixId {
 
 pub type SharedStr = Arc<str>;
 
-// FIXME serialise to a human-overridable
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+/// Airway network, (de)serialised as the ordered segments of each airway by airway
+/// name so overlays can add, remove or reroute airways.
+#[derive(Clone, Debug, Default)]
 pub struct AirwayGraph {
     fixes: Vec<GraphFix>,
     fix_id_by_name: HashMap<SharedStr, Vec<FixId>>,
@@ -129,9 +193,9 @@ impl AirwayGraph {
             return Some(
                 expanded_fixes
                     .iter()
-                    .map(|(fix, valid_direction, minimum_level)| {
-                        let designator = self.fix_name_by_id[fix.0].clone();
-                        let coordinate = self.fixes[fix.0].position;
+                    .map(|edge| {
+                        let designator = self.fix_name_by_id[edge.to.0].clone();
+                        let coordinate = self.fixes[edge.to.0].position;
                         let is_internal = locations.contains_nav_element(&designator, coordinate);
 
                         let af = AirwayFix {
@@ -139,8 +203,9 @@ impl AirwayGraph {
                                 designator: designator.to_string(),
                                 coordinate: coordinate.0,
                             },
-                            valid_direction: *valid_direction,
-                            minimum_level: *minimum_level,
+                            valid_direction: edge.valid_direction,
+                            minimum_level: edge.minimum_level,
+                            maximum_level: edge.maximum_level,
                         };
                         (af, is_internal)
                     })
@@ -150,6 +215,217 @@ impl AirwayGraph {
         None
     }
 
+    /// Shortest route from `from` to `to` along airways usable at `level`, by
+    /// great-circle distance. `None` if the fixes are not connected.
+    pub fn route(
+        &self,
+        from: &Fix,
+        to: &Fix,
+        level: u32,
+        constraints: &RoutingConstraints,
+    ) -> Option<Vec<AirwayLeg>> {
+        let start = *self.find_fix_id(from)?;
+        let end = *self.find_fix_id(to)?;
+        let end_position = self.fixes[end.0].position.0;
+        let distance_to_end =
+            |fix: FixId| Haversine.distance(self.fixes[fix.0].position.0, end_position);
+        let avoided_airways: HashSet<AirwayId> = constraints
+            .avoid_airways
+            .iter()
+            .filter_map(|name| self.get_airway_id(name))
+            .collect();
+
+        let neighbours = |current: FixId| {
+            let position = self.fixes[current.0].position.0;
+            self.fixes[current.0]
+                .edges
+                .iter()
+                .filter(|(airway, _)| !avoided_airways.contains(*airway))
+                .flat_map(|(airway, edges)| edges.iter().map(move |edge| (*airway, edge)))
+                .filter(move |(_, edge)| {
+                    edge.is_usable(level, constraints)
+                        && (edge.to == end
+                            || !constraints
+                                .avoid_fixes
+                                .contains(&*self.fix_name_by_id[edge.to.0]))
+                })
+                .map(move |(airway, edge)| {
+                    let distance = Haversine.distance(position, self.fixes[edge.to.0].position.0);
+                    (edge.to, (airway, edge), distance)
+                })
+        };
+
+        let path = shortest_path(start, end, neighbours, distance_to_end)?;
+        Some(
+            path.into_iter()
+                .map(|(fix, (airway, edge))| AirwayLeg {
+                    airway: self.airway_name_by_id[airway.0].to_string(),
+                    fix: AirwayFix {
+                        fix: self.fix(fix),
+                        valid_direction: edge.valid_direction,
+                        minimum_level: edge.minimum_level,
+                        maximum_level: edge.maximum_level,
+                    },
+                })
+                .collect(),
+        )
+    }
+
+    /// Names of all airways
+    pub fn airways(&self) -> impl Iterator<Item = &str> {
+        self.airway_name_by_id.iter().map(|name| &**name)
+    }
+
+    /// Fixes of `airway` in order, one list per continuous part of the airway.
+    pub fn airway_fixes(&self, airway: &str) -> Option<Vec<Vec<Fix>>> {
+        let airway = self.get_airway_id(airway)?;
+        let fixes_by_airway = self.fixes_by_airway();
+
+        Some(
+            self.airway_parts(airway, on_airway(&fixes_by_airway, airway))
+                .into_iter()
+                .map(|part| part.into_iter().map(|id| self.fix(id)).collect())
+                .collect(),
+        )
+    }
+
+    /// Every segment of `airway` once, first those along its parts in the order of
+    /// `airway_fixes`, then those left out by the parts, i.e. at junctions or closing
+    /// a circular part.
+    pub fn airway_segments(&self, airway: &str) -> Option<Vec<AirwaySegment>> {
+        let airway = self.get_airway_id(airway)?;
+        let fixes_by_airway = self.fixes_by_airway();
+
+        Some(self.segments_of_airway(airway, on_airway(&fixes_by_airway, airway)))
+    }
+
+    /// Fixes on each airway in one pass, in the order of their ids
+    fn fixes_by_airway(&self) -> HashMap<AirwayId, Vec<FixId>> {
+        let mut fixes_by_airway: HashMap<AirwayId, Vec<FixId>> = HashMap::new();
+        for (id, fix) in self.fixes.iter().enumerate() {
+            for airway in fix.edges.keys() {
+                fixes_by_airway.entry(*airway).or_default().push(FixId(id));
+            }
+        }
+        fixes_by_airway
+    }
+
+    fn segments_of_airway(&self, airway: AirwayId, on_airway: &[FixId]) -> Vec<AirwaySegment> {
+        let undirected = |a: FixId, b: FixId| (a.0.min(b.0), a.0.max(b.0));
+
+        let mut covered = HashSet::new();
+        let mut segments = vec![];
+        for part in self.airway_parts(airway, on_airway) {
+            for pair in part.windows(2) {
+                if covered.insert(undirected(pair[0], pair[1])) {
+                    segments.extend(self.airway_segment(airway, pair[0], pair[1]));
+                }
+            }
+        }
+        for &from in on_airway {
+            let Some(edges) = self.fixes[from.0].edges.get(&airway) else {
+                continue;
+            };
+            for edge in edges {
+                if !covered.insert(undirected(from, edge.to)) {
+                    continue;
+                }
+                // one-way segments from their valid direction
+                let reverse_only = !edge.valid_direction
+                    && self
+                        .airway_segment(airway, edge.to, from)
+                        .is_some_and(|segment| segment.valid_direction);
+                segments.extend(if reverse_only {
+                    self.airway_segment(airway, edge.to, from)
+                } else {
+                    self.airway_segment(airway, from, edge.to)
+                });
+            }
+        }
+
+        segments
+    }
+
+    /// Segment of `airway` between the neighbouring fixes `from` and `to`.
+    pub fn segment(&self, airway: &str, from: &str, to: &str) -> Option<AirwaySegment> {
+        let airway = self.get_airway_id(airway)?;
+
+        self.get_fix_ids(from)?.iter().find_map(|&from| {
+            self.fixes[from.0]
+                .edges
+                .get(&airway)?
+                .iter()
+                .find(|edge| *self.fix_name_by_id[edge.to.0] == *to)
+                .and_then(|edge| self.airway_segment(airway, from, edge.to))
+        })
+    }
+
+    fn fix(&self, id: FixId) -> Fix {
+        Fix {
+            designator: self.fix_name_by_id[id.0].to_string(),
+            coordinate: self.fixes[id.0].position.0,
+        }
+    }
+
+    fn airway_segment(&self, airway: AirwayId, from: FixId, to: FixId) -> Option<AirwaySegment> {
+        let edge = |from: FixId, to: FixId| {
+            self.fixes[from.0]
+                .edges
+                .get(&airway)?
+                .iter()
+                .find(|edge| edge.to == to)
+        };
+        let forward = edge(from, to)?;
+        let reverse = edge(to, from);
+
+        Some(AirwaySegment {
+            from: self.fix(from),
+            to: self.fix(to),
+            airway_type: forward.airway_type,
+            minimum_level: forward.minimum_level,
+            maximum_level: forward.maximum_level,
+            valid_direction: forward.valid_direction,
+            valid_reverse_direction: reverse.is_some_and(|edge| edge.valid_direction),
+        })
+    }
+
+    /// Continuous parts of `airway` with the fixes `on_airway`, each walked from one
+    /// of its ends. Parts are ordered by their first fix to keep the result stable.
+    fn airway_parts(&self, airway: AirwayId, on_airway: &[FixId]) -> Vec<Vec<FixId>> {
+        let neighbours = |id: FixId| {
+            self.fixes[id.0]
+                .edges
+                .get(&airway)
+                .map_or(&[][..], Vec::as_slice)
+        };
+        // ends first, then fixes of circular parts
+        let starts = on_airway
+            .iter()
+            .filter(|&&id| neighbours(id).len() == 1)
+            .chain(on_airway.iter());
+
+        let mut visited = HashSet::new();
+        let mut parts = vec![];
+        for &start in starts {
+            if visited.contains(&start) {
+                continue;
+            }
+            let mut part = vec![];
+            let mut current = Some(start);
+            while let Some(id) = current {
+                visited.insert(id);
+                part.push(id);
+                current = neighbours(id)
+                    .iter()
+                    .map(|edge| edge.to)
+                    .find(|to| !visited.contains(to));
+            }
+            parts.push(part);
+        }
+
+        parts
+    }
+
     pub(crate) fn insert_or_update_segment(
         &mut self,
         airway_name: &str,
@@ -169,7 +445,7 @@ impl AirwayGraph {
             to: to_id,
             valid_direction: segment.valid_direction,
             minimum_level: segment.minimum_level,
-            maximum_level: None,
+            maximum_level: segment.maximum_level,
             airway_type,
         };
 
@@ -179,7 +455,7 @@ impl AirwayGraph {
             to: self_id,
             valid_direction: false,
             minimum_level: segment.minimum_level,
-            maximum_level: None,
+            maximum_level: segment.maximum_level,
             airway_type,
         };
 
@@ -267,15 +543,15 @@ impl AirwayGraph {
         self.fixes[fix.0].edges.contains_key(&airway)
     }
 
-    fn traverse_airway(
-        &self,
+    fn traverse_airway<'a>(
+        &'a self,
         start_fix: FixId,
-        start_edge: &AirwayEdge,
+        start_edge: &'a AirwayEdge,
         end: FixId,
         airway: AirwayId,
-    ) -> Option<Vec<(FixId, bool, Option<u32>)>> {
+    ) -> Option<Vec<&'a AirwayEdge>> {
         let mut prev = start_fix;
-        let mut expanded: Vec<(FixId, bool, Option<u32>)> = vec![];
+        let mut expanded: Vec<&AirwayEdge> = vec![];
 
         let mut edge = start_edge;
         let mut count = 0_usize;
@@ -288,7 +564,7 @@ impl AirwayGraph {
             }
 
             let current = edge.to;
-            expanded.push((edge.to, edge.valid_direction, edge.minimum_level));
+            expanded.push(edge);
 
             if current == end {
                 return Some(expanded);
@@ -308,6 +584,57 @@ impl AirwayGraph {
     }
 }
 
+impl Serialize for AirwayGraph {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let fixes_by_airway = self.fixes_by_airway();
+        serializer.collect_map(self.airways().enumerate().map(|(id, airway)| {
+            let id = AirwayId(id);
+            (
+                airway,
+                self.segments_of_airway(id, on_airway(&fixes_by_airway, id)),
+            )
+        }))
+    }
+}
+
+impl<'de> Deserialize<'de> for AirwayGraph {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let airways = BTreeMap::<String, Vec<AirwaySegment>>::deserialize(deserializer)?;
+
+        Ok(airways
+            .iter()
+            .fold(AirwayGraph::default(), |mut graph, (airway, segments)| {
+                for segment in segments {
+                    let directions = [
+                        (&segment.from, &segment.to, segment.valid_direction),
+                        (&segment.to, &segment.from, segment.valid_reverse_direction),
+                    ];
+                    for (from, to, valid_direction) in directions {
+                        graph.insert_or_update_segment(
+                            airway,
+                            &from.designator,
+                            GraphPosition(from.coordinate),
+                            &AirwayFix {
+                                fix: to.clone(),
+                                valid_direction,
+                                minimum_level: segment.minimum_level,
+                                maximum_level: segment.maximum_level,
+                            },
+                            segment.airway_type,
+                        );
+                    }
+                }
+                graph
+            }))
+    }
+}
+
 impl Display for AirwayType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(match self {
@@ -345,3 +672,265 @@ impl PartialEq<GraphPosition> for GraphFix {
         &self.position == other
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use crate::adaptation::locations::{test_fixtures::fix, Fix, GraphPosition};
+
+    use super::{AirwayFix, AirwayGraph, AirwayType, RoutingConstraints};
+
+    fn insert(
+        graph: &mut AirwayGraph,
+        airway: &str,
+        from: &Fix,
+        to: &Fix,
+        airway_type: AirwayType,
+        minimum_level: Option<u32>,
+        one_way: bool,
+    ) {
+        for (from, to, valid_direction) in [(from, to, true), (to, from, !one_way)] {
+            graph.insert_or_update_segment(
+                airway,
+                &from.designator,
+                GraphPosition(from.coordinate),
+                &AirwayFix {
+                    fix: to.clone(),
+                    valid_direction,
+                    minimum_level,
+                    maximum_level: None,
+                },
+                airway_type,
+            );
+        }
+    }
+
+    fn route_names(
+        graph: &AirwayGraph,
+        from: &Fix,
+        to: &Fix,
+        level: u32,
+        constraints: &RoutingConstraints,
+    ) -> Option<Vec<String>> {
+        graph.route(from, to, level, constraints).map(|legs| {
+            legs.into_iter()
+                .map(|leg| format!("{} {}", leg.airway, leg.fix.fix.designator))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_route() {
+        let alpha = fix("ALPHA", 10.0, 48.0);
+        let bravo = fix("BRAVO", 11.0, 48.0);
+        let charlie = fix("CHARL", 12.0, 48.0);
+        let delta = fix("DELTA", 11.0, 49.0);
+        let echo = fix("ECHOO", 11.0, 47.9);
+        let mut graph = AirwayGraph::default();
+        insert(
+            &mut graph,
+            "L1",
+            &alpha,
+            &bravo,
+            AirwayType::Low,
+            Some(5000),
+            false,
+        );
+        insert(
+            &mut graph,
+            "L1",
+            &bravo,
+            &charlie,
+            AirwayType::Low,
+            Some(5000),
+            false,
+        );
+        insert(
+            &mut graph,
+            "H1",
+            &alpha,
+            &delta,
+            AirwayType::High,
+            None,
+            false,
+        );
+        insert(
+            &mut graph,
+            "H1",
+            &delta,
+            &charlie,
+            AirwayType::High,
+            None,
+            false,
+        );
+        insert(
+            &mut graph,
+            "Q1",
+            &charlie,
+            &echo,
+            AirwayType::Both,
+            None,
+            true,
+        );
+        insert(
+            &mut graph,
+            "Q1",
+            &echo,
+            &alpha,
+            AirwayType::Both,
+            None,
+            true,
+        );
+        let constraints = RoutingConstraints::default();
+
+        assert_eq!(
+            route_names(&graph, &alpha, &charlie, 8000, &constraints),
+            Some(vec!["L1 BRAVO".to_string(), "L1 CHARL".to_string()])
+        );
+        assert_eq!(
+            route_names(&graph, &alpha, &charlie, 4000, &constraints),
+            None
+        );
+        assert_eq!(
+            route_names(&graph, &alpha, &charlie, 30000, &constraints),
+            Some(vec!["H1 DELTA".to_string(), "H1 CHARL".to_string()])
+        );
+
+        let avoid_l1 = RoutingConstraints {
+            avoid_airways: ["L1".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
+        assert_eq!(route_names(&graph, &alpha, &charlie, 8000, &avoid_l1), None);
+        assert_eq!(
+            route_names(&graph, &charlie, &alpha, 8000, &avoid_l1),
+            Some(vec!["Q1 ECHOO".to_string(), "Q1 ALPHA".to_string()])
+        );
+
+        let avoid_bravo = RoutingConstraints {
+            avoid_fixes: ["BRAVO".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            route_names(&graph, &alpha, &charlie, 8000, &avoid_bravo),
+            None
+        );
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let alpha = fix("ALPHA", 10.0, 48.0);
+        let bravo = fix("BRAVO", 11.0, 48.0);
+        let charlie = fix("CHARL", 12.0, 48.0);
+        let mut graph = AirwayGraph::default();
+        insert(
+            &mut graph,
+            "L1",
+            &alpha,
+            &bravo,
+            AirwayType::Low,
+            Some(5000),
+            false,
+        );
+        insert(
+            &mut graph,
+            "L1",
+            &bravo,
+            &charlie,
+            AirwayType::Low,
+            None,
+            true,
+        );
+        insert(
+            &mut graph,
+            "H1",
+            &alpha,
+            &charlie,
+            AirwayType::High,
+            None,
+            false,
+        );
+
+        let json = serde_json::to_value(&graph).unwrap();
+        assert_eq!(json["L1"][0]["from"]["designator"], "ALPHA");
+        assert_eq!(json["L1"][0]["minimum_level"], 5000);
+        assert_eq!(json["L1"][1]["valid_reverse_direction"], false);
+
+        let deserialised: AirwayGraph = serde_json::from_value(json).unwrap();
+        for airway in ["L1", "H1"] {
+            assert_eq!(
+                deserialised.airway_segments(airway),
+                graph.airway_segments(airway)
+            );
+        }
+        assert_eq!(
+            deserialised.route(&charlie, &alpha, 8000, &RoutingConstraints::default()),
+            None
+        );
+    }
+    /// undirected segments with their direction flags from the alphabetically first fix
+    fn segment_set(graph: &AirwayGraph, airway: &str) -> BTreeSet<(String, String, bool, bool)> {
+        graph
+            .airway_segments(airway)
+            .unwrap()
+            .into_iter()
+            .map(|segment| {
+                let (from, to) = (segment.from.designator, segment.to.designator);
+                if from < to {
+                    (
+                        from,
+                        to,
+                        segment.valid_direction,
+                        segment.valid_reverse_direction,
+                    )
+                } else {
+                    (
+                        to,
+                        from,
+                        segment.valid_reverse_direction,
+                        segment.valid_direction,
+                    )
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_serde_round_trip_junction_and_ring() {
+        let alpha = fix("ALPHA", 10.0, 48.0);
+        let bravo = fix("BRAVO", 11.0, 48.0);
+        let charlie = fix("CHARL", 12.0, 48.0);
+        let delta = fix("DELTA", 11.0, 49.0);
+        let mut graph = AirwayGraph::default();
+        // Y-shaped with a one-way branch from DELTA to BRAVO
+        for (from, to, one_way) in [
+            (&alpha, &bravo, false),
+            (&bravo, &charlie, false),
+            (&delta, &bravo, true),
+        ] {
+            insert(&mut graph, "Y1", from, to, AirwayType::Both, None, one_way);
+        }
+        // ring
+        for (from, to) in [(&alpha, &delta), (&delta, &charlie), (&charlie, &alpha)] {
+            insert(&mut graph, "R1", from, to, AirwayType::Both, None, false);
+        }
+
+        assert_eq!(segment_set(&graph, "Y1").len(), 3);
+        assert_eq!(segment_set(&graph, "R1").len(), 3);
+        assert!(segment_set(&graph, "Y1").contains(&(
+            "BRAVO".to_string(),
+            "DELTA".to_string(),
+            false,
+            true
+        )));
+
+        let deserialised: AirwayGraph =
+            serde_json::from_value(serde_json::to_value(&graph).unwrap()).unwrap();
+        for airway in ["Y1", "R1"] {
+            assert_eq!(
+                segment_set(&deserialised, airway),
+                segment_set(&graph, airway)
+            );
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use geo::{Destination as _, Geodesic, LineString, Point};
+use multimap::MultiMap;
+use serde::{Deserialize, Serialize};
+use uom::si::f64::Length;
+use uom::si::length::{meter, nautical_mile};
+
+use super::magnetic::MagneticVariation;
+use super::{Fix, Locations};
+use crate::adaptation::maps::active::RunwayIdentifier;
+use crate::ese;
+
+/// ground speed holds are drawn with, 1 minute legs are 3 NM
+const HOLD_SPEED_KT: f64 = 180.0;
+/// step of the arcs of drawn holds in degrees
+const HOLD_ARC_STEP: f64 = 15.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ApproachType {
+    Ils,
+    Loc,
+    Rnp,
+    Vor,
+    Ndb,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ApproachFixRole {
+    /// initial approach fix
+    Iaf,
+    /// intermediate fix
+    If,
+    /// final approach fix
+    Faf,
+    /// missed approach point
+    Mapt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TurnDirection {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ApproachFix {
+    pub fix: Fix,
+    pub role: Option<ApproachFixRole>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hold {
+    pub fix: Fix,
+    /// magnetic
+    pub inbound_course: u32,
+    pub turn: TurnDirection,
+    pub leg_seconds: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Approach {
+    pub name: String,
+    pub airport: String,
+    pub runway: String,
+    pub approach_type: ApproachType,
+    /// in degrees, for ILS and RNP approaches with vertical guidance
+    pub glide_slope: Option<f64>,
+    pub waypoints: Vec<ApproachFix>,
+    /// from the missed approach point on
+    pub missed_approach: Vec<Fix>,
+    pub hold: Option<Hold>,
+}
+
+/// Geometry for drawing an `Approach`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApproachGeometry {
+    pub approach: LineString,
+    /// starts at the last waypoint of the approach
+    pub missed_approach: LineString,
+    /// closed racetrack
+    pub hold: Option<LineString>,
+}
+
+impl Hold {
+    /// Racetrack of the hold at `HOLD_SPEED_KT` with rate one turns
+    pub fn racetrack(&self, magnetic_variation: &MagneticVariation) -> LineString {
+        let inbound =
+            magnetic_variation.to_true(f64::from(self.inbound_course), self.fix.coordinate);
+        let side = match self.turn {
+            TurnDirection::Right => 1.0,
+            TurnDirection::Left => -1.0,
+        };
+        let leg =
+            Length::new::<nautical_mile>(HOLD_SPEED_KT * f64::from(self.leg_seconds) / 3600.0)
+                .get::<meter>();
+        // a rate one turn takes 2 minutes for the full circle
+        let radius = Length::new::<nautical_mile>(HOLD_SPEED_KT * 2.0 / 60.0).get::<meter>()
+            / std::f64::consts::TAU;
+
+        let outbound_end = Geodesic.destination(self.fix.coordinate, inbound + 180.0, leg);
+        // both turns are centred on the turn side of the inbound leg
+        let arc = |end_of_inbound_leg: Point, from: f64| {
+            let centre = Geodesic.destination(end_of_inbound_leg, inbound + side * 90.0, radius);
+            (0..=(180.0 / HOLD_ARC_STEP) as usize).map(move |step| {
+                Geodesic.destination(centre, from + side * step as f64 * HOLD_ARC_STEP, radius)
+            })
+        };
+
+        // the fix itself rather than its round trip through the arc centre, to close
+        // the racetrack exactly
+        let mut points = vec![self.fix.coordinate];
+        points.extend(arc(self.fix.coordinate, inbound - side * 90.0).skip(1));
+        points.extend(arc(outbound_end, inbound + side * 90.0));
+        points.push(self.fix.coordinate);
+        LineString::from(points)
+    }
+}
+
+impl Approach {
+    /// First waypoint with `role`
+    pub fn fix(&self, role: ApproachFixRole) -> Option<&Fix> {
+        self.waypoints
+            .iter()
+            .find(|waypoint| waypoint.role == Some(role))
+            .map(|waypoint| &waypoint.fix)
+    }
+
+    pub fn runway_identifier(&self) -> RunwayIdentifier {
+        RunwayIdentifier {
+            icao: self.airport.clone(),
+            designator: self.runway.clone(),
+        }
+    }
+
+    pub fn geometry(&self, magnetic_variation: &MagneticVariation) -> ApproachGeometry {
+        let approach = self
+            .waypoints
+            .iter()
+            .map(|waypoint| waypoint.fix.coordinate)
+            .collect::<LineString>();
+        let missed_approach = approach
+            .0
+            .last()
+            .copied()
+            .into_iter()
+            .chain(self.missed_approach.iter().map(|fix| fix.coordinate.0))
+            .collect::<LineString>();
+
+        ApproachGeometry {
+            approach,
+            missed_approach,
+            hold: self
+                .hold
+                .as_ref()
+                .map(|hold| hold.racetrack(magnetic_variation)),
+        }
+    }
+}
+
+impl Locations {
+    /// All approaches to `runway`
+    pub fn approaches_to(&self, runway: &RunwayIdentifier) -> Vec<&Approach> {
+        self.approaches
+            .get(&runway.icao)
+            .into_iter()
+            .flat_map(MultiMap::flat_iter)
+            .map(|(_, approach)| approach)
+            .filter(|approach| approach.runway == runway.designator)
+            .collect()
+    }
+
+    /// Resolves the waypoints of an .ese approach, each closest to the previous one
+    /// starting at the airport. Unknown waypoints are skipped.
+    pub(super) fn convert_approach(&self, approach: ese::Approach) -> Approach {
+        let context = format!(
+            "approach {} {} {}",
+            approach.airport, approach.runway, approach.name
+        );
+        // resolved in one go to chain the missed approach and hold to the approach
+        let designators: Vec<&str> = approach
+            .waypoints
+            .iter()
+            .map(|(designator, _)| designator.as_str())
+            .chain(approach.missed_approach.iter().map(String::as_str))
+            .chain(approach.hold.iter().map(|hold| hold.fix.as_str()))
+            .collect();
+        let mut resolved = self
+            .resolve_waypoints(&approach.airport, &designators, &context)
+            .into_iter();
+
+        let waypoints = approach
+            .waypoints
+            .iter()
+            .zip(resolved.by_ref())
+            .filter_map(|((_, role), fix)| fix.map(|fix| ApproachFix { fix, role: *role }))
+            .collect();
+        let missed_approach = resolved
+            .by_ref()
+            .take(approach.missed_approach.len())
+            .flatten()
+            .collect();
+        let hold = approach.hold.as_ref().and_then(|hold| {
+            resolved.next().flatten().map(|fix| Hold {
+                fix,
+                inbound_course: hold.inbound_course,
+                turn: hold.turn,
+                // .ese holds are in minutes, possibly fractional
+                leg_seconds: (hold.leg_minutes * 60.0).round() as u32,
+            })
+        });
+
+        Approach {
+            name: approach.name,
+            airport: approach.airport,
+            runway: approach.runway,
+            approach_type: approach.approach_type,
+            glide_slope: approach.glide_slope,
+            waypoints,
+            missed_approach,
+            hold,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{point, Distance as _, Geodesic};
+
+    use crate::adaptation::{
+        locations::{
+            magnetic::MagneticVariation,
+            test_fixtures::{fix_entry, locations},
+        },
+        maps::active::RunwayIdentifier,
+    };
+    use crate::ese::{self, Ese};
+
+    use super::{ApproachFixRole, ApproachType, TurnDirection};
+
+    #[test]
+    fn test_approach() {
+        let mut locations = locations([
+            fix_entry("ROKIL", 12.3, 48.4),
+            fix_entry("DM440", 12.1, 48.37),
+            fix_entry("DM430", 11.95, 48.355),
+            fix_entry("MIQ", 11.6, 48.3),
+        ]);
+
+        let ese = Ese::parse(
+            b"[SIDSSTARS]
+APPROACH:EDDM:26R:ILS26R:ILS:3.0:ROKIL/IAF DM440/IF DM430/FAF EDDM26R/MAPT:MIQ:MIQ/83/R/1
+APPROACH:EDDM:26R:RNP26R:RNP::ROKIL/IAF NOWHERE DM430/FAF EDDM26R/MAPT:MIQ:
+",
+        )
+        .unwrap();
+        let approaches = ese
+            .sids_stars
+            .into_iter()
+            .map(|approach| match approach {
+                ese::SidStar::Approach(approach) => locations.convert_approach(approach),
+                sid_star => panic!("{sid_star:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        let ils = &approaches[0];
+        assert_eq!(ils.approach_type, ApproachType::Ils);
+        assert_eq!(ils.glide_slope, Some(3.0));
+        assert_eq!(
+            ils.fix(ApproachFixRole::Faf)
+                .map(|fix| fix.designator.as_str()),
+            Some("DM430")
+        );
+        assert_eq!(
+            ils.fix(ApproachFixRole::Mapt).map(|fix| fix.coordinate),
+            Some(point! { x: 11.8, y: 48.37 })
+        );
+        assert_eq!(
+            ils.runway_identifier(),
+            RunwayIdentifier {
+                icao: "EDDM".to_string(),
+                designator: "26R".to_string(),
+            }
+        );
+        let hold = ils.hold.as_ref().unwrap();
+        assert_eq!(hold.turn, TurnDirection::Right);
+
+        // unknown waypoints are skipped
+        let rnp = &approaches[1];
+        assert_eq!(rnp.waypoints.len(), 3);
+        assert!(rnp.glide_slope.is_none() && rnp.hold.is_none());
+
+        let geometry = ils.geometry(&MagneticVariation::default());
+        assert_eq!(geometry.approach.0.len(), 4);
+        assert_eq!(
+            geometry.missed_approach.points().collect::<Vec<_>>(),
+            [point! { x: 11.8, y: 48.37 }, point! { x: 11.6, y: 48.3 }]
+        );
+        // 1 minute legs at 180 kt, 2 rate one turns of about 1.9 NM diameter
+        let racetrack = geometry.hold.unwrap();
+        assert_eq!(racetrack.0.first(), racetrack.0.last());
+        let farthest = racetrack
+            .points()
+            .map(|point| Geodesic.distance(hold.fix.coordinate, point))
+            .fold(0.0, f64::max);
+        assert_eq!((farthest / 1852.0).round() as i32, 4);
+        // right turns with the inbound course 083 keep the racetrack south of the fix
+        assert!(racetrack
+            .points()
+            .all(|point| point.y() <= hold.fix.coordinate.y() + 1e-9));
+
+        for approach in approaches {
+            locations
+                .approaches
+                .entry(approach.airport.clone())
+                .or_default()
+                .insert(approach.name.clone(), approach);
+        }
+        let runway = |designator: &str| RunwayIdentifier {
+            icao: "EDDM".to_string(),
+            designator: designator.to_string(),
+        };
+        assert_eq!(locations.approaches_to(&runway("26R")).len(), 2);
+        assert!(locations.approaches_to(&runway("08L")).is_empty());
+    }
+}
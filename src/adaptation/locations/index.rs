@@ -0,0 +1,220 @@
+use geo::{Distance as _, Geodesic, Point};
+use rstar::{primitives::GeomWithData, RTree, AABB};
+use serde::{Deserialize, Serialize};
+use uom::si::f64::Length;
+use uom::si::length::meter;
+
+use super::Locations;
+use crate::adaptation::lazy_index::LazyIndex;
+
+/// lower bound of the metres per degree of latitude, for converting search radii into
+/// envelopes
+const METERS_PER_DEGREE: f64 = 110_000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LocationKind {
+    Fix,
+    Vor,
+    Ndb,
+    Airport,
+}
+
+/// Point of `Locations` as stored in the `LocationIndex`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexedLocation {
+    pub kind: LocationKind,
+    pub designator: String,
+    pub coordinate: Point,
+}
+
+type Entry = GeomWithData<[f64; 2], IndexedLocation>;
+
+fn build_tree(locations: &Locations) -> RTree<Entry> {
+    let entry = |kind, designator: &String, coordinate: Point| {
+        GeomWithData::new(
+            [coordinate.x(), coordinate.y()],
+            IndexedLocation {
+                kind,
+                designator: designator.clone(),
+                coordinate,
+            },
+        )
+    };
+    let fixes = locations
+        .fixes
+        .flat_iter()
+        .map(|(_, fix)| entry(LocationKind::Fix, &fix.designator, fix.coordinate));
+    let vors = locations
+        .vors
+        .flat_iter()
+        .map(|(_, vor)| entry(LocationKind::Vor, &vor.designator, vor.coordinate));
+    let ndbs = locations
+        .ndbs
+        .flat_iter()
+        .map(|(_, ndb)| entry(LocationKind::Ndb, &ndb.designator, ndb.coordinate));
+    let airports = locations.airports.values().map(|airport| {
+        entry(
+            LocationKind::Airport,
+            &airport.designator,
+            airport.coordinate,
+        )
+    });
+
+    RTree::bulk_load(fixes.chain(vors).chain(ndbs).chain(airports).collect())
+}
+
+/// Spatial index over all fixes, VORs, NDBs and airports of `Locations`.
+///
+/// A `LazyIndex`, call `Locations::rebuild_index` after mutating `Locations`.
+#[derive(Clone, Debug, Default)]
+pub struct LocationIndex(LazyIndex<RTree<Entry>>);
+
+impl LocationIndex {
+    fn get_or_build<'a>(&'a self, locations: &Locations) -> &'a RTree<Entry> {
+        self.0.get_or_build(|| build_tree(locations))
+    }
+
+    /// Forces a rebuild, overwriting any already-cached tree.
+    pub(crate) fn rebuild(&mut self, locations: &Locations) {
+        self.0.rebuild(build_tree(locations));
+    }
+
+    /// The `k` locations closest to `point`, closest first.
+    pub fn nearest<'a>(
+        &'a self,
+        locations: &Locations,
+        point: Point,
+        k: usize,
+    ) -> Vec<&'a IndexedLocation> {
+        // the tree measures in degrees, so the k nearest in degrees only bound the
+        // radius in which the geodesically nearest are found
+        let Some(radius) = self
+            .get_or_build(locations)
+            .nearest_neighbor_iter([point.x(), point.y()])
+            .take(k)
+            .map(|entry| Geodesic.distance(point, entry.data.coordinate))
+            .max_by(f64::total_cmp)
+        else {
+            return vec![];
+        };
+
+        let mut nearest = self.within(locations, point, Length::new::<meter>(radius));
+        nearest.truncate(k);
+        nearest
+    }
+
+    /// All locations within `radius` of `point`, closest first.
+    pub fn within<'a>(
+        &'a self,
+        locations: &Locations,
+        point: Point,
+        radius: Length,
+    ) -> Vec<&'a IndexedLocation> {
+        let radius = radius.get::<meter>();
+        let latitude_delta = radius / METERS_PER_DEGREE;
+        // degrees of longitude are shortest at the poleward edge of the envelope
+        let poleward_latitude = (point.y().abs() + latitude_delta).min(90.0);
+        let longitude_delta =
+            (latitude_delta / poleward_latitude.to_radians().cos().max(1e-6)).min(180.0);
+        let envelope = AABB::from_corners(
+            [point.x() - longitude_delta, point.y() - latitude_delta],
+            [point.x() + longitude_delta, point.y() + latitude_delta],
+        );
+
+        let mut within = self
+            .get_or_build(locations)
+            .locate_in_envelope(envelope)
+            .map(|entry| (Geodesic.distance(point, entry.data.coordinate), &entry.data))
+            .filter(|(distance, _)| *distance <= radius)
+            .collect::<Vec<_>>();
+        within.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        within.into_iter().map(|(_, location)| location).collect()
+    }
+}
+
+impl Locations {
+    /// The `k` fixes, navaids and airports closest to `point`, closest first.
+    pub fn nearest(&self, point: Point, k: usize) -> Vec<&IndexedLocation> {
+        self.index.nearest(self, point, k)
+    }
+
+    /// All fixes, navaids and airports within `radius` of `point`, closest first.
+    pub fn within(&self, point: Point, radius: Length) -> Vec<&IndexedLocation> {
+        self.index.within(self, point, radius)
+    }
+
+    /// Rebuilds the spatial index, only needed after mutating `Locations` once it has
+    /// been queried.
+    pub fn rebuild_index(&mut self) {
+        let mut index = std::mem::take(&mut self.index);
+        index.rebuild(self);
+        self.index = index;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::point;
+    use uom::si::{f64::Length, length::nautical_mile};
+
+    use crate::adaptation::locations::{test_fixtures::fix_entry, Locations, VOR};
+
+    use super::{IndexedLocation, LocationKind};
+
+    fn designators<'a>(locations: &[&'a IndexedLocation]) -> Vec<&'a str> {
+        locations
+            .iter()
+            .map(|location| location.designator.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_location_index() {
+        let locations = Locations {
+            fixes: [
+                fix_entry("ARMUT", 12.32, 49.72),
+                fix_entry("MERSI", 11.9, 48.7),
+                fix_entry("BAMUR", 11.3, 49.1),
+                // far away namesake
+                fix_entry("MERSI", -58.0, 46.3),
+            ]
+            .into_iter()
+            .collect(),
+            vors: [(
+                "OTT".to_string(),
+                VOR {
+                    designator: "OTT".to_string(),
+                    frequency: "112.300".to_string(),
+                    coordinate: point! { x: 11.82, y: 48.18 },
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let munich = point! { x: 11.8, y: 48.4 };
+
+        let nearest = locations.nearest(munich, 2);
+        assert_eq!(designators(&nearest), ["OTT", "MERSI"]);
+        assert_eq!(nearest[0].kind, LocationKind::Vor);
+        assert_eq!(
+            designators(&locations.within(munich, Length::new::<nautical_mile>(60.0))),
+            ["OTT", "MERSI", "BAMUR"]
+        );
+        assert!(locations.nearest(munich, 0).is_empty());
+
+        assert_eq!(
+            locations
+                .resolve_ambiguous("MERSI", point! { x: -50.0, y: 45.0 })
+                .map(|fix| fix.coordinate),
+            Some(point! { x: -58.0, y: 46.3 })
+        );
+        assert_eq!(
+            locations
+                .resolve_ambiguous("MERSI", munich)
+                .map(|fix| fix.coordinate),
+            Some(point! { x: 11.9, y: 48.7 })
+        );
+        assert_eq!(locations.resolve_ambiguous("OZE", munich), None);
+    }
+}
@@ -0,0 +1,257 @@
+use geo::Point;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// WGS84 semi-major axis in km
+const WGS84_A: f64 = 6378.137;
+/// WGS84 flattening
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// geomagnetic reference radius in km
+const REFERENCE_RADIUS: f64 = 6371.2;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MagneticModelError {
+    #[error("missing epoch line")]
+    MissingEpoch,
+    #[error("invalid coefficient line {0}: {1}")]
+    Coefficient(usize, String),
+}
+
+/// Gauss coefficients of degree `n` and order `m` in nT and nT/year
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GaussCoefficient {
+    pub n: usize,
+    pub m: usize,
+    pub g: f64,
+    pub h: f64,
+    pub g_dot: f64,
+    pub h_dot: f64,
+}
+
+/// World Magnetic Model as published by NOAA in WMM.COF files, evaluated offline. No
+/// coefficients are bundled, the WMM.COF is supplied via `magnetic_model` in
+/// adaptation.toml.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldMagneticModel {
+    /// decimal year the coefficients refer to
+    pub epoch: f64,
+    pub coefficients: Vec<GaussCoefficient>,
+}
+
+impl WorldMagneticModel {
+    pub fn parse(cof: &str) -> Result<Self, MagneticModelError> {
+        let mut lines = cof
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let epoch = lines
+            .next()
+            .and_then(|(_, line)| line.split_whitespace().next()?.parse().ok())
+            .ok_or(MagneticModelError::MissingEpoch)?;
+
+        let coefficients = lines
+            .take_while(|(_, line)| !line.trim_start().starts_with("9999"))
+            .map(|(i, line)| {
+                let error = || MagneticModelError::Coefficient(i + 1, line.to_string());
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+                let [n, m, g, h, g_dot, h_dot] = fields[..] else {
+                    return Err(error());
+                };
+                let float = |field: &str| field.parse::<f64>().map_err(|_| error());
+
+                Ok(GaussCoefficient {
+                    n: n.parse().map_err(|_| error())?,
+                    m: m.parse().map_err(|_| error())?,
+                    g: float(g)?,
+                    h: float(h)?,
+                    g_dot: float(g_dot)?,
+                    h_dot: float(h_dot)?,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            epoch,
+            coefficients,
+        })
+    }
+
+    /// Declination in degrees, east positive, at `point` on the WGS84 ellipsoid on the
+    /// decimal year `date`.
+    pub fn declination(&self, point: Point, date: f64) -> f64 {
+        let max_degree = self.coefficients.iter().map(|c| c.n).max().unwrap_or(0);
+        let (latitude, longitude) = (point.y().to_radians(), point.x().to_radians());
+
+        // geodetic to geocentric spherical coordinates
+        let e2 = WGS84_F * (2.0 - WGS84_F);
+        let prime_vertical = WGS84_A / (1.0 - e2 * latitude.sin().powi(2)).sqrt();
+        let equatorial = prime_vertical * latitude.cos();
+        let polar = prime_vertical * (1.0 - e2) * latitude.sin();
+        let radius = equatorial.hypot(polar);
+        let geocentric_latitude = (polar / radius).asin();
+
+        // Schmidt semi-normalised associated Legendre functions of the colatitude
+        // and their derivatives
+        let (cos_theta, sin_theta) = (
+            geocentric_latitude.sin(),
+            geocentric_latitude.cos().max(1e-10),
+        );
+        let size = max_degree + 1;
+        let mut legendre = vec![vec![0.0; size]; size];
+        let mut derivative = vec![vec![0.0; size]; size];
+        legendre[0][0] = 1.0;
+        for n in 1..size {
+            for m in 0..=n {
+                let (nf, mf) = (n as f64, m as f64);
+                if n == m {
+                    let factor = if n == 1 {
+                        1.0
+                    } else {
+                        ((2.0 * nf - 1.0) / (2.0 * nf)).sqrt()
+                    };
+                    legendre[n][n] = factor * sin_theta * legendre[n - 1][n - 1];
+                    derivative[n][n] = factor
+                        * (cos_theta * legendre[n - 1][n - 1]
+                            + sin_theta * derivative[n - 1][n - 1]);
+                } else {
+                    let previous = |table: &Vec<Vec<f64>>| {
+                        if n >= 2 && m <= n - 2 {
+                            ((nf - 1.0).powi(2) - mf * mf).sqrt() * table[n - 2][m]
+                        } else {
+                            0.0
+                        }
+                    };
+                    let norm = (nf * nf - mf * mf).sqrt();
+                    legendre[n][m] = ((2.0 * nf - 1.0) * cos_theta * legendre[n - 1][m]
+                        - previous(&legendre))
+                        / norm;
+                    derivative[n][m] = ((2.0 * nf - 1.0)
+                        * (cos_theta * derivative[n - 1][m] - sin_theta * legendre[n - 1][m])
+                        - previous(&derivative))
+                        / norm;
+                }
+            }
+        }
+
+        let elapsed = date - self.epoch;
+        let (mut north, mut east, mut down) = (0.0, 0.0, 0.0);
+        for c in &self.coefficients {
+            let (g, h) = (c.g + elapsed * c.g_dot, c.h + elapsed * c.h_dot);
+            let scale = (REFERENCE_RADIUS / radius).powf(c.n as f64 + 2.0);
+            let (sin_m, cos_m) = (c.m as f64 * longitude).sin_cos();
+
+            north += scale * (g * cos_m + h * sin_m) * derivative[c.n][c.m];
+            east += scale * c.m as f64 * (g * sin_m - h * cos_m) * legendre[c.n][c.m] / sin_theta;
+            down -= scale * (c.n as f64 + 1.0) * (g * cos_m + h * sin_m) * legendre[c.n][c.m];
+        }
+
+        // rotate back to the geodetic frame, the east component is unaffected
+        let rotation = geocentric_latitude - latitude;
+        let north = north * rotation.cos() - down * rotation.sin();
+
+        east.atan2(north).to_degrees()
+    }
+}
+
+/// Source of the magnetic variation for converting between magnetic and true bearings
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MagneticVariation {
+    /// declination in degrees, east positive
+    Constant(f64),
+    /// model evaluated at the position on the decimal year `date`
+    Model {
+        model: WorldMagneticModel,
+        date: f64,
+    },
+}
+
+impl Default for MagneticVariation {
+    fn default() -> Self {
+        Self::Constant(0.0)
+    }
+}
+
+impl MagneticVariation {
+    /// From the .sct `[INFO]` magnetic variation, which is west positive.
+    pub fn from_sct(magnetic_variation: f64) -> Self {
+        Self::Constant(-magnetic_variation)
+    }
+
+    /// Model evaluated on the decimal year `date`, the model's epoch if `None`. The
+    /// date is fixed so the same inputs always give the same adaptation.
+    pub fn from_model(model: WorldMagneticModel, date: Option<f64>) -> Self {
+        Self::Model {
+            date: date.unwrap_or(model.epoch),
+            model,
+        }
+    }
+
+    /// Declination in degrees at `point`, east positive
+    pub fn declination(&self, point: Point) -> f64 {
+        match self {
+            Self::Constant(declination) => *declination,
+            Self::Model { model, date } => model.declination(point, *date),
+        }
+    }
+
+    /// True bearing of the magnetic `bearing` at `point`
+    pub fn to_true(&self, bearing: f64, point: Point) -> f64 {
+        (bearing + self.declination(point)).rem_euclid(360.0)
+    }
+
+    /// Magnetic bearing of the true `bearing` at `point`
+    pub fn to_magnetic(&self, bearing: f64, point: Point) -> f64 {
+        (bearing - self.declination(point)).rem_euclid(360.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::point;
+
+    use super::{MagneticModelError, MagneticVariation, WorldMagneticModel};
+
+    const DIPOLE_COF: &str = "    2025.0            WMM-TEST     01/01/2025
+  1  0  -29000.0       0.0       10.0        0.0
+  1  1   -1500.0    5000.0        0.0      -20.0
+999999999999999999999999999999999999999999999999
+999999999999999999999999999999999999999999999999
+";
+
+    #[test]
+    fn test_world_magnetic_model() {
+        let model = WorldMagneticModel::parse(DIPOLE_COF).unwrap();
+        assert!((model.epoch - 2025.0).abs() < f64::EPSILON);
+        assert_eq!(model.coefficients.len(), 2);
+
+        // at the equator and prime meridian a dipole gives atan2(-h11, -g10)
+        let expected = (-5000.0_f64).atan2(29_000.0).to_degrees();
+        let declination = model.declination(point! { x: 0.0, y: 0.0 }, 2025.0);
+        assert!((declination - expected).abs() < 1e-9, "{declination}");
+        let later = (-4900.0_f64).atan2(28_950.0).to_degrees();
+        let declination = model.declination(point! { x: 0.0, y: 0.0 }, 2030.0);
+        assert!((declination - later).abs() < 1e-9, "{declination}");
+
+        let at_epoch = MagneticVariation::from_model(model.clone(), None);
+        assert!((at_epoch.declination(point! { x: 0.0, y: 0.0 }) - expected).abs() < 1e-9);
+        let in_2030 = MagneticVariation::from_model(model, Some(2030.0));
+        assert!((in_2030.declination(point! { x: 0.0, y: 0.0 }) - later).abs() < 1e-9);
+
+        assert_eq!(
+            WorldMagneticModel::parse("2025.0\n  1  0  x\n"),
+            Err(MagneticModelError::Coefficient(2, "  1  0  x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_magnetic_variation() {
+        let variation = MagneticVariation::from_sct(-3.0);
+        let munich = point! { x: 11.8, y: 48.4 };
+
+        let rounded = |bearing: f64| bearing.round() as i32;
+
+        assert_eq!(rounded(variation.declination(munich)), 3);
+        assert_eq!(rounded(variation.to_true(358.0, munich)), 1);
+        assert_eq!(rounded(variation.to_magnetic(1.0, munich)), 358);
+    }
+}
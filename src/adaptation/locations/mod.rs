@@ -1,14 +1,24 @@
 pub mod airways;
+pub mod approach;
+pub mod index;
+pub mod magnetic;
+pub mod procedure;
+pub mod restrictions;
+pub mod route;
+pub mod runway;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
 
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::sync::OnceLock;
 
-use geo::{point, Destination as _, Geodesic, Point};
+use geo::{Destination as _, Distance as _, Geodesic, Point};
 use multimap::MultiMap;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use tracing::{trace, warn};
+use tracing::{debug, trace, warn};
+use uom::si::f32::Length as Level;
 use uom::si::f64::Length;
 use uom::si::length::{meter, nautical_mile};
 
@@ -16,7 +26,14 @@ use std::collections::hash_map::Entry;
 
 use crate::adaptation::icao::IcaoAirport;
 use crate::adaptation::locations::airways::AirwayGraph;
+use crate::adaptation::locations::approach::Approach;
+use crate::adaptation::locations::index::LocationIndex;
+use crate::adaptation::locations::magnetic::MagneticVariation;
+use crate::adaptation::locations::procedure::ProcedureLeg;
+use crate::adaptation::locations::restrictions::ProcedureRestrictions;
 use crate::adaptation::Quantize as _;
+use crate::coordinate::{parse_icao, CoordinateFormat};
+use crate::level;
 use crate::navdata_airports::NavdataAirport;
 use crate::{
     ese::{Ese, SidStar},
@@ -87,8 +104,25 @@ pub struct VOR {
 pub struct Runway {
     pub designators: (String, String),
     pub headings: (u32, u32),
+    /// thresholds of both runway directions
     pub location: (Point, Point),
     pub aerodrome: String,
+    /// not part of .sct files, set via overlays in metres
+    #[serde(default)]
+    pub width: Option<Length>,
+    /// not part of .sct files, set via overlays in feet like levels
+    #[serde(default, with = "level::option_as_feet")]
+    pub elevation: Option<Level>,
+}
+
+impl Runway {
+    /// True headings of both runway directions, `headings` being magnetic
+    pub fn true_headings(&self, magnetic_variation: &MagneticVariation) -> (f64, f64) {
+        (
+            magnetic_variation.to_true(f64::from(self.headings.0), self.location.0),
+            magnetic_variation.to_true(f64::from(self.headings.1), self.location.1),
+        )
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -132,6 +166,9 @@ pub struct SID {
     pub airport: String,
     pub runway: Option<String>,
     pub waypoints: Vec<Fix>,
+    /// leg-typed procedure from overlays, `waypoints` are flown if empty
+    #[serde(default)]
+    pub legs: Vec<ProcedureLeg>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -140,6 +177,9 @@ pub struct STAR {
     pub airport: String,
     pub runway: Option<String>,
     pub waypoints: Vec<Fix>,
+    /// leg-typed procedure from overlays, `waypoints` are flown if empty
+    #[serde(default)]
+    pub legs: Vec<ProcedureLeg>,
 }
 
 impl PartialEq<STAR> for String {
@@ -162,11 +202,34 @@ pub struct Locations {
     pub airways: AirwayGraph,
     pub sids: HashMap<String, MultiMap<String, SID>>,
     pub stars: HashMap<String, MultiMap<String, STAR>>,
+    /// by airport and name
+    #[serde(default)]
+    pub approaches: HashMap<String, MultiMap<String, Approach>>,
+    /// SID and STAR waypoint restrictions, not part of the .ese
+    #[serde(default)]
+    pub restrictions: ProcedureRestrictions,
+    /// applied to range/bearing fixes and runway headings
+    #[serde(default)]
+    pub magnetic_variation: MagneticVariation,
+    /// reference point for ambiguous designators without a nearer one, the .sct
+    /// centre point
+    #[serde(default)]
+    pub centre: Option<Point>,
+    /// Spatial index over fixes, navaids and airports. Not part of the actual data and
+    /// excluded from serde.
+    #[serde(skip)]
+    pub index: LocationIndex,
 }
 
-fn coord_regex() -> &'static Regex {
-    static COORD_RE: OnceLock<Regex> = OnceLock::new();
-    COORD_RE.get_or_init(|| Regex::new(r"^(\d{1,6})(N|S)(\d{2,7})(E|W)$").unwrap())
+/// Points sharing a designator further apart than this make it ambiguous
+const AMBIGUITY_DISTANCE_NM: f64 = 20.0;
+
+fn closest(candidates: Vec<Fix>, near: Point) -> Option<Fix> {
+    candidates.into_iter().min_by(|a, b| {
+        Geodesic
+            .distance(near, a.coordinate)
+            .total_cmp(&Geodesic.distance(near, b.coordinate))
+    })
 }
 
 fn range_bearing_regex() -> &'static Regex {
@@ -233,31 +296,32 @@ impl Locations {
             airways,
             sids: HashMap::new(),
             stars: HashMap::new(),
+            approaches: HashMap::new(),
+            restrictions: HashMap::new(),
+            magnetic_variation: MagneticVariation::from_sct(sct.info.magnetic_variation),
+            centre: Some(sct.info.centre_point),
+            index: LocationIndex::default(),
         };
         ese.sids_stars
             .into_iter()
             .for_each(|sid_star| match sid_star {
                 SidStar::Sid(sid) => {
+                    let context = format!(
+                        "SID {} {} {}",
+                        sid.airport,
+                        sid.name,
+                        sid.runway.as_deref().unwrap_or("")
+                    );
                     let adap_sid = SID {
-                        waypoints: sid
-                            .waypoints
+                        waypoints: locations
+                            .resolve_waypoints(&sid.airport, &sid.waypoints, &context)
                             .into_iter()
-                            .filter_map(|wpt| {
-                                let fix = locations.convert_designator(&wpt);
-                                if fix.is_none() {
-                                    warn!(
-                                        "SID {} {} {}: waypoint {wpt} not found",
-                                        sid.airport,
-                                        sid.name,
-                                        sid.runway.as_deref().unwrap_or("")
-                                    );
-                                }
-                                fix
-                            })
+                            .flatten()
                             .collect(),
                         name: sid.name.clone(),
                         airport: sid.airport.clone(),
                         runway: sid.runway,
+                        legs: vec![],
                     };
                     locations
                         .sids
@@ -268,26 +332,22 @@ impl Locations {
                         .or_insert_with(|| MultiMap::from_iter([(sid.name, adap_sid)]));
                 }
                 SidStar::Star(star) => {
+                    let context = format!(
+                        "STAR {} {} {}",
+                        star.airport,
+                        star.name,
+                        star.runway.as_deref().unwrap_or("")
+                    );
                     let adap_star = STAR {
-                        waypoints: star
-                            .waypoints
+                        waypoints: locations
+                            .resolve_waypoints(&star.airport, &star.waypoints, &context)
                             .into_iter()
-                            .filter_map(|wpt| {
-                                let fix = locations.convert_designator(&wpt);
-                                if fix.is_none() {
-                                    warn!(
-                                        "STAR {} {} {}: waypoint {wpt} not found",
-                                        star.airport,
-                                        star.name,
-                                        star.runway.as_deref().unwrap_or("")
-                                    );
-                                }
-                                fix
-                            })
+                            .flatten()
                             .collect(),
                         name: star.name.clone(),
                         airport: star.airport.clone(),
                         runway: star.runway,
+                        legs: vec![],
                     };
                     locations
                         .stars
@@ -297,135 +357,185 @@ impl Locations {
                         })
                         .or_insert_with(|| MultiMap::from_iter([(star.name.clone(), adap_star)]));
                 }
+                SidStar::Approach(approach) => {
+                    let approach = locations.convert_approach(approach);
+                    locations
+                        .approaches
+                        .entry(approach.airport.clone())
+                        .or_default()
+                        .insert(approach.name.clone(), approach);
+                }
             });
 
         locations
     }
 
+    fn airport_coordinate(&self, designator: &str) -> Option<Point> {
+        self.airports
+            .get(designator)
+            .map(|airport| airport.coordinate)
+    }
+
     pub fn convert_location(&self, loc: &Location) -> Option<Point> {
+        self.convert_location_near(loc, None)
+    }
+
+    /// Like `convert_location`, but picks the point closest to `near` if several
+    /// points share the designator.
+    pub fn convert_location_near(&self, loc: &Location, near: Option<Point>) -> Option<Point> {
         match loc {
             Location::Coordinate(c) => Some(*c),
-            Location::Fix(wpt) => self.convert_designator(wpt).map(|f| f.coordinate),
+            Location::Fix(wpt) => self
+                .convert_designator_near(wpt, near)
+                .map(|f| f.coordinate),
         }
     }
 
-    fn convert_range_bearing(&self, designator: &str) -> Option<Fix> {
+    fn convert_range_bearing(&self, designator: &str, near: Option<Point>) -> Option<Fix> {
         range_bearing_regex()
             .captures(designator)
             .and_then(|captures| {
                 let fix = &captures[1];
-                // TODO magnetic
                 let bearing: f64 = captures[2].parse().unwrap();
                 let range = Length::new::<nautical_mile>(captures[3].parse::<f64>().unwrap());
 
-                self.convert_fix(fix).map(|f| Fix {
+                self.convert_fix(fix, near).map(|f| Fix {
                     designator: designator.to_string(),
-                    coordinate: Geodesic.destination(f.coordinate, bearing, range.get::<meter>()),
+                    coordinate: Geodesic.destination(
+                        f.coordinate,
+                        self.magnetic_variation.to_true(bearing, f.coordinate),
+                        range.get::<meter>(),
+                    ),
                 })
             })
     }
 
     fn convert_coordinate(designator: &str) -> Option<Fix> {
-        coord_regex().captures(designator).and_then(|captures| {
-            let lat_str = &captures[1];
-            let lng_str = &captures[3];
-            let normalised_lat_str = if matches!(lat_str.len(), 1 | 3 | 5) {
-                // invalid syntax
-                if lat_str.starts_with('0') {
-                    warn!("Coordinate waypoints must not be abbreviated and start with a 0: {designator} (lat_str)");
-                    return None;
-                }
-                format!("0{lat_str}")
-            } else {
-                lat_str.to_string()
-            };
-            let normalised_lng_str = if matches!(lng_str.len(), 2 | 4 | 6) {
-                // invalid syntax
-                if lng_str.starts_with('0') {
-                    warn!("Coordinate waypoints must not be abbreviated and start with a 0: {designator} (lng_str)");
-                    return None;
-                }
-                format!("0{lng_str}")
-            } else {
-                lng_str.to_string()
-            };
-            if normalised_lng_str.len().saturating_sub(normalised_lat_str.len()) != 1 {
-                warn!("Coordinate waypoints must have the same precision in lat/lon: {designator}");
-                return None;
-            }
-            let lat: f64 = match normalised_lat_str.len() {
-                2 => normalised_lat_str.parse().unwrap(),
-                4 => normalised_lat_str[0..2].parse::<f64>().unwrap() + normalised_lat_str[2..4].parse::<f64>().unwrap() / 60.0,
-                6 => normalised_lat_str[0..2].parse::<f64>().unwrap() + normalised_lat_str[2..4].parse::<f64>().unwrap() / 60.0
-                    + normalised_lat_str[4..6].parse::<f64>().unwrap() / 3600.0,
-                _ => unreachable!(),
-            };
-            let lng: f64 = match normalised_lng_str.len() {
-                3 => normalised_lng_str.parse().unwrap(),
-                5 => normalised_lng_str[0..3].parse::<f64>().unwrap() + normalised_lng_str[3..5].parse::<f64>().unwrap() / 60.0,
-                7 => normalised_lng_str[0..3].parse::<f64>().unwrap() + normalised_lng_str[3..5].parse::<f64>().unwrap() / 60.0
-                    + normalised_lng_str[5..7].parse::<f64>().unwrap() / 3600.0,
-                _ => unreachable!(),
-            };
-            let n_s = &captures[2];
-            let w_e = &captures[4];
-            Some(Fix {
-                designator: format!("{normalised_lat_str}{n_s}{normalised_lng_str}{w_e}"),
-                coordinate: point! {
-                    x: if w_e == "E" { 1.0 } else { -1.0 } * lng,
-                    y: if n_s == "N" { 1.0 } else { -1.0 } * lat,
-                }
-            })
+        parse_icao(designator).map(|(designator, coordinate)| Fix {
+            designator,
+            coordinate,
+        })
+    }
+
+    /// Coordinates in the other notations of `CoordinateFormat`, i.e. ARINC `4810N`
+    fn convert_other_coordinate(designator: &str) -> Option<Fix> {
+        CoordinateFormat::detect(designator).map(|(_, coordinate)| Fix {
+            designator: designator.to_string(),
+            coordinate,
         })
     }
 
     fn convert_rwy(&self, designator: &str) -> Option<Fix> {
-        if !matches!(designator.len(), 6..=7) {
-            return None;
-        }
+        let end = self.runway_end(designator)?;
 
-        let (ad_designator, rwy_designator) = designator.split_at_checked(4)?;
-        let airport = self.airports.get(ad_designator)?;
-
-        airport.runways.iter().find_map(|rwy| {
-            let (rwy_des, coord) = if rwy.designators.0 == rwy_designator {
-                (&rwy.designators.0, rwy.location.0)
-            } else if rwy.designators.1 == rwy_designator {
-                (&rwy.designators.1, rwy.location.1)
-            } else {
-                return None;
-            };
-
-            Some(Fix {
-                designator: format!("{ad}{rwy_des}", ad = airport.designator),
-                coordinate: coord,
-            })
+        Some(Fix {
+            designator: format!("{}{}", end.runway.aerodrome, end.designator),
+            coordinate: end.threshold,
         })
     }
 
-    fn convert_fix(&self, designator: &str) -> Option<Fix> {
-        self.vors
-            .get(designator)
-            .map(|vor| Fix {
-                designator: vor.designator.clone(),
-                coordinate: vor.coordinate,
-            })
-            .or(self.ndbs.get(designator).map(|ndb| Fix {
-                designator: ndb.designator.clone(),
-                coordinate: ndb.coordinate,
-            }))
-            .or(self.fixes.get(designator).cloned())
-            .or(self.airports.get(designator).map(|airport| Fix {
-                designator: airport.designator.clone(),
-                coordinate: airport.coordinate,
-            }))
-            .or(self.convert_rwy(designator))
+    /// All navaids, fixes and airports named `designator`.
+    fn fix_candidates(&self, designator: &str) -> Vec<Fix> {
+        let vors = self.vors.get_vec(designator).into_iter().flatten();
+        let ndbs = self.ndbs.get_vec(designator).into_iter().flatten();
+        let fixes = self.fixes.get_vec(designator).into_iter().flatten();
+
+        vors.map(|vor| Fix {
+            designator: vor.designator.clone(),
+            coordinate: vor.coordinate,
+        })
+        .chain(ndbs.map(|ndb| Fix {
+            designator: ndb.designator.clone(),
+            coordinate: ndb.coordinate,
+        }))
+        .chain(fixes.cloned())
+        .chain(self.airports.get(designator).map(|airport| Fix {
+            designator: airport.designator.clone(),
+            coordinate: airport.coordinate,
+        }))
+        .collect()
+    }
+
+    /// Whether the candidates of `designator` lie further apart than
+    /// `AMBIGUITY_DISTANCE_NM`, i.e. picking the wrong one matters.
+    fn far_apart(candidates: &[Fix]) -> bool {
+        let threshold = Length::new::<nautical_mile>(AMBIGUITY_DISTANCE_NM).get::<meter>();
+
+        candidates.split_first().is_some_and(|(first, others)| {
+            others
+                .iter()
+                .any(|other| Geodesic.distance(first.coordinate, other.coordinate) > threshold)
+        })
+    }
+
+    /// Whether several points far apart share `designator`.
+    pub fn is_ambiguous(&self, designator: &str) -> bool {
+        Self::far_apart(&self.fix_candidates(designator))
+    }
+
+    /// The navaid, fix or airport named `designator` closest to `near`.
+    pub fn resolve_ambiguous(&self, designator: &str, near: Point) -> Option<Fix> {
+        closest(self.fix_candidates(designator), near)
+    }
+
+    /// Co-located candidates are picked in the order VOR, NDB, fix, airport. Only if
+    /// they lie far apart the one closest to `near` or `centre` is picked.
+    fn convert_fix(&self, designator: &str, near: Option<Point>) -> Option<Fix> {
+        let candidates = self.fix_candidates(designator);
+        // frequent in .sct and TopSky maps, hence only debug
+        let fix = match near.or(self.centre) {
+            Some(near) if Self::far_apart(&candidates) => {
+                debug!("{designator} is ambiguous, picking the one closest to {near:?}");
+                closest(candidates, near)
+            }
+            None if Self::far_apart(&candidates) => {
+                debug!("{designator} is ambiguous and no reference point is known, picking the first one");
+                candidates.into_iter().next()
+            }
+            _ => candidates.into_iter().next(),
+        };
+
+        fix.or_else(|| self.convert_rwy(designator))
     }
 
     pub fn convert_designator(&self, designator: &str) -> Option<Fix> {
-        self.convert_fix(designator)
-            .or(Self::convert_coordinate(designator))
-            .or(self.convert_range_bearing(designator))
+        self.convert_designator_near(designator, None)
+    }
+
+    /// Like `convert_designator`, but picks the point closest to `near` if several
+    /// points far apart share the designator. Without `near` the one closest to
+    /// `centre` is picked.
+    pub fn convert_designator_near(&self, designator: &str, near: Option<Point>) -> Option<Fix> {
+        self.convert_fix(designator, near)
+            .or_else(|| Self::convert_coordinate(designator))
+            .or_else(|| self.convert_range_bearing(designator, near))
+            .or_else(|| Self::convert_other_coordinate(designator))
+    }
+
+    /// Resolves procedure waypoints of `airport`, each closest to the previous one
+    /// starting at the airport. Unknown waypoints are warned about with `context` and
+    /// left `None`, keeping the result aligned with `designators`.
+    fn resolve_waypoints<S: AsRef<str>>(
+        &self,
+        airport: &str,
+        designators: &[S],
+        context: &str,
+    ) -> Vec<Option<Fix>> {
+        let mut near = self.airport_coordinate(airport);
+
+        designators
+            .iter()
+            .map(|designator| {
+                let designator = designator.as_ref();
+                let fix = self.convert_designator_near(designator, near);
+                if let Some(fix) = &fix {
+                    near = Some(fix.coordinate);
+                } else {
+                    warn!("{context}: waypoint {designator} not found");
+                }
+                fix
+            })
+            .collect()
     }
 
     pub fn contains_designator(&self, designator: &str) -> bool {
@@ -459,7 +569,10 @@ impl Locations {
 mod test {
     use geo::point;
 
-    use crate::adaptation::locations::{Airport, Fix, Locations, Runway, NDB, VOR};
+    use crate::adaptation::locations::{
+        magnetic::MagneticVariation, Airport, Fix, Locations, Runway, NDB, VOR,
+    };
+    use crate::Location;
 
     #[test]
     fn test_get_by_wpt() {
@@ -536,6 +649,8 @@ mod test {
                                 },
                             ),
                             aerodrome: "EDDM".to_string(),
+                            width: None,
+                            elevation: None,
                         },
                         Runway {
                             designators: ("08L".to_string(), "26R".to_string()),
@@ -551,6 +666,8 @@ mod test {
                                 },
                             ),
                             aerodrome: "EDDM".to_string(),
+                            width: None,
+                            elevation: None,
                         },
                     ],
                     coordinate: point! {
@@ -672,6 +789,17 @@ mod test {
                 coordinate: point! { x: -40.0, y: 4.0 }
             }
         );
+        assert_eq!(
+            locs.convert_designator("4810N").unwrap(),
+            Fix {
+                designator: "4810N".to_string(),
+                coordinate: point! { x: -10.0, y: 48.0 }
+            }
+        );
+        assert_eq!(
+            locs.convert_designator("H4530").unwrap().coordinate,
+            point! { x: -30.0, y: 45.5 }
+        );
         assert_eq!(locs.convert_designator("400N0400W"), None);
         assert_eq!(locs.convert_designator("0400N0400W"), None);
         assert_eq!(
@@ -705,4 +833,120 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn test_magnetic_variation() {
+        let armut = point! {
+            x: 12.323_332_777_777_777,
+            y: 49.722_499_722_222_224,
+        };
+        let locs = Locations {
+            fixes: [(
+                "ARMUT".to_string(),
+                Fix {
+                    designator: "ARMUT".to_string(),
+                    coordinate: armut,
+                },
+            )]
+            .into_iter()
+            .collect(),
+            magnetic_variation: MagneticVariation::from_sct(-3.0),
+            ..Default::default()
+        };
+        let runway = Runway {
+            designators: ("08R".to_string(), "26L".to_string()),
+            headings: (80, 260),
+            location: (armut, armut),
+            aerodrome: "EDDM".to_string(),
+            width: None,
+            elevation: None,
+        };
+
+        // magnetic 067 is true 070 with 3° east variation
+        assert_eq!(
+            locs.convert_designator("ARMUT067005").unwrap().coordinate,
+            point! {
+                x: 12.444_077_899_400_547,
+                y: 49.750_911_853_173,
+            }
+        );
+        let (heading, reciprocal) = runway.true_headings(&locs.magnetic_variation);
+        assert_eq!(
+            (heading.round() as i32, reciprocal.round() as i32),
+            (83, 263)
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_designators() {
+        let ndb = |x: f64, y: f64| NDB {
+            designator: "MN".to_string(),
+            frequency: "400.000".to_string(),
+            coordinate: point! { x: x, y: y },
+        };
+        let locs = Locations {
+            ndbs: [
+                ("MN".to_string(), ndb(11.5, 48.2)),
+                ("MN".to_string(), ndb(-3.5, 40.4)),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let madrid = point! { x: -3.7, y: 40.5 };
+
+        assert!(locs.is_ambiguous("MN"));
+        assert!(!locs.is_ambiguous("OTT"));
+        assert_eq!(
+            locs.convert_designator("MN").unwrap().coordinate,
+            point! { x: 11.5, y: 48.2 }
+        );
+        assert_eq!(
+            locs.convert_designator_near("MN", Some(madrid))
+                .unwrap()
+                .coordinate,
+            point! { x: -3.5, y: 40.4 }
+        );
+        assert_eq!(
+            locs.convert_location_near(&Location::Fix("MN".to_string()), Some(madrid)),
+            Some(point! { x: -3.5, y: 40.4 })
+        );
+        // without a nearer reference the centre decides
+        let locs = Locations {
+            centre: Some(madrid),
+            ..locs
+        };
+        assert_eq!(
+            locs.convert_designator("MN").unwrap().coordinate,
+            point! { x: -3.5, y: 40.4 }
+        );
+        // the base of range/bearing fixes is resolved near the reference, too
+        let fix = locs
+            .convert_designator_near("MN090010", Some(madrid))
+            .unwrap();
+        assert!(fix.coordinate.x() > -3.5 && fix.coordinate.x() < -3.0);
+
+        // co-located navaids keep the VOR before the NDB, even if the NDB is closer
+        let locs = Locations {
+            vors: [(
+                "MN".to_string(),
+                VOR {
+                    designator: "MN".to_string(),
+                    frequency: "112.000".to_string(),
+                    coordinate: point! { x: 11.51, y: 48.2 },
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ndbs: [("MN".to_string(), ndb(11.5, 48.2))].into_iter().collect(),
+            ..Default::default()
+        };
+        assert!(!locs.is_ambiguous("MN"));
+        assert_eq!(
+            locs.convert_designator_near("MN", Some(point! { x: 11.4, y: 48.2 }))
+                .unwrap()
+                .coordinate,
+            point! { x: 11.51, y: 48.2 }
+        );
+    }
 }
@@ -0,0 +1,468 @@
+//! Leg-typed SIDs and STARs after ARINC 424 path terminators. The .ese only knows
+//! waypoints, so legs are set via overlays and only approximated laterally.
+
+use geo::{Bearing as _, Destination as _, Distance as _, Geodesic, LineString, Point};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uom::si::f32::Length;
+use uom::si::f64::Length as Distance;
+use uom::si::length::{foot, meter, nautical_mile};
+
+use super::approach::{Hold, TurnDirection};
+use super::{Fix, Locations, SID, STAR};
+use crate::level;
+
+fn feet(altitude: u32) -> Length {
+    level::feet(altitude as f32)
+}
+
+/// assumed climb gradient of altitude-terminated legs, about 5 %
+const CLIMB_GRADIENT_FT_PER_NM: f32 = 300.0;
+/// length of legs flown on a heading until intercepting the next leg or until vectored
+const OPEN_LEG_NM: f64 = 5.0;
+/// step of drawn RF arcs in degrees
+const ARC_STEP: f64 = 10.0;
+
+/// In feet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AltitudeRestriction {
+    At(u32),
+    AtOrAbove(u32),
+    AtOrBelow(u32),
+    Between { lower: u32, upper: u32 },
+}
+
+/// In knots
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpeedRestriction {
+    At(u32),
+    AtOrAbove(u32),
+    AtOrBelow(u32),
+}
+
+/// ARINC 424 path terminator, courses and headings being magnetic, altitudes in feet
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathTerminator {
+    /// initial fix
+    IF { fix: String },
+    /// track between the previous fix and `fix`
+    TF { fix: String },
+    /// direct to `fix`
+    DF { fix: String },
+    /// course to `fix`, drawn like `DF` as the course is not intercepted
+    CF { fix: String, course: u32 },
+    /// course to an altitude
+    CA { course: u32, altitude: u32 },
+    /// from `fix` on a course to an altitude
+    FA {
+        fix: String,
+        course: u32,
+        altitude: u32,
+    },
+    /// heading to an altitude
+    VA { heading: u32, altitude: u32 },
+    /// heading to intercept the next leg
+    VI { heading: u32 },
+    /// heading until vectored
+    VM { heading: u32 },
+    /// constant radius arc around `centre` to `fix`
+    RF {
+        fix: String,
+        centre: String,
+        turn: TurnDirection,
+    },
+    /// hold at `fix` until manually terminated
+    HM {
+        fix: String,
+        inbound_course: u32,
+        turn: TurnDirection,
+        leg_seconds: u32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcedureLeg {
+    pub path: PathTerminator,
+    #[serde(default)]
+    pub altitude: Option<AltitudeRestriction>,
+    #[serde(default)]
+    pub speed: Option<SpeedRestriction>,
+}
+
+impl PathTerminator {
+    /// Fix the leg ends at, `None` for legs ending at an altitude, on a heading or
+    /// after leaving a fix
+    pub fn end_fix(&self) -> Option<&str> {
+        match self {
+            Self::IF { fix }
+            | Self::TF { fix }
+            | Self::DF { fix }
+            | Self::CF { fix, .. }
+            | Self::RF { fix, .. }
+            | Self::HM { fix, .. } => Some(fix),
+            Self::CA { .. }
+            | Self::FA { .. }
+            | Self::VA { .. }
+            | Self::VI { .. }
+            | Self::VM { .. } => None,
+        }
+    }
+}
+
+impl AltitudeRestriction {
+    /// Expected altitude after the restriction when climbing from `altitude`
+    fn apply(self, altitude: Length) -> Length {
+        match self {
+            Self::At(at) => feet(at),
+            Self::AtOrAbove(lower) | Self::Between { lower, .. } => altitude.max(feet(lower)),
+            Self::AtOrBelow(upper) => altitude.min(feet(upper)),
+        }
+    }
+}
+
+/// Approximate lateral path of `legs` being flown
+struct Expansion<'a> {
+    locations: &'a Locations,
+    points: Vec<Point>,
+    altitude: Length,
+}
+
+impl Expansion<'_> {
+    fn position(&self) -> Point {
+        *self.points.last().unwrap()
+    }
+
+    fn push(&mut self, point: Point) {
+        if self.position() != point {
+            self.points.push(point);
+        }
+    }
+
+    fn resolve(&self, designator: &str) -> Option<Point> {
+        let point = self
+            .locations
+            .convert_designator_near(designator, Some(self.position()))
+            .map(|fix| fix.coordinate);
+        if point.is_none() {
+            warn!("procedure leg: fix {designator} not found");
+        }
+        point
+    }
+
+    fn to_true(&self, magnetic: u32) -> f64 {
+        self.locations
+            .magnetic_variation
+            .to_true(f64::from(magnetic), self.position())
+    }
+
+    /// Straight ahead on the true `bearing` for `distance_nm`
+    fn fly(&mut self, bearing: f64, distance_nm: f64) {
+        let distance = Distance::new::<nautical_mile>(distance_nm).get::<meter>();
+        let point = Geodesic.destination(self.position(), bearing, distance);
+        self.push(point);
+    }
+
+    fn climb_to(&mut self, bearing: f64, altitude: u32) {
+        let altitude = feet(altitude);
+        let climb = (altitude - self.altitude).get::<foot>().max(0.0);
+        self.fly(bearing, f64::from(climb / CLIMB_GRADIENT_FT_PER_NM));
+        self.altitude = self.altitude.max(altitude);
+    }
+
+    /// Arc around `centre` to `to`, a full circle if `to` is at the current bearing
+    fn arc(&mut self, centre: Point, to: Point, turn: TurnDirection) {
+        let radius = Geodesic.distance(centre, to);
+        let from_bearing = Geodesic.bearing(centre, self.position());
+        let sweep = (Geodesic.bearing(centre, to) - from_bearing).rem_euclid(360.0);
+        let (sweep, side) = match turn {
+            TurnDirection::Right => (sweep, 1.0),
+            TurnDirection::Left => (360.0 - sweep, -1.0),
+        };
+        let sweep = if sweep == 0.0 { 360.0 } else { sweep };
+
+        for step in 1..(sweep / ARC_STEP).ceil() as usize {
+            let point =
+                Geodesic.destination(centre, from_bearing + side * step as f64 * ARC_STEP, radius);
+            self.push(point);
+        }
+        self.push(to);
+    }
+
+    fn leg(&mut self, leg: &ProcedureLeg) {
+        match &leg.path {
+            PathTerminator::IF { fix }
+            | PathTerminator::TF { fix }
+            | PathTerminator::DF { fix }
+            | PathTerminator::CF { fix, .. } => {
+                if let Some(point) = self.resolve(fix) {
+                    self.push(point);
+                }
+            }
+            PathTerminator::CA { course, altitude } => {
+                self.climb_to(self.to_true(*course), *altitude);
+            }
+            PathTerminator::FA {
+                fix,
+                course,
+                altitude,
+            } => {
+                if let Some(point) = self.resolve(fix) {
+                    self.push(point);
+                }
+                self.climb_to(self.to_true(*course), *altitude);
+            }
+            PathTerminator::VA { heading, altitude } => {
+                self.climb_to(self.to_true(*heading), *altitude);
+            }
+            PathTerminator::VI { heading } | PathTerminator::VM { heading } => {
+                self.fly(self.to_true(*heading), OPEN_LEG_NM);
+            }
+            PathTerminator::RF { fix, centre, turn } => {
+                if let (Some(centre), Some(point)) = (self.resolve(centre), self.resolve(fix)) {
+                    self.arc(centre, point, *turn);
+                }
+            }
+            PathTerminator::HM {
+                fix,
+                inbound_course,
+                turn,
+                leg_seconds,
+            } => {
+                if let Some(coordinate) = self.resolve(fix) {
+                    let racetrack = Hold {
+                        fix: Fix {
+                            designator: fix.clone(),
+                            coordinate,
+                        },
+                        inbound_course: *inbound_course,
+                        turn: *turn,
+                        leg_seconds: *leg_seconds,
+                    }
+                    .racetrack(&self.locations.magnetic_variation);
+                    for point in racetrack.points() {
+                        self.push(point);
+                    }
+                }
+            }
+        }
+
+        if let Some(restriction) = leg.altitude {
+            self.altitude = restriction.apply(self.altitude);
+        }
+    }
+}
+
+impl Locations {
+    /// Approximate lateral path of flying `legs` from `start` at `altitude`. Legs
+    /// terminating at an altitude are assumed to climb at 300 ft/NM, legs ending in
+    /// an interception or vectors are drawn 5 NM long. Legs with unknown fixes are
+    /// skipped.
+    pub fn expand_legs(&self, legs: &[ProcedureLeg], start: Point, altitude: Length) -> LineString {
+        let mut expansion = Expansion {
+            locations: self,
+            points: vec![start],
+            altitude,
+        };
+        for leg in legs {
+            expansion.leg(leg);
+        }
+
+        expansion.points.into_iter().collect()
+    }
+}
+
+impl SID {
+    /// Lateral path from the runway threshold, or the airport if the runway is unknown,
+    /// along `legs` if there are any, otherwise along `waypoints`.
+    pub fn path(&self, locations: &Locations) -> LineString {
+        let runway = self
+            .runway
+            .as_ref()
+            .and_then(|runway| locations.runway_end(&format!("{}{runway}", self.airport)));
+        let start = runway
+            .map(|runway| runway.threshold)
+            .or_else(|| locations.airport_coordinate(&self.airport));
+
+        if self.legs.is_empty() {
+            return start
+                .into_iter()
+                .chain(self.waypoints.iter().map(|fix| fix.coordinate))
+                .collect();
+        }
+        let Some(start) = start else {
+            warn!("SID {} {}: airport not found", self.airport, self.name);
+            return LineString::new(vec![]);
+        };
+        let elevation = runway
+            .and_then(|runway| runway.runway.elevation)
+            .unwrap_or_else(|| level::feet(0.0));
+
+        locations.expand_legs(&self.legs, start, elevation)
+    }
+}
+
+impl STAR {
+    /// Lateral path along `legs` if there are any, otherwise along `waypoints`. Legs
+    /// are flown from the first waypoint, so they usually start with an `IF`.
+    pub fn path(&self, locations: &Locations) -> LineString {
+        let waypoints = self.waypoints.iter().map(|fix| fix.coordinate);
+        if self.legs.is_empty() {
+            return waypoints.collect();
+        }
+        let Some(start) = self
+            .waypoints
+            .first()
+            .map(|fix| fix.coordinate)
+            .or_else(|| locations.airport_coordinate(&self.airport))
+        else {
+            warn!("STAR {} {}: airport not found", self.airport, self.name);
+            return LineString::new(vec![]);
+        };
+
+        // altitude terminated legs are unusual on arrivals, assume a high start
+        locations.expand_legs(&self.legs, start, level::feet(10_000.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{point, Destination as _, Distance as _, Geodesic, Point};
+
+    use crate::adaptation::locations::{
+        approach::TurnDirection,
+        test_fixtures::{fix, fix_entry, locations},
+        SID,
+    };
+    use crate::level::feet;
+
+    use super::{AltitudeRestriction, PathTerminator, ProcedureLeg};
+
+    const NM: f64 = 1852.0;
+
+    #[test]
+    fn test_procedure_legs() {
+        let threshold = point! { x: 11.8, y: 48.37 };
+        let locations = locations([
+            fix_entry("MERSI", 11.0, 48.0),
+            fix_entry("DM010", 11.5, 48.3),
+        ]);
+
+        // as written in overlays
+        let legs: Vec<ProcedureLeg> = serde_json::from_str(
+            r#"[
+                { "path": { "VA": { "heading": 260, "altitude": 3000 } } },
+                { "path": { "DF": { "fix": "MERSI" } }, "altitude": { "AtOrAbove": 5000 } }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            legs[1],
+            ProcedureLeg {
+                path: PathTerminator::DF {
+                    fix: "MERSI".to_string()
+                },
+                altitude: Some(AltitudeRestriction::AtOrAbove(5000)),
+                speed: None,
+            }
+        );
+
+        let mut sid = SID {
+            name: "MERSI1S".to_string(),
+            airport: "EDDM".to_string(),
+            runway: Some("26R".to_string()),
+            waypoints: vec![fix("DM010", 11.5, 48.3), fix("MERSI", 11.0, 48.0)],
+            legs: vec![],
+        };
+        // without legs along the waypoints
+        assert_eq!(sid.path(&locations).0.len(), 3);
+
+        sid.legs = legs;
+        let path = sid.path(&locations).into_points();
+        assert_eq!(path.len(), 3);
+        assert_eq!(path[0], threshold);
+        // 3000 ft at 300 ft/NM on heading 260
+        assert_eq!(
+            (Geodesic.distance(path[0], path[1]) / 1852.0).round() as i32,
+            10
+        );
+        assert!(path[1].x() < threshold.x());
+        assert_eq!(path[2], point! { x: 11.0, y: 48.0 });
+    }
+
+    #[test]
+    fn test_procedure_arcs() {
+        let centre = point! { x: 11.0, y: 48.0 };
+        let on_arc = |bearing: f64| Geodesic.destination(centre, bearing, 5.0 * NM);
+        let (north, east) = (on_arc(0.0), on_arc(90.0));
+        let locations = locations([
+            fix_entry("CTR", centre.x(), centre.y()),
+            fix_entry("NORTH", north.x(), north.y()),
+            fix_entry("EAST", east.x(), east.y()),
+        ]);
+        let arc = |fix: &str, turn| {
+            let legs = [ProcedureLeg {
+                path: PathTerminator::RF {
+                    fix: fix.to_string(),
+                    centre: "CTR".to_string(),
+                    turn,
+                },
+                altitude: None,
+                speed: None,
+            }];
+            locations
+                .expand_legs(&legs, north, feet(5000.0))
+                .into_points()
+        };
+        let west_of_centre = |path: &[Point]| path.iter().any(|point| point.x() < centre.x());
+
+        // a quarter circle in 10° steps
+        let right = arc("EAST", TurnDirection::Right);
+        assert_eq!(right.len(), 10);
+        assert_eq!(right.last(), Some(&east));
+        assert!(!west_of_centre(&right));
+        for point in &right {
+            assert_eq!((Geodesic.distance(centre, *point) / NM).round() as i32, 5);
+        }
+
+        // the long way round
+        let left = arc("EAST", TurnDirection::Left);
+        assert_eq!(left.len(), 28);
+        assert_eq!(left.last(), Some(&east));
+        assert!(west_of_centre(&left));
+
+        // ending where it starts is a full circle either way
+        for turn in [TurnDirection::Right, TurnDirection::Left] {
+            let circle = arc("NORTH", turn);
+            assert_eq!(circle.len(), 37);
+            assert!(west_of_centre(&circle));
+            assert_eq!(circle.last(), Some(&north));
+        }
+    }
+
+    #[test]
+    fn test_procedure_hold() {
+        let hold = point! { x: 11.0, y: 48.0 };
+        let locations = locations([fix_entry("HOLD", hold.x(), hold.y())]);
+        let legs: Vec<ProcedureLeg> = serde_json::from_str(
+            r#"[
+                { "path": { "IF": { "fix": "HOLD" } } },
+                { "path": { "HM": { "fix": "HOLD", "inbound_course": 90, "turn": "Right", "leg_seconds": 60 } } }
+            ]"#,
+        )
+        .unwrap();
+
+        let path = locations
+            .expand_legs(&legs, hold, feet(5000.0))
+            .into_points();
+        // a closed racetrack
+        assert_eq!(path.first(), Some(&hold));
+        assert_eq!(path.last(), Some(&hold));
+        // right turns keep it south of the inbound course
+        assert!(path.iter().all(|point| point.y() <= hold.y() + 1e-9));
+        // the outbound leg ends 1 minute at 180 kt, i.e. 3 NM, west of the fix
+        let outbound_end = Geodesic.destination(hold, 270.0, 3.0 * NM);
+        assert!(path
+            .iter()
+            .any(|point| Geodesic.distance(*point, outbound_end) < 10.0));
+    }
+}
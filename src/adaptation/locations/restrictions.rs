@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+
+use geo::{Distance as _, Geodesic};
+use serde::{Deserialize, Serialize};
+use uom::si::f32::Length as Level;
+use uom::si::f64::Length;
+use uom::si::length::meter;
+
+use super::procedure::{AltitudeRestriction, ProcedureLeg, SpeedRestriction};
+use super::{Fix, Locations, SID, STAR};
+use crate::level;
+
+/// runway key of restrictions applying to all runways of a procedure
+pub const ANY_RUNWAY: &str = "*";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaypointRestriction {
+    #[serde(default)]
+    pub altitude: Option<AltitudeRestriction>,
+    #[serde(default)]
+    pub speed: Option<SpeedRestriction>,
+}
+
+/// Restrictions by airport, procedure, runway (or `ANY_RUNWAY`) and waypoint, as
+/// supplied via overlays
+pub type ProcedureRestrictions =
+    HashMap<String, HashMap<String, HashMap<String, HashMap<String, WaypointRestriction>>>>;
+
+/// Altitude window and speed restriction at a waypoint of a procedure
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProfilePoint {
+    pub designator: String,
+    /// along the procedure from its first waypoint
+    pub distance: Length,
+    #[serde(with = "level::option_as_feet")]
+    pub lower: Option<Level>,
+    #[serde(with = "level::option_as_feet")]
+    pub upper: Option<Level>,
+    pub speed: Option<SpeedRestriction>,
+}
+
+impl AltitudeRestriction {
+    pub fn lower(self) -> Option<Level> {
+        match self {
+            Self::At(level) | Self::AtOrAbove(level) | Self::Between { lower: level, .. } => {
+                Some(level::feet(level as f32))
+            }
+            Self::AtOrBelow(_) => None,
+        }
+    }
+
+    pub fn upper(self) -> Option<Level> {
+        match self {
+            Self::At(level) | Self::AtOrBelow(level) | Self::Between { upper: level, .. } => {
+                Some(level::feet(level as f32))
+            }
+            Self::AtOrAbove(_) => None,
+        }
+    }
+}
+
+/// Envelope along `waypoints` for aircraft only climbing, or only descending. A
+/// climbing aircraft is above every lower limit it has passed and below every upper
+/// limit still ahead, and vice versa when descending.
+fn envelope(waypoints: &[(Fix, WaypointRestriction)], climb: bool) -> Vec<ProfilePoint> {
+    let altitudes = |waypoints: &[(Fix, WaypointRestriction)]| {
+        waypoints
+            .iter()
+            .filter_map(|(_, restriction)| restriction.altitude)
+            .collect::<Vec<_>>()
+    };
+    let lowest = |waypoints| {
+        altitudes(waypoints)
+            .into_iter()
+            .filter_map(AltitudeRestriction::lower)
+            .reduce(Level::max)
+    };
+    let highest = |waypoints| {
+        altitudes(waypoints)
+            .into_iter()
+            .filter_map(AltitudeRestriction::upper)
+            .reduce(Level::min)
+    };
+
+    let mut distance = Length::new::<meter>(0.0);
+    waypoints
+        .iter()
+        .enumerate()
+        .map(|(index, (fix, restriction))| {
+            if index > 0 {
+                distance += Length::new::<meter>(
+                    Geodesic.distance(waypoints[index - 1].0.coordinate, fix.coordinate),
+                );
+            }
+            let (passed, ahead) = (&waypoints[..=index], &waypoints[index..]);
+            let (lower, upper) = if climb {
+                (lowest(passed), highest(ahead))
+            } else {
+                (lowest(ahead), highest(passed))
+            };
+
+            ProfilePoint {
+                designator: fix.designator.clone(),
+                distance,
+                lower,
+                upper,
+                speed: restriction.speed,
+            }
+        })
+        .collect()
+}
+
+impl Locations {
+    /// Restriction at `waypoint` of `procedure`, runway specific ones taking
+    /// precedence over ones for `ANY_RUNWAY`
+    pub fn waypoint_restriction(
+        &self,
+        airport: &str,
+        procedure: &str,
+        runway: Option<&str>,
+        waypoint: &str,
+    ) -> Option<&WaypointRestriction> {
+        let runways = self.restrictions.get(airport)?.get(procedure)?;
+        runway
+            .and_then(|runway| runways.get(runway)?.get(waypoint))
+            .or_else(|| runways.get(ANY_RUNWAY)?.get(waypoint))
+    }
+
+    fn restricted_waypoints(
+        &self,
+        airport: &str,
+        procedure: &str,
+        runway: Option<&str>,
+        waypoints: &[Fix],
+    ) -> Vec<(Fix, WaypointRestriction)> {
+        waypoints
+            .iter()
+            .map(|fix| {
+                let restriction =
+                    self.waypoint_restriction(airport, procedure, runway, &fix.designator);
+                (fix.clone(), restriction.copied().unwrap_or_default())
+            })
+            .collect()
+    }
+
+    /// Fixes the `legs` end at, each leg's own altitude and speed restriction taking
+    /// precedence over the overlay restriction of its fix
+    fn restricted_legs(
+        &self,
+        airport: &str,
+        procedure: &str,
+        runway: Option<&str>,
+        legs: &[ProcedureLeg],
+    ) -> Vec<(Fix, WaypointRestriction)> {
+        let mut near = self.airports.get(airport).map(|airport| airport.coordinate);
+        legs.iter()
+            .filter_map(|leg| {
+                let designator = leg.path.end_fix()?;
+                let fix = self.convert_designator_near(designator, near)?;
+                near = Some(fix.coordinate);
+
+                let overlay = self
+                    .waypoint_restriction(airport, procedure, runway, designator)
+                    .copied()
+                    .unwrap_or_default();
+                let restriction = WaypointRestriction {
+                    altitude: leg.altitude.or(overlay.altitude),
+                    speed: leg.speed.or(overlay.speed),
+                };
+                Some((fix, restriction))
+            })
+            .collect()
+    }
+
+    fn procedure_profile(
+        &self,
+        airport: &str,
+        procedure: &str,
+        runway: Option<&str>,
+        waypoints: &[Fix],
+        legs: &[ProcedureLeg],
+        climb: bool,
+    ) -> Vec<ProfilePoint> {
+        let waypoints = if legs.is_empty() {
+            self.restricted_waypoints(airport, procedure, runway, waypoints)
+        } else {
+            self.restricted_legs(airport, procedure, runway, legs)
+        };
+        envelope(&waypoints, climb)
+    }
+
+    /// Vertical profile envelope of a climbing `sid`, along the fixes its legs end
+    /// at or along its waypoints if it has no legs
+    pub fn sid_profile(&self, sid: &SID) -> Vec<ProfilePoint> {
+        self.procedure_profile(
+            &sid.airport,
+            &sid.name,
+            sid.runway.as_deref(),
+            &sid.waypoints,
+            &sid.legs,
+            true,
+        )
+    }
+
+    /// Vertical profile envelope of a descending `star`, along the fixes its legs end
+    /// at or along its waypoints if it has no legs
+    pub fn star_profile(&self, star: &STAR) -> Vec<ProfilePoint> {
+        self.procedure_profile(
+            &star.airport,
+            &star.name,
+            star.runway.as_deref(),
+            &star.waypoints,
+            &star.legs,
+            false,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uom::si::length::nautical_mile;
+
+    use crate::adaptation::locations::{
+        procedure::SpeedRestriction,
+        test_fixtures::{fix, fix_entry, locations},
+        Locations, SID, STAR,
+    };
+    use crate::level::feet;
+
+    #[test]
+    fn test_profile() {
+        let waypoints = vec![
+            fix("DM010", 11.6, 48.0),
+            fix("DM020", 11.3, 48.0),
+            fix("MERSI", 11.0, 48.0),
+        ];
+        let locations = Locations {
+            // as written in overlays
+            restrictions: serde_json::from_str(
+                r#"{ "EDDM": {
+                    "MERSI1S": {
+                        "*": {
+                            "DM010": { "altitude": { "AtOrBelow": 5000 } },
+                            "MERSI": { "altitude": { "AtOrAbove": 10000 }, "speed": { "AtOrBelow": 250 } }
+                        },
+                        "26R": { "DM010": { "altitude": { "AtOrBelow": 6000 } } }
+                    },
+                    "MERSI1A": {
+                        "*": {
+                            "DM020": { "altitude": { "Between": { "lower": 7000, "upper": 9000 } } }
+                        }
+                    }
+                } }"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let levels = |profile: &[super::ProfilePoint]| {
+            profile
+                .iter()
+                .map(|point| (point.lower, point.upper))
+                .collect::<Vec<_>>()
+        };
+
+        let mut sid = SID {
+            name: "MERSI1S".to_string(),
+            airport: "EDDM".to_string(),
+            runway: Some("08L".to_string()),
+            waypoints: waypoints.clone(),
+            legs: vec![],
+        };
+        let profile = locations.sid_profile(&sid);
+        assert_eq!(
+            levels(&profile),
+            [
+                (None, Some(feet(5000.0))),
+                (None, None),
+                (Some(feet(10_000.0)), None),
+            ]
+        );
+        assert_eq!(profile[2].speed, Some(SpeedRestriction::AtOrBelow(250)));
+        assert_eq!(
+            profile[2].distance.get::<nautical_mile>().round() as i32,
+            24
+        );
+
+        // runway specific restrictions win
+        sid.runway = Some("26R".to_string());
+        assert_eq!(locations.sid_profile(&sid)[0].upper, Some(feet(6000.0)));
+
+        let star = STAR {
+            name: "MERSI1A".to_string(),
+            airport: "EDDM".to_string(),
+            runway: None,
+            waypoints: waypoints.into_iter().rev().collect(),
+            legs: vec![],
+        };
+        assert_eq!(
+            levels(&locations.star_profile(&star)),
+            [
+                (Some(feet(7000.0)), None),
+                (Some(feet(7000.0)), Some(feet(9000.0))),
+                (None, Some(feet(9000.0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profile_from_legs() {
+        let mut locations = locations([
+            fix_entry("DM010", 11.6, 48.0),
+            fix_entry("DM020", 11.3, 48.0),
+            fix_entry("MERSI", 11.0, 48.0),
+        ]);
+        locations.restrictions = serde_json::from_str(
+            r#"{ "EDDM": { "MERSI1S": { "*": {
+                "DM010": { "altitude": { "AtOrBelow": 5000 }, "speed": { "AtOrBelow": 210 } },
+                "MERSI": { "altitude": { "AtOrAbove": 10000 } }
+            } } } }"#,
+        )
+        .unwrap();
+        let sid = SID {
+            name: "MERSI1S".to_string(),
+            airport: "EDDM".to_string(),
+            runway: Some("26R".to_string()),
+            // not flown when legs are given
+            waypoints: vec![fix("DM020", 11.3, 48.0)],
+            legs: serde_json::from_str(
+                r#"[
+                    { "path": { "VA": { "heading": 260, "altitude": 3000 } } },
+                    { "path": { "DF": { "fix": "DM010" } }, "altitude": { "AtOrBelow": 4000 } },
+                    { "path": { "TF": { "fix": "MERSI" } }, "speed": { "AtOrBelow": 250 } }
+                ]"#,
+            )
+            .unwrap(),
+        };
+
+        let profile = locations.sid_profile(&sid);
+        assert_eq!(
+            profile
+                .iter()
+                .map(|point| (point.designator.as_str(), point.lower, point.upper))
+                .collect::<Vec<_>>(),
+            [
+                // leg restrictions win over overlay ones
+                ("DM010", None, Some(feet(4000.0))),
+                ("MERSI", Some(feet(10_000.0)), None),
+            ]
+        );
+        assert_eq!(
+            profile.iter().map(|point| point.speed).collect::<Vec<_>>(),
+            [
+                Some(SpeedRestriction::AtOrBelow(210)),
+                Some(SpeedRestriction::AtOrBelow(250)),
+            ]
+        );
+    }
+}
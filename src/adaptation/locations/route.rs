@@ -0,0 +1,856 @@
+use std::{collections::HashMap, ops::Range, sync::OnceLock};
+
+use geo::Point;
+use multimap::MultiMap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use uom::si::f32::Length;
+use uom::si::length::{foot, meter};
+
+use super::{Fix, Locations, SID, STAR};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Speed {
+    /// `N0450`
+    Knots(u32),
+    /// `K0830`
+    KilometresPerHour(u32),
+    /// `M082`, in hundredths of Mach
+    Mach(u32),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Level {
+    /// `F350`
+    FlightLevel(u32),
+    /// `A045`, in hundreds of feet
+    Altitude(u32),
+    /// `S1130`, in tens of metres
+    MetricLevel(u32),
+    /// `M0840`, in tens of metres
+    MetricAltitude(u32),
+    Vfr,
+}
+
+/// Cruising speed and level group, i.e. `N0450F350`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpeedLevel {
+    pub speed: Speed,
+    pub level: Level,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RouteElement {
+    /// speed and level group at the start of the route
+    SpeedLevel(SpeedLevel),
+    Sid(String),
+    Star(String),
+    /// significant point, optionally changing speed and level from there on
+    Point {
+        designator: String,
+        speed_level: Option<SpeedLevel>,
+    },
+    Airway(String),
+    Direct,
+    /// tokens not understood, i.e. `VFR`/`IFR` changes or cruise climbs
+    Unknown(String),
+}
+
+/// ICAO flight plan item 15 route
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Route {
+    pub elements: Vec<RouteElement>,
+    /// byte range of each element in the parsed route string
+    pub spans: Vec<Range<usize>>,
+}
+
+fn speed_level_regex() -> &'static Regex {
+    static SPEED_LEVEL_RE: OnceLock<Regex> = OnceLock::new();
+    SPEED_LEVEL_RE.get_or_init(|| {
+        Regex::new(r"^(N\d{4}|K\d{4}|M\d{3})(F\d{3}|A\d{3}|S\d{4}|M\d{4}|VFR)$").unwrap()
+    })
+}
+
+fn airway_regex() -> &'static Regex {
+    static AIRWAY_RE: OnceLock<Regex> = OnceLock::new();
+    AIRWAY_RE.get_or_init(|| Regex::new(r"^[A-Z]{1,3}\d{1,4}[A-Z]?$").unwrap())
+}
+
+fn procedure_regex() -> &'static Regex {
+    static PROCEDURE_RE: OnceLock<Regex> = OnceLock::new();
+    PROCEDURE_RE.get_or_init(|| Regex::new(r"^[A-Z]{2,5}\d[A-Z]?$").unwrap())
+}
+
+fn point_regex() -> &'static Regex {
+    static POINT_RE: OnceLock<Regex> = OnceLock::new();
+    POINT_RE.get_or_init(|| Regex::new(r"^[0-9A-Z]{2,15}$").unwrap())
+}
+
+impl SpeedLevel {
+    pub fn parse(s: &str) -> Option<Self> {
+        let captures = speed_level_regex().captures(s)?;
+        let value = |group: &str| group[1..].parse::<u32>().unwrap();
+
+        let speed = match &captures[1] {
+            speed if speed.starts_with('N') => Speed::Knots(value(speed)),
+            speed if speed.starts_with('K') => Speed::KilometresPerHour(value(speed)),
+            speed => Speed::Mach(value(speed)),
+        };
+        let level = match &captures[2] {
+            "VFR" => Level::Vfr,
+            level if level.starts_with('F') => Level::FlightLevel(value(level)),
+            level if level.starts_with('A') => Level::Altitude(value(level)),
+            level if level.starts_with('S') => Level::MetricLevel(value(level)),
+            level => Level::MetricAltitude(value(level)),
+        };
+
+        Some(Self { speed, level })
+    }
+}
+
+impl Level {
+    /// `None` for VFR
+    pub fn length(&self) -> Option<Length> {
+        match *self {
+            Self::FlightLevel(level) | Self::Altitude(level) => {
+                Some(Length::new::<foot>(level as f32 * 100.0))
+            }
+            Self::MetricLevel(level) | Self::MetricAltitude(level) => {
+                Some(Length::new::<meter>(level as f32 * 10.0))
+            }
+            Self::Vfr => None,
+        }
+    }
+}
+
+/// Byte ranges of the whitespace separated tokens of `route`
+fn token_spans(route: &str) -> Vec<Range<usize>> {
+    let mut spans = vec![];
+    let mut start = None;
+    for (index, c) in route.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(token_start)) => {
+                spans.push(token_start..index);
+                start = None;
+            }
+            (false, None) => start = Some(index),
+            _ => {}
+        }
+    }
+    if let Some(token_start) = start {
+        spans.push(token_start..route.len());
+    }
+    spans
+}
+
+impl Route {
+    /// Classifies the tokens of a route string. Airways are only recognised after a
+    /// point, procedures only as the first (SID) or last (STAR) element.
+    pub fn parse(route: &str) -> Self {
+        let spans = token_spans(route);
+        let tokens = spans
+            .iter()
+            .map(|span| &route[span.clone()])
+            .collect::<Vec<_>>();
+        let last = tokens.len().saturating_sub(1);
+        let mut elements: Vec<RouteElement> = Vec::with_capacity(tokens.len());
+
+        for (i, token) in tokens.into_iter().enumerate() {
+            let after_point = matches!(elements.last(), Some(RouteElement::Point { .. }));
+            let is_first = elements
+                .iter()
+                .all(|element| matches!(element, RouteElement::SpeedLevel(_)));
+
+            let element = if let Some(speed_level) = SpeedLevel::parse(token) {
+                RouteElement::SpeedLevel(speed_level)
+            } else if token == "DCT" {
+                RouteElement::Direct
+            } else if is_first && procedure_regex().is_match(token) {
+                RouteElement::Sid(token.to_string())
+            } else if i == last && after_point && procedure_regex().is_match(token) {
+                RouteElement::Star(token.to_string())
+            } else if after_point && airway_regex().is_match(token) {
+                RouteElement::Airway(token.to_string())
+            } else {
+                let (designator, speed_level) = match token.split_once('/') {
+                    Some((designator, speed_level)) => (designator, SpeedLevel::parse(speed_level)),
+                    None => (token, None),
+                };
+                if point_regex().is_match(designator)
+                    && (speed_level.is_some() || !token.contains('/'))
+                {
+                    RouteElement::Point {
+                        designator: designator.to_string(),
+                        speed_level,
+                    }
+                } else {
+                    RouteElement::Unknown(token.to_string())
+                }
+            };
+            elements.push(element);
+        }
+
+        Self { elements, spans }
+    }
+
+    fn points(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.elements.iter().filter_map(|element| match element {
+            RouteElement::Point { designator, .. } => Some(designator.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn first_point(&self) -> Option<&str> {
+        self.points().next()
+    }
+
+    pub fn last_point(&self) -> Option<&str> {
+        self.points().next_back()
+    }
+
+    /// SID as filed in the route
+    pub fn sid(&self) -> Option<&str> {
+        self.elements.iter().find_map(|element| match element {
+            RouteElement::Sid(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// STAR as filed in the route
+    pub fn star(&self) -> Option<&str> {
+        self.elements.iter().find_map(|element| match element {
+            RouteElement::Star(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+}
+
+trait Procedure {
+    fn name(&self) -> &str;
+    fn runway(&self) -> Option<&str>;
+}
+
+impl Procedure for SID {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn runway(&self) -> Option<&str> {
+        self.runway.as_deref()
+    }
+}
+
+impl Procedure for STAR {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn runway(&self) -> Option<&str> {
+        self.runway.as_deref()
+    }
+}
+
+/// Procedure for `runway` that was `filed` or `connects` to the route. Filed procedures
+/// win over connecting ones, runway specific ones over ones without a runway, and
+/// the lowest name breaks remaining ties.
+fn suggest_procedure<'a, P: Procedure>(
+    procedures: Option<&'a MultiMap<String, P>>,
+    runway: &str,
+    filed: Option<&str>,
+    connects: impl Fn(&P) -> bool,
+) -> Option<&'a P> {
+    procedures?
+        .iter_all()
+        .flat_map(|(_, procedures)| procedures)
+        .filter(|procedure| procedure.runway().is_none_or(|rwy| rwy == runway))
+        .filter(|procedure| filed == Some(procedure.name()) || connects(procedure))
+        .min_by_key(|procedure| {
+            (
+                filed != Some(procedure.name()),
+                procedure.runway().is_none(),
+                procedure.name(),
+            )
+        })
+}
+
+/// Filed procedure `name` out of `procedures` by airport, restricted to `airport` if
+/// given. Variants for `runway` win over ones without a runway, and the lowest airport
+/// and runway break remaining ties, so the choice is stable. Also returns whether
+/// airports other than the chosen one have a procedure of that name.
+fn filed_procedure<'a, P: Procedure>(
+    procedures: &'a HashMap<String, MultiMap<String, P>>,
+    name: &str,
+    airport: Option<&str>,
+    runway: Option<&str>,
+) -> Option<(&'a P, bool)> {
+    let candidates = procedures
+        .iter()
+        .filter(|(designator, _)| airport.is_none_or(|airport| airport == *designator))
+        .filter_map(|(designator, procedures)| Some((designator, procedures.get_vec(name)?)))
+        .flat_map(|(designator, procedures)| {
+            procedures
+                .iter()
+                .map(move |procedure| (designator, procedure))
+        })
+        .collect::<Vec<_>>();
+    let (designator, procedure) = candidates.iter().min_by_key(|(designator, procedure)| {
+        (
+            runway.is_some() && procedure.runway() != runway,
+            procedure.runway().is_none(),
+            *designator,
+            procedure.runway(),
+        )
+    })?;
+    let ambiguous = candidates.iter().any(|(other, _)| other != designator);
+
+    Some((*procedure, ambiguous))
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RouteFix {
+    pub fix: Fix,
+    /// airway or procedure leading to this fix, `None` for direct legs
+    pub via: Option<String>,
+    /// index of the route element naming `via`
+    pub via_element: Option<usize>,
+    /// speed and level in effect from this fix on
+    pub speed_level: Option<SpeedLevel>,
+    /// index of the route element this fix results from, `None` for the airports
+    pub element: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum RouteDiagnostic {
+    UnknownPoint(String),
+    UnknownSid(String),
+    UnknownStar(String),
+    /// airway could not be expanded between the two points, continued direct
+    UnknownAirwaySegment {
+        airway: String,
+        from: String,
+        to: String,
+    },
+    /// airway without a point following it
+    DanglingAirway(String),
+    UnknownToken(String),
+    /// several points far apart share the designator, the closest one was picked
+    AmbiguousPoint(String),
+    /// several airports have a SID or STAR of that name, the one of the first airport
+    /// by designator was picked
+    AmbiguousProcedure(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ExpandedRoute {
+    pub fixes: Vec<RouteFix>,
+    /// diagnostics with the index of the route element causing them
+    pub diagnostics: Vec<(usize, RouteDiagnostic)>,
+}
+
+impl ExpandedRoute {
+    /// Adds `fix` unless it repeats the previous fix, i.e. the departure airport.
+    fn push(
+        &mut self,
+        fix: Fix,
+        via: Option<(usize, String)>,
+        speed_level: Option<SpeedLevel>,
+        element: Option<usize>,
+    ) {
+        if self.fixes.last().is_some_and(|last| last.fix == fix) {
+            return;
+        }
+        let (via_element, via) = via.unzip();
+        self.fixes.push(RouteFix {
+            fix,
+            via,
+            via_element,
+            speed_level,
+            element,
+        });
+    }
+
+    fn diagnose(&mut self, element: usize, diagnostic: RouteDiagnostic) {
+        self.diagnostics.push((element, diagnostic));
+    }
+
+    fn last_coordinate(&self) -> Option<Point> {
+        self.fixes.last().map(|route_fix| route_fix.fix.coordinate)
+    }
+}
+
+impl Locations {
+    /// SID from `adep` for the active `runway` ending at the first point of `route`.
+    pub fn suggest_sid(&self, adep: &str, runway: &str, route: &Route) -> Option<&SID> {
+        let first_point = route.first_point();
+        suggest_procedure(self.sids.get(adep), runway, route.sid(), |sid| {
+            first_point.is_some()
+                && sid.waypoints.last().map(|fix| fix.designator.as_str()) == first_point
+        })
+    }
+
+    /// STAR to `ades` for the active `runway` starting at the last point of `route`.
+    pub fn suggest_star(&self, ades: &str, runway: &str, route: &Route) -> Option<&STAR> {
+        let last_point = route.last_point();
+        suggest_procedure(self.stars.get(ades), runway, route.star(), |star| {
+            last_point.is_some()
+                && star.waypoints.first().map(|fix| fix.designator.as_str()) == last_point
+        })
+    }
+
+    /// Expands a route to its fixes, following airways, SIDs and STARs. SIDs and STARs
+    /// are taken for the departure and arrival runway if known. Tokens that cannot be
+    /// resolved are skipped and reported as diagnostics.
+    pub fn expand_route(
+        &self,
+        route: &Route,
+        (departure, departure_runway): (Option<&str>, Option<&str>),
+        (destination, arrival_runway): (Option<&str>, Option<&str>),
+    ) -> ExpandedRoute {
+        let mut expanded = ExpandedRoute::default();
+        let mut speed_level = None;
+        let mut pending_airway: Option<(usize, &str)> = None;
+
+        if let Some(airport) = departure.and_then(|departure| self.airports.get(departure)) {
+            expanded.push(
+                Fix {
+                    designator: airport.designator.clone(),
+                    coordinate: airport.coordinate,
+                },
+                None,
+                None,
+                None,
+            );
+        }
+
+        for (i, element) in route.elements.iter().enumerate() {
+            match element {
+                RouteElement::SpeedLevel(initial) => speed_level = Some(*initial),
+                RouteElement::Direct => (),
+                RouteElement::Airway(airway) => pending_airway = Some((i, airway)),
+                RouteElement::Unknown(token) => {
+                    expanded.diagnose(i, RouteDiagnostic::UnknownToken(token.clone()));
+                }
+                RouteElement::Sid(name) => {
+                    match filed_procedure(&self.sids, name, departure, departure_runway) {
+                        Some((sid, ambiguous)) => {
+                            if ambiguous {
+                                expanded
+                                    .diagnose(i, RouteDiagnostic::AmbiguousProcedure(name.clone()));
+                            }
+                            sid.waypoints.iter().for_each(|fix| {
+                                expanded.push(
+                                    fix.clone(),
+                                    Some((i, name.clone())),
+                                    speed_level,
+                                    Some(i),
+                                );
+                            });
+                        }
+                        None => expanded.diagnose(i, RouteDiagnostic::UnknownSid(name.clone())),
+                    }
+                }
+                RouteElement::Star(name) => {
+                    match filed_procedure(&self.stars, name, destination, arrival_runway) {
+                        Some((star, ambiguous)) => {
+                            if ambiguous {
+                                expanded
+                                    .diagnose(i, RouteDiagnostic::AmbiguousProcedure(name.clone()));
+                            }
+                            star.waypoints.iter().for_each(|fix| {
+                                expanded.push(
+                                    fix.clone(),
+                                    Some((i, name.clone())),
+                                    speed_level,
+                                    Some(i),
+                                );
+                            });
+                        }
+                        None => expanded.diagnose(i, RouteDiagnostic::UnknownStar(name.clone())),
+                    }
+                }
+                RouteElement::Point {
+                    designator,
+                    speed_level: changed_speed_level,
+                } => {
+                    let airway_fixes =
+                        pending_airway.take().and_then(|(airway_element, airway)| {
+                            let from = expanded.fixes.last()?.fix.clone();
+                            let fixes = self
+                                .airways
+                                .expand_airway_segment(&from, designator, airway, self);
+                            if fixes.is_none() {
+                                debug!(
+                                    "Could not expand {} {airway} {designator}",
+                                    from.designator
+                                );
+                                expanded.diagnose(
+                                    airway_element,
+                                    RouteDiagnostic::UnknownAirwaySegment {
+                                        airway: airway.to_string(),
+                                        from: from.designator,
+                                        to: designator.clone(),
+                                    },
+                                );
+                            }
+                            fixes.map(|fixes| (airway_element, airway, fixes))
+                        });
+
+                    match airway_fixes {
+                        Some((airway_element, airway, fixes)) => {
+                            let last = fixes.len() - 1;
+                            for (j, (airway_fix, _)) in fixes.into_iter().enumerate() {
+                                let element = if j == last {
+                                    speed_level = changed_speed_level.or(speed_level);
+                                    i
+                                } else {
+                                    airway_element
+                                };
+                                expanded.push(
+                                    airway_fix.fix,
+                                    Some((airway_element, airway.to_string())),
+                                    speed_level,
+                                    Some(element),
+                                );
+                            }
+                        }
+                        None => match self
+                            .convert_designator_near(designator, expanded.last_coordinate())
+                        {
+                            Some(fix) => {
+                                if self.is_ambiguous(designator) {
+                                    expanded.diagnose(
+                                        i,
+                                        RouteDiagnostic::AmbiguousPoint(designator.clone()),
+                                    );
+                                }
+                                speed_level = changed_speed_level.or(speed_level);
+                                expanded.push(fix, None, speed_level, Some(i));
+                            }
+                            None => expanded
+                                .diagnose(i, RouteDiagnostic::UnknownPoint(designator.clone())),
+                        },
+                    }
+                }
+            }
+        }
+
+        if let Some((airway_element, airway)) = pending_airway {
+            expanded.diagnose(
+                airway_element,
+                RouteDiagnostic::DanglingAirway(airway.to_string()),
+            );
+        }
+        if let Some(airport) = destination.and_then(|destination| self.airports.get(destination)) {
+            expanded.push(
+                Fix {
+                    designator: airport.designator.clone(),
+                    coordinate: airport.coordinate,
+                },
+                None,
+                speed_level,
+                None,
+            );
+        }
+
+        expanded
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::point;
+
+    use multimap::MultiMap;
+
+    use crate::adaptation::locations::{
+        airways::{AirwayFix, AirwayGraph, AirwayType},
+        test_fixtures::fix,
+        Fix, GraphPosition, Locations, SID, STAR, VOR,
+    };
+
+    use super::{Level, Route, RouteDiagnostic, RouteElement, Speed, SpeedLevel};
+
+    #[test]
+    fn test_route_parse() {
+        let route = Route::parse(
+            "N0450F350 MERSI1S MERSI Y101 ARMUT DCT OTT/M082F370 T161 46N078W C/ABC BIBOS1A",
+        );
+
+        assert_eq!(
+            route.elements,
+            vec![
+                RouteElement::SpeedLevel(SpeedLevel {
+                    speed: Speed::Knots(450),
+                    level: Level::FlightLevel(350),
+                }),
+                RouteElement::Sid("MERSI1S".to_string()),
+                RouteElement::Point {
+                    designator: "MERSI".to_string(),
+                    speed_level: None,
+                },
+                RouteElement::Airway("Y101".to_string()),
+                RouteElement::Point {
+                    designator: "ARMUT".to_string(),
+                    speed_level: None,
+                },
+                RouteElement::Direct,
+                RouteElement::Point {
+                    designator: "OTT".to_string(),
+                    speed_level: Some(SpeedLevel {
+                        speed: Speed::Mach(82),
+                        level: Level::FlightLevel(370),
+                    }),
+                },
+                RouteElement::Airway("T161".to_string()),
+                RouteElement::Point {
+                    designator: "46N078W".to_string(),
+                    speed_level: None,
+                },
+                RouteElement::Unknown("C/ABC".to_string()),
+                RouteElement::Point {
+                    designator: "BIBOS1A".to_string(),
+                    speed_level: None,
+                },
+            ]
+        );
+        assert_eq!(
+            Route::parse("N0100A045 MERSI BIBOS1A").elements.last(),
+            Some(&RouteElement::Star("BIBOS1A".to_string()))
+        );
+        assert_eq!(
+            Route::parse("  MERSI\tDCT  BIBOS ").spans,
+            vec![2..7, 8..11, 13..18]
+        );
+        assert_eq!(
+            SpeedLevel::parse("K0830S1130"),
+            Some(SpeedLevel {
+                speed: Speed::KilometresPerHour(830),
+                level: Level::MetricLevel(1130),
+            })
+        );
+    }
+
+    #[test]
+    fn test_route_expand() {
+        let mersi = fix("MERSI", 11.0, 48.0);
+        let bamur = fix("BAMUR", 11.5, 48.5);
+        let armut = fix("ARMUT", 12.0, 49.0);
+        let mut airways = AirwayGraph::default();
+        for (from, to) in [(&mersi, &bamur), (&bamur, &armut)] {
+            airways.insert_or_update_segment(
+                "Y101",
+                &from.designator,
+                GraphPosition(from.coordinate),
+                &AirwayFix {
+                    fix: to.clone(),
+                    valid_direction: true,
+                    minimum_level: None,
+                    maximum_level: None,
+                },
+                AirwayType::Both,
+            );
+        }
+        let locations = Locations {
+            fixes: [
+                ("MERSI".to_string(), mersi.clone()),
+                ("BAMUR".to_string(), bamur.clone()),
+                ("ARMUT".to_string(), armut.clone()),
+                ("DUP".to_string(), fix("DUP", 2.0, 40.0)),
+                ("DUP".to_string(), fix("DUP", 12.2, 49.1)),
+            ]
+            .into_iter()
+            .collect(),
+            vors: [(
+                "OTT".to_string(),
+                VOR {
+                    designator: "OTT".to_string(),
+                    frequency: "112.300".to_string(),
+                    coordinate: point! { x: 11.8, y: 48.2 },
+                },
+            )]
+            .into_iter()
+            .collect(),
+            airways,
+            ..Default::default()
+        };
+
+        let expanded = locations.expand_route(
+            &Route::parse("N0450F350 MERSI Y101 ARMUT/N0440F370 DCT DUP OTT Q99 MIQ 4620N05805W"),
+            (None, None),
+            (None, None),
+        );
+        let f350 = Some(SpeedLevel {
+            speed: Speed::Knots(450),
+            level: Level::FlightLevel(350),
+        });
+        let f370 = Some(SpeedLevel {
+            speed: Speed::Knots(440),
+            level: Level::FlightLevel(370),
+        });
+
+        assert_eq!(
+            expanded
+                .fixes
+                .iter()
+                .map(|route_fix| (
+                    route_fix.fix.designator.as_str(),
+                    route_fix.via.as_deref(),
+                    route_fix.speed_level
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("MERSI", None, f350),
+                ("BAMUR", Some("Y101"), f350),
+                ("ARMUT", Some("Y101"), f370),
+                ("DUP", None, f370),
+                ("OTT", None, f370),
+                ("4620N05805W", None, f370),
+            ]
+        );
+        assert_eq!(expanded.fixes[3].fix, fix("DUP", 12.2, 49.1));
+        assert_eq!(
+            expanded.diagnostics,
+            vec![
+                (5, RouteDiagnostic::AmbiguousPoint("DUP".to_string())),
+                (
+                    7,
+                    RouteDiagnostic::UnknownAirwaySegment {
+                        airway: "Q99".to_string(),
+                        from: "OTT".to_string(),
+                        to: "MIQ".to_string(),
+                    }
+                ),
+                (8, RouteDiagnostic::UnknownPoint("MIQ".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_route_procedure_variants() {
+        let sid = |airport: &str, runway: &str, first: Fix| SID {
+            name: "MERSI1S".to_string(),
+            airport: airport.to_string(),
+            runway: Some(runway.to_string()),
+            waypoints: vec![first, fix("MERSI", 11.0, 48.0)],
+            legs: vec![],
+        };
+        let mut sids: MultiMap<String, SID> = MultiMap::new();
+        for (runway, first) in [
+            ("08L", fix("DM080", 12.0, 48.4)),
+            ("26R", fix("DM260", 11.5, 48.3)),
+        ] {
+            sids.insert("MERSI1S".to_string(), sid("EDDM", runway, first));
+        }
+        let mut other: MultiMap<String, SID> = MultiMap::new();
+        other.insert(
+            "MERSI1S".to_string(),
+            sid("EDMA", "25", fix("AX250", 10.9, 48.4)),
+        );
+        let locations = Locations {
+            sids: [("EDDM".to_string(), sids), ("EDMA".to_string(), other)]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let route = Route::parse("MERSI1S");
+        let first_fix = |departure, runway| {
+            let expanded = locations.expand_route(&route, (departure, runway), (None, None));
+            (
+                expanded.fixes[0].fix.designator.clone(),
+                expanded.diagnostics,
+            )
+        };
+
+        assert_eq!(
+            first_fix(Some("EDDM"), Some("26R")),
+            ("DM260".to_string(), vec![])
+        );
+        assert_eq!(
+            first_fix(Some("EDDM"), Some("08L")),
+            ("DM080".to_string(), vec![])
+        );
+        // without a runway the lowest one is taken
+        assert_eq!(first_fix(Some("EDDM"), None), ("DM080".to_string(), vec![]));
+        // without an airport the first one is taken, and flagged
+        assert_eq!(
+            first_fix(None, Some("26R")),
+            (
+                "DM260".to_string(),
+                vec![(
+                    0,
+                    RouteDiagnostic::AmbiguousProcedure("MERSI1S".to_string())
+                )]
+            )
+        );
+    }
+
+    #[test]
+    fn test_suggest_procedures() {
+        let sid = |name: &str, runway: Option<&str>, last: &str| SID {
+            name: name.to_string(),
+            airport: "EDDM".to_string(),
+            runway: runway.map(str::to_string),
+            waypoints: vec![fix("DM010", 11.7, 48.3), fix(last, 11.0, 48.0)],
+            legs: vec![],
+        };
+        let star = |name: &str, runway: Option<&str>, first: &str| STAR {
+            name: name.to_string(),
+            airport: "EDDF".to_string(),
+            runway: runway.map(str::to_string),
+            waypoints: vec![fix(first, 8.0, 50.5), fix("DF010", 8.5, 50.0)],
+            legs: vec![],
+        };
+        let sids: MultiMap<String, SID> = [
+            sid("MERSI1S", Some("26R"), "MERSI"),
+            sid("MERSI1N", Some("08L"), "MERSI"),
+            sid("MERSI2X", None, "MERSI"),
+            sid("ARMUT1S", Some("26R"), "ARMUT"),
+        ]
+        .into_iter()
+        .map(|sid| (sid.name.clone(), sid))
+        .collect();
+        let stars: MultiMap<String, STAR> = [
+            star("BIBOS1A", None, "BIBOS"),
+            star("ASPAT1A", None, "ASPAT"),
+            star("ASPAT2A", None, "ASPAT"),
+        ]
+        .into_iter()
+        .map(|star| (star.name.clone(), star))
+        .collect();
+        let locations = Locations {
+            sids: [("EDDM".to_string(), sids)].into_iter().collect(),
+            stars: [("EDDF".to_string(), stars)].into_iter().collect(),
+            ..Default::default()
+        };
+
+        let route = Route::parse("N0450F350 MERSI Y101 ARMUT DCT ASPAT");
+        let suggested_sid = |runway, route: &str| {
+            locations
+                .suggest_sid("EDDM", runway, &Route::parse(route))
+                .map(|sid| sid.name.clone())
+        };
+        let route_str = "N0450F350 MERSI Y101 ARMUT DCT ASPAT";
+        assert_eq!(suggested_sid("26R", route_str).as_deref(), Some("MERSI1S"));
+        assert_eq!(suggested_sid("08L", route_str).as_deref(), Some("MERSI1N"));
+        assert_eq!(suggested_sid("26L", route_str).as_deref(), Some("MERSI2X"));
+        assert_eq!(
+            suggested_sid("26R", "ARMUT1S ARMUT DCT ASPAT").as_deref(),
+            Some("ARMUT1S")
+        );
+        assert_eq!(suggested_sid("26R", "N0450F350 DCT"), None);
+        assert_eq!(
+            locations
+                .suggest_star("EDDF", "25C", &route)
+                .map(|star| star.name.as_str()),
+            Some("ASPAT1A")
+        );
+        assert_eq!(
+            locations
+                .suggest_star("EDDF", "25C", &Route::parse("ARMUT DCT ASPAT ASPAT2A"))
+                .map(|star| star.name.as_str()),
+            Some("ASPAT2A")
+        );
+        assert_eq!(locations.suggest_star("EDDM", "26R", &route), None);
+    }
+}
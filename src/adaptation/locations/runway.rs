@@ -0,0 +1,232 @@
+use geo::{
+    Bearing as _, Destination as _, Distance as _, Geodesic, Line, LineString, Point, Polygon,
+};
+use uom::si::f64::Length;
+use uom::si::length::{meter, nautical_mile};
+
+use super::magnetic::MagneticVariation;
+use super::{Locations, Runway};
+
+/// assumed for runways without a width
+const DEFAULT_WIDTH_M: f64 = 45.0;
+/// ICAO Annex 14, shortened to half the runway on short runways
+const TOUCHDOWN_ZONE_LENGTH_M: f64 = 900.0;
+/// length of the ticks on either side of an extended centreline
+const TICK_HALF_LENGTH_NM: f64 = 0.25;
+
+/// One direction of a `Runway`, seen from its threshold
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunwayEnd<'a> {
+    pub runway: &'a Runway,
+    pub designator: &'a str,
+    pub threshold: Point,
+    /// threshold of the opposite direction
+    pub opposite: Point,
+}
+
+/// Geometry for approach displays
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtendedCentreline {
+    /// from the threshold outwards, against the landing direction
+    pub centreline: Line,
+    /// across the centreline every tick interval, starting one interval out
+    pub ticks: Vec<Line>,
+}
+
+impl Runway {
+    pub fn ends(&self) -> [RunwayEnd<'_>; 2] {
+        [
+            RunwayEnd {
+                runway: self,
+                designator: &self.designators.0,
+                threshold: self.location.0,
+                opposite: self.location.1,
+            },
+            RunwayEnd {
+                runway: self,
+                designator: &self.designators.1,
+                threshold: self.location.1,
+                opposite: self.location.0,
+            },
+        ]
+    }
+
+    /// The direction designated `designator`, i.e. `26R`
+    pub fn end(&self, designator: &str) -> Option<RunwayEnd<'_>> {
+        self.ends()
+            .into_iter()
+            .find(|end| end.designator == designator)
+    }
+
+    /// Distance between the thresholds
+    pub fn length(&self) -> Length {
+        Length::new::<meter>(Geodesic.distance(self.location.0, self.location.1))
+    }
+
+    /// `width` or the common 45 m
+    pub fn width_or_default(&self) -> Length {
+        self.width
+            .unwrap_or_else(|| Length::new::<meter>(DEFAULT_WIDTH_M))
+    }
+}
+
+impl RunwayEnd<'_> {
+    /// True course from the threshold towards the opposite threshold
+    pub fn true_course(&self) -> f64 {
+        Geodesic
+            .bearing(self.threshold, self.opposite)
+            .rem_euclid(360.0)
+    }
+
+    /// Magnetic course from the threshold towards the opposite threshold
+    pub fn magnetic_course(&self, magnetic_variation: &MagneticVariation) -> f64 {
+        magnetic_variation.to_magnetic(self.true_course(), self.threshold)
+    }
+
+    /// Touchdown zone as a rectangle of the runway width beyond the threshold
+    pub fn touchdown_zone(&self) -> Polygon {
+        let course = self.true_course();
+        let length = TOUCHDOWN_ZONE_LENGTH_M.min(self.runway.length().get::<meter>() / 2.0);
+        let half_width = self.runway.width_or_default().get::<meter>() / 2.0;
+        let end = Geodesic.destination(self.threshold, course, length);
+        let side = |point, offset: f64| Geodesic.destination(point, course + offset, half_width);
+
+        Polygon::new(
+            LineString::from(vec![
+                side(self.threshold, -90.0),
+                side(end, -90.0),
+                side(end, 90.0),
+                side(self.threshold, 90.0),
+                side(self.threshold, -90.0),
+            ]),
+            vec![],
+        )
+    }
+
+    /// Extended centreline of `length_nm` with ticks every `tick_interval` NM, no
+    /// ticks for a non-positive interval.
+    pub fn extended_centreline(&self, length_nm: f64, tick_interval: f64) -> ExtendedCentreline {
+        let outbound = (self.true_course() + 180.0).rem_euclid(360.0);
+        let along = |distance_nm: f64| {
+            Geodesic.destination(
+                self.threshold,
+                outbound,
+                Length::new::<nautical_mile>(distance_nm).get::<meter>(),
+            )
+        };
+        let tick_half_length = Length::new::<nautical_mile>(TICK_HALF_LENGTH_NM).get::<meter>();
+
+        let ticks = if tick_interval > 0.0 {
+            (1..=(length_nm / tick_interval).floor() as usize)
+                .map(|i| {
+                    let centre = along(i as f64 * tick_interval);
+                    Line::new(
+                        Geodesic.destination(centre, outbound - 90.0, tick_half_length),
+                        Geodesic.destination(centre, outbound + 90.0, tick_half_length),
+                    )
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        ExtendedCentreline {
+            centreline: Line::new(self.threshold, along(length_nm)),
+            ticks,
+        }
+    }
+}
+
+impl Locations {
+    /// Runway direction by airport and runway designator, i.e. `EDDM26R`
+    pub fn runway_end(&self, designator: &str) -> Option<RunwayEnd<'_>> {
+        if !matches!(designator.len(), 6..=7) {
+            return None;
+        }
+
+        let (ad_designator, rwy_designator) = designator.split_at_checked(4)?;
+        self.airports
+            .get(ad_designator)?
+            .runways
+            .iter()
+            .find_map(|rwy| rwy.end(rwy_designator))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{point, Distance as _, Geodesic};
+    use uom::si::{f64::Length, length::meter};
+
+    use crate::adaptation::locations::{magnetic::MagneticVariation, Runway};
+    use crate::level::feet;
+
+    #[test]
+    fn test_runway_geometry() {
+        let runway = Runway {
+            designators: ("08R".to_string(), "26L".to_string()),
+            headings: (80, 260),
+            location: (
+                point! { x: 11.751_016_944_444_444, y: 48.340_668_888_888_89 },
+                point! { x: 11.804_613_888_888_89, y: 48.344_796_944_444_45 },
+            ),
+            aerodrome: "EDDM".to_string(),
+            width: Some(Length::new::<meter>(60.0)),
+            elevation: None,
+        };
+        let rounded = |value: f64| value.round() as i32;
+
+        assert_eq!(rounded(runway.length().get::<meter>()), 4000);
+        assert!(runway.end("26R").is_none());
+
+        let west = runway.end("08R").unwrap();
+        let east = runway.end("26L").unwrap();
+        assert_eq!(rounded(west.true_course()), 83);
+        assert_eq!(rounded(east.true_course()), 263);
+        assert_eq!(
+            rounded(west.magnetic_course(&MagneticVariation::from_sct(-3.0))),
+            80
+        );
+
+        let touchdown_zone = west.touchdown_zone();
+        let corners = touchdown_zone.exterior().points().collect::<Vec<_>>();
+        assert_eq!(corners.len(), 5);
+        assert_eq!(rounded(Geodesic.distance(corners[0], corners[1])), 900);
+        assert_eq!(rounded(Geodesic.distance(corners[0], corners[3])), 60);
+
+        let centreline = east.extended_centreline(10.0, 2.0);
+        assert_eq!(centreline.ticks.len(), 5);
+        assert_eq!(
+            rounded(Geodesic.distance(centreline.centreline.start_point(), east.threshold)),
+            0
+        );
+        // extends beyond the eastern threshold, away from the runway
+        assert!(centreline.centreline.end.x > east.threshold.x());
+        assert_eq!(
+            rounded(Geodesic.distance(east.threshold, centreline.centreline.end_point())),
+            18_520
+        );
+        assert!(east.extended_centreline(10.0, 0.0).ticks.is_empty());
+    }
+
+    #[test]
+    fn test_runway_from_overlay() {
+        let mut json = serde_json::to_value(Runway {
+            designators: ("08R".to_string(), "26L".to_string()),
+            headings: (80, 260),
+            location: (point! { x: 11.75, y: 48.34 }, point! { x: 11.8, y: 48.34 }),
+            aerodrome: "EDDM".to_string(),
+            width: None,
+            elevation: None,
+        })
+        .unwrap();
+        // as written in overlays, the width in metres and the elevation in feet
+        json["width"] = 60.into();
+        json["elevation"] = 1487.into();
+
+        let runway: Runway = serde_json::from_value(json).unwrap();
+        assert_eq!(runway.width, Some(Length::new::<meter>(60.0)));
+        assert_eq!(runway.elevation, Some(feet(1487.0)));
+        assert_eq!(serde_json::to_value(&runway).unwrap()["elevation"], 1487.0);
+    }
+}
@@ -0,0 +1,54 @@
+//! Hand-built locations shared by the tests of the location consumers.
+
+use geo::point;
+
+use super::{Airport, Fix, Locations, Runway};
+
+pub(crate) fn fix(designator: &str, x: f64, y: f64) -> Fix {
+    Fix {
+        designator: designator.to_string(),
+        coordinate: point! { x: x, y: y },
+    }
+}
+
+/// A fix keyed by its designator, ready to be collected into [`Locations::fixes`].
+pub(crate) fn fix_entry(designator: &str, x: f64, y: f64) -> (String, Fix) {
+    (designator.to_string(), fix(designator, x, y))
+}
+
+/// An airport without runways, keyed by its designator.
+pub(crate) fn airport(designator: &str, x: f64, y: f64) -> (String, Airport) {
+    (
+        designator.to_string(),
+        Airport {
+            designator: designator.to_string(),
+            coordinate: point! { x: x, y: y },
+            name: None,
+            country: None,
+            runways: vec![],
+        },
+    )
+}
+
+/// EDDM with its northern runway 08L/26R.
+pub(crate) fn eddm() -> (String, Airport) {
+    let (designator, mut airport) = airport("EDDM", 11.78, 48.35);
+    airport.runways.push(Runway {
+        designators: ("08L".to_string(), "26R".to_string()),
+        headings: (80, 260),
+        location: (point! { x: 11.7, y: 48.36 }, point! { x: 11.8, y: 48.37 }),
+        aerodrome: "EDDM".to_string(),
+        width: None,
+        elevation: None,
+    });
+    (designator, airport)
+}
+
+/// EDDM and the given fixes.
+pub(crate) fn locations(fixes: impl IntoIterator<Item = (String, Fix)>) -> Locations {
+    Locations {
+        fixes: fixes.into_iter().collect(),
+        airports: [eddm()].into_iter().collect(),
+        ..Default::default()
+    }
+}
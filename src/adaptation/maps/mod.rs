@@ -2,7 +2,7 @@ pub mod active;
 
 use std::collections::HashMap;
 
-use geo::{MultiLineString, Point, Polygon};
+use geo::{LineString, MultiLineString, Point, Polygon};
 use serde::{Deserialize, Serialize};
 use tracing::warn;
 
@@ -103,13 +103,25 @@ impl MapGroup {
         }
     }
 
+    /// Last point added, to resolve ambiguous designators of the next items near it
+    fn reference_point(&self) -> Option<Point> {
+        self.lines
+            .0
+            .last()
+            .and_then(|line| line.points().next_back())
+            .or_else(|| self.symbols.last().map(|symbol| symbol.coordinate))
+            .or_else(|| self.labels.last().map(|label| label.coordinate))
+    }
+
     fn add_topsky_symbol(
         &mut self,
         symbol: &MapSymbol,
         settings: &Settings,
         locations: &Locations,
     ) {
-        if let Some(coordinate) = locations.convert_location(&symbol.location) {
+        if let Some(coordinate) =
+            locations.convert_location_near(&symbol.location, self.reference_point())
+        {
             self.symbols.push(Symbol {
                 name: symbol.name.clone(),
                 coordinate,
@@ -126,28 +138,36 @@ impl MapGroup {
         }
     }
     fn add_topsky_lines(&mut self, lines: &[MapLine], locations: &Locations) {
+        let mut reference = self.reference_point();
         self.lines.0.extend(lines.iter().map(|line| {
-            line.points
+            let mut near = reference;
+            let converted = line
+                .points
                 .iter()
                 .filter_map(|loc| {
-                    let coord = locations.convert_location(loc);
+                    let coord = locations.convert_location_near(loc, near);
                     if coord.is_none() {
                         warn!("Could not convert {:?}", loc);
                     }
+                    near = coord.or(near);
                     coord
                 })
-                .collect()
+                .collect::<LineString>();
+            reference = converted.points().next_back().or(reference);
+            converted
         }));
     }
     fn add_topsky_polygon(&mut self, coords: &[Location], locations: &Locations) {
+        let mut near = self.reference_point();
         self.polygons.push(Polygon::new(
             coords
                 .iter()
                 .filter_map(|loc| {
-                    let coord = locations.convert_location(loc);
+                    let coord = locations.convert_location_near(loc, near);
                     if coord.is_none() {
                         warn!("Could not convert {:?}", loc);
                     }
+                    near = coord.or(near);
                     coord
                 })
                 .collect(),
@@ -155,7 +175,9 @@ impl MapGroup {
         ));
     }
     fn add_topsky_text(&mut self, text: &Text, locations: &Locations) {
-        if let Some(coordinate) = locations.convert_location(&text.location) {
+        if let Some(coordinate) =
+            locations.convert_location_near(&text.location, self.reference_point())
+        {
             self.labels.push(Label {
                 coordinate,
                 text: text.content.clone(),
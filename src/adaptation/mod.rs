@@ -1,13 +1,19 @@
 pub mod colours;
 pub mod constraints;
+pub mod flight_plan;
+pub mod frequency;
+pub mod ground;
 pub mod icao;
+pub mod lazy_index;
 pub mod line_styles;
 pub mod locations;
 pub mod maps;
 pub mod sct_items;
+pub mod search;
 pub mod sector_index;
 pub mod sectors;
 pub mod settings;
+pub mod shortest_path;
 pub mod symbols;
 
 use std::path::PathBuf;
@@ -15,10 +21,12 @@ use std::{collections::HashMap, fmt::Write as _, io, path::Path};
 
 use bevy_reflect::Reflect;
 use constraints::extract_constraints;
-use fs_err::read;
+use frequency::{Frequency, VoiceChannel};
+use fs_err::{read, read_to_string};
 use geo::Coord;
 use geo::Line;
 use geo::Point;
+use ground::GroundLayout;
 use icao::AircraftMap;
 use icao::Airline;
 use itertools::Itertools;
@@ -26,19 +34,25 @@ use jrsonnet_evaluator::manifest::escape_string_json;
 use jrsonnet_evaluator::{FileImportResolver, StateBuilder};
 use line_styles::{line_styles_from_topsky, Dash};
 use sct_items::SctItems;
-use sector_index::SectorVolumeIndex;
+use search::SearchIndex;
+use sector_index::{SectorCrossing, SectorVolumeIndex};
 use sectors::Volume;
 use serde::{Deserialize, Serialize};
 use symbols::Symbols;
 use thiserror::Error;
+use tracing::debug;
 use tracing::trace;
 use tracing::warn;
+use uom::si::f32::{Length, Pressure};
 
 use crate::adaptation::sectors::Sectors;
 use crate::airway::parse_airway_txt;
+use crate::aliases::{parse_aliases, Aliases, AliasesError};
 use crate::ese::Constraint;
+use crate::ground::{parse_ground_network, GroundError};
 use crate::navdata_airports::{parse_navdata_airports, NavdataAirportsError};
 use crate::prf::PrfError;
+use crate::voice_channels::{parse_voice_channels, VoiceChannelsError};
 use crate::{
     airway::AirwayError,
     ese::{self, Ese, EseError},
@@ -51,7 +65,15 @@ use crate::{
     topsky::{Topsky, TopskyError},
 };
 
-use self::{colours::Colours, locations::Locations, maps::MapFolders, settings::Settings};
+use self::{
+    colours::{ColourTheme, Colours},
+    locations::{
+        magnetic::{MagneticModelError, MagneticVariation, WorldMagneticModel},
+        Locations,
+    },
+    maps::MapFolders,
+    settings::Settings,
+};
 
 #[derive(Clone, Copy, Debug, Default, Reflect, Serialize, Deserialize, PartialEq, Eq)]
 pub enum HorizontalAlignment {
@@ -79,22 +101,43 @@ pub struct Alignment {
 pub struct Position {
     pub id: String,
     pub name: String,
-    pub frequency: String,
+    /// `None` for positions without a valid VHF COM frequency, i.e. 199.998
+    pub frequency: Option<Frequency>,
+    /// voice channel of the profile on the same frequency, preferably with the same name
+    pub voice_channel: Option<VoiceChannel>,
     pub prefix: String,
     pub suffix: String,
     pub visibility_points: Vec<Point>,
 }
 impl Position {
-    fn from_ese_positions(positions: HashMap<String, ese::Position>) -> HashMap<String, Position> {
+    fn from_ese_positions(
+        positions: HashMap<String, ese::Position>,
+        voice_channels: &[VoiceChannel],
+    ) -> HashMap<String, Position> {
         positions
             .into_iter()
             .map(|(id, pos)| {
+                let frequency = pos.frequency.parse::<Frequency>().map_or_else(
+                    |e| {
+                        debug!("Position {}: {e}", pos.name);
+                        None
+                    },
+                    Some,
+                );
+                let voice_channel = frequency.and_then(|frequency| {
+                    voice_channels
+                        .iter()
+                        .filter(|channel| channel.frequency == frequency)
+                        .min_by_key(|channel| (channel.name != pos.name, &channel.name))
+                        .cloned()
+                });
                 (
                     id,
                     Position {
                         id: pos.identifier,
                         name: pos.name,
-                        frequency: pos.frequency,
+                        frequency,
+                        voice_channel,
                         prefix: pos.prefix,
                         suffix: pos.suffix,
                         visibility_points: pos
@@ -131,6 +174,14 @@ pub enum AdaptationError {
     Airlines(#[from] AirlinesError),
     #[error("ICAO_Airports.txt: {0}")]
     Airports(#[from] AirportsError),
+    #[error("Aliases: {0}")]
+    Aliases(#[from] AliasesError),
+    #[error("Voice channels: {0}")]
+    VoiceChannels(#[from] VoiceChannelsError),
+    #[error("WMM.COF: {0}")]
+    MagneticModel(#[from] MagneticModelError),
+    #[error("Ground network: {0}")]
+    Ground(#[from] GroundError),
     #[error("Failed to serialize/deserialize JSON: {0}")]
     JSON(#[from] serde_json::Error),
     #[error("Failed to serialize/deserialize TOML: {0}")]
@@ -147,10 +198,32 @@ fn normalise_path(base: &Path, to_normalise: PathBuf) -> Result<PathBuf, Adaptat
     Ok(base.join(to_normalise).canonicalize()?)
 }
 
+/// Alternative colour theme, i.e. night colours. Files not given fall back to the
+/// ones of the profile.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ThemeSetup {
+    pub name: String,
+    pub symbology: Option<PathBuf>,
+    /// TopSky settings file with the theme's colours
+    pub topsky_settings: Option<PathBuf>,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct AdaptationSetup {
     pub prf: PathBuf,
     pub overlays: Vec<PathBuf>,
+    #[serde(default)]
+    pub themes: Vec<ThemeSetup>,
+    /// World Magnetic Model coefficients (WMM.COF) replacing the constant magnetic
+    /// variation of the .sct
+    #[serde(default)]
+    pub magnetic_model: Option<PathBuf>,
+    /// decimal year `magnetic_model` is evaluated on, defaults to the model's epoch
+    #[serde(default)]
+    pub magnetic_model_date: Option<f64>,
+    /// EuroScope ground networks, not referenced by the .prf
+    #[serde(default)]
+    pub ground_networks: Vec<PathBuf>,
 }
 impl AdaptationSetup {
     pub fn parse(adaptation_toml: &Path) -> Result<Self, AdaptationError> {
@@ -162,6 +235,28 @@ impl AdaptationSetup {
             .into_iter()
             .map(|overlay| normalise_path(adaptation_toml_parent, overlay))
             .try_collect()?;
+        for theme in &mut adaptation_setup.themes {
+            theme.symbology = theme
+                .symbology
+                .take()
+                .map(|path| normalise_path(adaptation_toml_parent, path))
+                .transpose()?;
+            theme.topsky_settings = theme
+                .topsky_settings
+                .take()
+                .map(|path| normalise_path(adaptation_toml_parent, path))
+                .transpose()?;
+        }
+        adaptation_setup.magnetic_model = adaptation_setup
+            .magnetic_model
+            .take()
+            .map(|path| normalise_path(adaptation_toml_parent, path))
+            .transpose()?;
+        adaptation_setup.ground_networks = adaptation_setup
+            .ground_networks
+            .into_iter()
+            .map(|path| normalise_path(adaptation_toml_parent, path))
+            .try_collect()?;
         trace!("{adaptation_setup:?}");
 
         Ok(adaptation_setup)
@@ -175,6 +270,8 @@ pub struct Adaptation {
     pub locations: Locations,
     // TODO id -> pos? something else might be more useful/efficient (freq, prefix, suffix)?
     pub positions: HashMap<String, Position>,
+    /// voice channels of the profile's voice settings
+    pub voice_channels: Vec<VoiceChannel>,
     pub volumes: HashMap<String, Volume>,
     pub sectors: Sectors,
     pub departure_constraints: HashMap<String, Constraint>,
@@ -187,14 +284,10 @@ pub struct Adaptation {
     pub colours: Colours,
     pub line_styles: HashMap<String, Option<Vec<Dash>>>,
     pub settings: Settings,
+    /// stands, taxiways and holding points of the ground networks
+    pub ground: GroundLayout,
     // TODO
-    // approaches: Vec<String>,
-    // missed_approaches: Vec<String>,
-    // ground stuff
-    // - stands
-    // - taxiways
-    // - maps
-    // - runways
+    // ground maps
     // surveillance information (radar/mlat/...)
     // mva? map only?
     // msaw
@@ -203,16 +296,56 @@ pub struct Adaptation {
     // external/extra_plugin_settings?
     pub aircraft: AircraftMap,
     pub airlines: HashMap<String, Airline>,
+    /// text macros from the alias file referenced by the .prf
+    pub aliases: Aliases,
     /// .sct items used for drawing maps and otherwise not usable
     pub sct_items: SctItems,
     /// Spatial index over `sectors`/`volumes`,. Not part of the adaptation's actual
     /// data and excluded from serde and rebuilt explicitly by `rebuild_sector_index`.
     #[serde(skip)]
     pub sector_index: SectorVolumeIndex,
+    /// Typeahead index, built lazily on the first search and excluded from serde.
+    #[serde(skip)]
+    pub search_index: SearchIndex,
+}
+
+fn load_colour_theme(
+    theme: &ThemeSetup,
+    symbology: &Symbology,
+    sct: &Sct,
+    topsky: Option<&Topsky>,
+    settings: &Settings,
+) -> Result<ColourTheme, AdaptationError> {
+    let theme_symbology = theme
+        .symbology
+        .as_ref()
+        .map(|path| Symbology::parse(&fs_err::read(path)?))
+        .transpose()?;
+    let theme_topsky = match (&theme.topsky_settings, topsky) {
+        (Some(path), Some(topsky)) => Some(topsky.with_colours_from(path)?),
+        (Some(_), None) => {
+            warn!("Theme {}: profile does not use TopSky", theme.name);
+            None
+        }
+        (None, topsky) => topsky.cloned(),
+    };
+
+    Ok(ColourTheme::from_euroscope(
+        theme_symbology.as_ref().unwrap_or(symbology),
+        sct,
+        &theme_topsky,
+        settings,
+    ))
 }
 
 impl Adaptation {
     pub fn from_prf(prf: &Prf) -> AdaptationResult {
+        Self::from_prf_with_themes(prf, &[])
+    }
+
+    /// Create adaptation from .prf with additional colour themes, see `Colours::set_theme`.
+    /// Maps and .sct items keep the colours of the profile.
+    pub fn from_prf_with_themes(prf: &Prf, themes: &[ThemeSetup]) -> AdaptationResult {
         // TODO parallelise/asyncify where able
         let sct = Sct::parse(&fs_err::read(prf.sct_path())?)?;
         let ese = Ese::parse(&fs_err::read(prf.ese_path())?)?;
@@ -220,7 +353,22 @@ impl Adaptation {
         let name = sct.info.name.clone();
         let (volumes, sectors) = Sectors::from_ese(&ese);
         let (departure_constraints, destination_constraints) = extract_constraints(&ese);
-        let positions = Position::from_ese_positions(ese.positions.clone());
+        let voice_channels = prf
+            .voice_channels_path()
+            .and_then(|path| {
+                fs_err::read(path).map_or_else(
+                    |e| {
+                        warn!("Voice channels: {e}");
+                        None
+                    },
+                    Some,
+                )
+            })
+            .map(|bytes| parse_voice_channels(&bytes))
+            .transpose()?
+            .map(VoiceChannel::from_euroscope)
+            .unwrap_or_default();
+        let positions = Position::from_ese_positions(ese.positions.clone(), &voice_channels);
         let symbology = Symbology::parse(&fs_err::read(prf.symbology_path())?)?;
         let squawks = prf
             .squawks_path()
@@ -236,7 +384,13 @@ impl Adaptation {
             )
         });
         let settings = Settings::from_euroscope(&symbology, topsky.as_ref(), squawks.as_ref(), prf);
-        let colours = Colours::from_euroscope(&symbology, &sct, &topsky, &settings);
+        let mut colours = Colours::from_euroscope(&symbology, &sct, &topsky, &settings);
+        for theme in themes {
+            colours.add_theme(
+                theme.name.clone(),
+                load_colour_theme(theme, &symbology, &sct, topsky.as_ref(), &settings)?,
+            );
+        }
         let airports = parse_airports(&fs_err::read(prf.airports_path())?)?;
         let navdata_airports = parse_navdata_airports(&fs_err::read(prf.navdata_airports_path())?)?;
         let locations =
@@ -244,11 +398,26 @@ impl Adaptation {
         let sct_items = SctItems::from_sct(sct, &locations, &colours, &settings);
         let aircraft = parse_aircraft(&fs_err::read(prf.aircraft_path())?)?;
         let airlines = parse_airlines(&fs_err::read(prf.airlines_path())?)?;
+        let aliases = prf
+            .alias_path()
+            .and_then(|path| {
+                fs_err::read(path).map_or_else(
+                    |e| {
+                        warn!("Aliases: {e}");
+                        None
+                    },
+                    Some,
+                )
+            })
+            .map(|bytes| parse_aliases(&bytes))
+            .transpose()?
+            .unwrap_or_default();
         let mut sector_index = SectorVolumeIndex::default();
         sector_index.rebuild(&sectors, &volumes);
         Ok(Adaptation {
             name,
             positions,
+            voice_channels,
             volumes,
             sectors,
             departure_constraints,
@@ -264,8 +433,11 @@ impl Adaptation {
             settings,
             aircraft,
             airlines,
+            aliases,
             sct_items,
             sector_index,
+            ground: GroundLayout::default(),
+            search_index: SearchIndex::default(),
         })
     }
 
@@ -321,12 +493,47 @@ impl Adaptation {
         self.sector_index.rebuild(&self.sectors, &self.volumes);
     }
 
-    /// The single sector containing `coordinate` at `level_ft`, tie-broken by the lowest
-    /// sector id designator when multiple volumes overlap.
+    /// The single sector containing `coordinate` at pressure altitude `level`, tie-broken
+    /// by the lowest sector id designator when multiple volumes overlap. With a `qnh`,
+    /// volume limits at or below the FIR's transition altitude are taken as altitudes.
     #[must_use]
-    pub fn find_sector(&self, coordinate: Point, level_ft: f32) -> Option<&str> {
+    pub fn find_sector(
+        &self,
+        coordinate: Point,
+        level: Length,
+        qnh: Option<Pressure>,
+    ) -> Option<&str> {
+        let Some(qnh) = qnh else {
+            return self
+                .sector_index
+                .find_sector(&self.sectors, &self.volumes, coordinate, level);
+        };
+
+        self.sector_index.find_sector_by(
+            &self.sectors,
+            &self.volumes,
+            coordinate,
+            |sector, volume| {
+                let transition = self
+                    .settings
+                    .transitions
+                    .transition(None, Some(sectors::sector_fir(sector)));
+                let limit = |limit| {
+                    transition.map_or(limit, |transition| {
+                        transition.limit_pressure_altitude(limit, qnh)
+                    })
+                };
+
+                level >= limit(volume.lower_level) && level < limit(volume.upper_level)
+            },
+        )
+    }
+
+    /// Sectors flown through along `path` of positions and levels, with the entry and
+    /// exit point and level of each.
+    pub fn sector_sequence(&self, path: &[(Point, Length)]) -> Vec<SectorCrossing> {
         self.sector_index
-            .find_sector(&self.sectors, &self.volumes, coordinate, level_ft)
+            .sector_sequence(&self.sectors, &self.volumes, path)
     }
 
     /// All volumes whose lateral border contains `coordinate`, regardless of level.
@@ -342,6 +549,13 @@ impl Adaptation {
             .volumes_near_line(&self.sectors, &self.volumes, line)
     }
 
+    /// All positions using `frequency`, i.e. to find the station of an ATIS frequency.
+    pub fn positions_on_frequency(&self, frequency: Frequency) -> impl Iterator<Item = &Position> {
+        self.positions
+            .values()
+            .filter(move |position| position.frequency == Some(frequency))
+    }
+
     /// Create adaptation from .prf and apply .jsonnet overlays
     pub fn from_prf_with_overlays<P: AsRef<Path>>(
         prf: &Prf,
@@ -354,10 +568,33 @@ impl Adaptation {
     pub fn from_adaptation_toml<P: AsRef<Path>>(adaptation_toml: &P) -> AdaptationResult {
         let adaptation_setup = AdaptationSetup::parse(adaptation_toml.as_ref())?;
 
-        Self::from_prf_with_overlays(
+        let mut adaptation = Self::from_prf_with_themes(
             &Prf::parse(&adaptation_setup.prf, &read(&adaptation_setup.prf)?)?,
-            adaptation_setup.overlays.iter(),
-        )
+            &adaptation_setup.themes,
+        )?;
+        if let Some(magnetic_model) = &adaptation_setup.magnetic_model {
+            adaptation.locations.magnetic_variation = MagneticVariation::from_model(
+                WorldMagneticModel::parse(&read_to_string(magnetic_model)?)?,
+                adaptation_setup.magnetic_model_date,
+            );
+        }
+        let ground_networks = adaptation_setup
+            .ground_networks
+            .iter()
+            .filter_map(|path| {
+                read(path).map_or_else(
+                    |e| {
+                        warn!("Ground network: {e}");
+                        None
+                    },
+                    Some,
+                )
+            })
+            .map(|bytes| parse_ground_network(&bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        adaptation.ground = GroundLayout::from_networks(ground_networks);
+
+        adaptation.apply_jsonnet_overlays(adaptation_setup.overlays.iter())
     }
 }
 
@@ -388,8 +625,19 @@ impl Quantize for Coord {
 mod tests {
     use test_log::test;
 
-    use crate::{adaptation::Adaptation, prf::Prf};
+    use crate::{
+        adaptation::{
+            colours::{Colour, Colours},
+            sectors::{Sector, Sectors, Volume},
+            settings::transition::Transition,
+            Adaptation,
+        },
+        level::feet,
+        prf::Prf,
+    };
+    use geo::{line_string, point};
     use std::{collections::HashMap, fs, path::Path};
+    use uom::si::{f32::Pressure, pressure::hectopascal};
 
     #[test]
     fn test_adaptation_toml() {
@@ -404,6 +652,118 @@ mod tests {
             HashMap::from([("7000".to_string(), "V".to_string())])
         );
         assert!(adaptation.settings.track.vector.enabled);
+        assert_eq!(
+            adaptation
+                .settings
+                .transitions
+                .transition(Some("EDDM"), Some("EDMM"))
+                .map(|transition| transition.level),
+            Some(feet(8_000.0))
+        );
+        assert_eq!(
+            adaptation
+                .aliases
+                .get("sq")
+                .map(|alias| alias.text.as_str()),
+            Some("squawk $squawk")
+        );
+        let alb = adaptation
+            .positions_on_frequency("129.1".parse().unwrap())
+            .next()
+            .unwrap();
+        assert_eq!(alb.id, "ALB");
+        assert_eq!(
+            alb.voice_channel
+                .as_ref()
+                .map(|channel| channel.name.as_str()),
+            Some("EDMM_ALB_CTR")
+        );
+
+        let mut colours = adaptation.colours.clone();
+        let day = colours.sector.active_background;
+        assert!(colours.set_theme("night"));
+        assert_eq!(
+            colours.sector.active_background,
+            Colour::from_rgb(50, 50, 50)
+        );
+        colours.set_brightness(0.5);
+        assert_eq!(
+            colours.sector.active_background,
+            Colour::from_rgb(25, 25, 25)
+        );
+        assert!(colours.set_theme(Colours::DEFAULT_THEME));
+        assert_eq!(
+            colours.sector.active_background,
+            day.interpolate(Colour::from_rgb(0, 0, 0), 0.5)
+        );
+        assert!(!colours.set_theme("dusk"));
+    }
+
+    #[test]
+    fn test_find_sector_qnh() {
+        let border = line_string![
+            (x: 0.0, y: 0.0),
+            (x: 10.0, y: 0.0),
+            (x: 10.0, y: 10.0),
+            (x: 0.0, y: 10.0),
+            (x: 0.0, y: 0.0),
+        ];
+        let sector = |id: &str, volume: &str| Sector {
+            id: id.to_string(),
+            position_priority: vec!["POS".to_string()],
+            runway_filter: vec![],
+            volumes: [volume.to_string()].into_iter().collect(),
+            departure_aerodromes: std::collections::HashSet::default(),
+            arrival_aerodromes: std::collections::HashSet::default(),
+        };
+        let mut adaptation = Adaptation {
+            sectors: Sectors(
+                [
+                    sector("EDMM\u{b7}LOWER", "LOWER"),
+                    sector("EDMM\u{b7}UPPER", "UPPER"),
+                ]
+                .into_iter()
+                .map(|sector| (sector.id.clone(), sector))
+                .collect(),
+            ),
+            volumes: [
+                Volume::new(
+                    "LOWER".to_string(),
+                    feet(0.0),
+                    feet(5_000.0),
+                    border.clone(),
+                ),
+                Volume::new("UPPER".to_string(), feet(5_000.0), feet(20_000.0), border),
+            ]
+            .into_iter()
+            .map(|volume| (volume.id.clone(), volume))
+            .collect(),
+            ..Default::default()
+        };
+        adaptation.settings.transitions.firs.insert(
+            "EDMM".to_string(),
+            Transition {
+                altitude: feet(5_000.0),
+                level: feet(7_000.0),
+            },
+        );
+        adaptation.rebuild_sector_index();
+        let inside = point! { x: 5.0, y: 5.0 };
+        let low_qnh = Some(Pressure::new::<hectopascal>(990.0));
+
+        assert_eq!(
+            adaptation.find_sector(inside, feet(5_100.0), None),
+            Some("EDMM\u{b7}UPPER")
+        );
+        // A050 is at a pressure altitude of about 5 640 ft with a QNH of 990 hPa
+        assert_eq!(
+            adaptation.find_sector(inside, feet(5_100.0), low_qnh),
+            Some("EDMM\u{b7}LOWER")
+        );
+        assert_eq!(
+            adaptation.find_sector(inside, feet(5_700.0), low_qnh),
+            Some("EDMM\u{b7}UPPER")
+        );
     }
 
     #[test]
@@ -426,5 +786,18 @@ mod tests {
             HashMap::from([("7000".to_string(), "V".to_string())])
         );
         assert!(adaptation.settings.track.vector.enabled);
+        assert_eq!(
+            adaptation
+                .locations
+                .airways
+                .segment("Z999", "ERNAS", "GIVMI")
+                .map(|segment| segment.minimum_level),
+            Some(Some(5000))
+        );
+        assert!(adaptation
+            .locations
+            .airways
+            .segment("Y101", "GIVMI", "ERNAS")
+            .is_some());
     }
 }
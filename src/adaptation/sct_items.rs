@@ -43,14 +43,16 @@ impl ColouredLines {
             .lines
             .iter()
             .filter_map(|loc_line| {
+                let mut near = None;
                 let line: LineString = loc_line
                     .points
                     .iter()
                     .filter_map(|loc| {
-                        let point = locations.convert_location(loc);
+                        let point = locations.convert_location_near(loc, near);
                         if point.is_none() {
                             warn!("Could not convert {:?} in .sct {typ} {name}", loc);
                         }
+                        near = point.or(near);
                         point
                     })
                     .dedup()
@@ -107,10 +109,11 @@ fn airway_to_multi_line_string(
     let lines = airways
         .iter()
         .filter_map(|airway| {
-            let line = locations
-                .convert_location(&airway.start)
-                .zip(locations.convert_location(&airway.start))
-                .map(|(start, end)| Line::new(start, end));
+            let line = locations.convert_location(&airway.start).and_then(|start| {
+                locations
+                    .convert_location_near(&airway.end, Some(start))
+                    .map(|end| Line::new(start, end))
+            });
             if line.is_none() {
                 warn!("Could not convert {airway:?} in .sct {typ} {name}");
             }
@@ -0,0 +1,374 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use geo::Point;
+use serde::{Deserialize, Serialize};
+
+use super::lazy_index::LazyIndex;
+use super::locations::index::LocationKind;
+use super::Adaptation;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SearchKind {
+    Location(LocationKind),
+    Airline,
+    Aircraft,
+}
+
+/// How a query matched, better matches compare greater
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum MatchQuality {
+    /// all characters of the query in order, i.e. `MNCH` for `MUNICH`
+    Fuzzy,
+    Substring,
+    /// start of a word other than the first, i.e. `INT` for `MUNICH INTL`
+    WordPrefix,
+    Prefix,
+    Exact,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    pub designator: String,
+    /// name of airports, airlines and aircraft
+    pub name: Option<String>,
+    pub coordinate: Option<Point>,
+    pub quality: MatchQuality,
+}
+
+/// quality, then fewer skipped characters, then shorter keys
+type Rank = (MatchQuality, Reverse<usize>, Reverse<usize>);
+
+#[derive(Clone, Debug)]
+struct Entry {
+    kind: SearchKind,
+    designator: String,
+    name: Option<String>,
+    coordinate: Option<Point>,
+}
+
+/// Uppercased designators, names and callsigns pointing to their entry, sorted for
+/// prefix lookups
+#[derive(Clone, Debug, Default)]
+struct Tree {
+    entries: Vec<Entry>,
+    keys: Vec<(String, usize)>,
+}
+
+fn build_tree(adaptation: &Adaptation) -> Tree {
+    let mut tree = Tree::default();
+    let mut add = |entry: Entry, keys: &[&str]| {
+        let index = tree.entries.len();
+        tree.keys.extend(
+            keys.iter()
+                .filter(|key| !key.is_empty())
+                .map(|key| (key.to_uppercase(), index)),
+        );
+        tree.entries.push(entry);
+    };
+    let locations = &adaptation.locations;
+
+    for (kind, designator, coordinate) in locations
+        .fixes
+        .flat_iter()
+        .map(|(_, fix)| (LocationKind::Fix, &fix.designator, fix.coordinate))
+        .chain(
+            locations
+                .vors
+                .flat_iter()
+                .map(|(_, vor)| (LocationKind::Vor, &vor.designator, vor.coordinate)),
+        )
+        .chain(
+            locations
+                .ndbs
+                .flat_iter()
+                .map(|(_, ndb)| (LocationKind::Ndb, &ndb.designator, ndb.coordinate)),
+        )
+    {
+        add(
+            Entry {
+                kind: SearchKind::Location(kind),
+                designator: designator.clone(),
+                name: None,
+                coordinate: Some(coordinate),
+            },
+            &[designator],
+        );
+    }
+    for airport in locations.airports.values() {
+        add(
+            Entry {
+                kind: SearchKind::Location(LocationKind::Airport),
+                designator: airport.designator.clone(),
+                name: airport.name.clone(),
+                coordinate: Some(airport.coordinate),
+            },
+            &[&airport.designator, airport.name.as_deref().unwrap_or("")],
+        );
+    }
+    for airline in adaptation.airlines.values() {
+        add(
+            Entry {
+                kind: SearchKind::Airline,
+                designator: airline.designator.clone(),
+                name: Some(airline.name.clone()),
+                coordinate: None,
+            },
+            &[&airline.designator, &airline.callsign, &airline.name],
+        );
+    }
+    for aircraft in adaptation.aircraft.0.values() {
+        add(
+            Entry {
+                kind: SearchKind::Aircraft,
+                designator: aircraft.designator.clone(),
+                name: Some(format!("{} {}", aircraft.manufacturer, aircraft.name)),
+                coordinate: None,
+            },
+            &[&aircraft.designator, &aircraft.name, &aircraft.manufacturer],
+        );
+    }
+
+    tree.keys.sort_unstable();
+    tree
+}
+
+/// Quality of `key` matching `query` below a prefix match, with the number of skipped
+/// characters for fuzzy matches
+fn match_key(key: &str, query: &str) -> Option<(MatchQuality, usize)> {
+    if let Some(position) = key.find(query) {
+        let word_start = key[..position].ends_with(|c: char| !c.is_alphanumeric());
+        return Some(if word_start {
+            (MatchQuality::WordPrefix, 0)
+        } else {
+            (MatchQuality::Substring, 0)
+        });
+    }
+
+    let mut remaining = query.chars().peekable();
+    let (mut skipped, mut started) = (0, false);
+    for c in key.chars() {
+        match remaining.peek() {
+            Some(&next) if next == c => {
+                remaining.next();
+                started = true;
+            }
+            Some(_) if started => skipped += 1,
+            Some(_) => {}
+            None => break,
+        }
+    }
+    remaining
+        .peek()
+        .is_none()
+        .then_some((MatchQuality::Fuzzy, skipped))
+}
+
+fn consider(best: &mut HashMap<usize, Rank>, index: usize, rank: Rank) {
+    best.entry(index)
+        .and_modify(|current| *current = (*current).max(rank))
+        .or_insert(rank);
+}
+
+/// Typeahead index over fixes, navaids, airports, airlines and aircraft types.
+///
+/// A `LazyIndex`, call `Adaptation::rebuild_search_index` after mutating the
+/// adaptation.
+#[derive(Clone, Debug, Default)]
+pub struct SearchIndex(LazyIndex<Tree>);
+
+impl SearchIndex {
+    fn get_or_build<'a>(&'a self, adaptation: &Adaptation) -> &'a Tree {
+        self.0.get_or_build(|| build_tree(adaptation))
+    }
+
+    /// Forces a rebuild, overwriting any already-cached tree.
+    pub(crate) fn rebuild(&mut self, adaptation: &Adaptation) {
+        self.0.rebuild(build_tree(adaptation));
+    }
+
+    /// Up to `limit` entries matching `query`, best first. Prefix matches are looked up
+    /// in the sorted keys, slower substring and fuzzy matches only scanned for while
+    /// fewer than `limit` results are found.
+    pub fn search(&self, adaptation: &Adaptation, query: &str, limit: usize) -> Vec<SearchResult> {
+        let query = query.trim().to_uppercase();
+        if query.is_empty() || limit == 0 {
+            return vec![];
+        }
+        let tree = self.get_or_build(adaptation);
+
+        // best rank per entry index
+        let mut best: HashMap<usize, Rank> = HashMap::new();
+        let start = tree
+            .keys
+            .partition_point(|(key, _)| key.as_str() < query.as_str());
+        for (key, index) in tree.keys[start..]
+            .iter()
+            .take_while(|(key, _)| key.starts_with(&query))
+        {
+            let quality = if *key == query {
+                MatchQuality::Exact
+            } else {
+                MatchQuality::Prefix
+            };
+            consider(&mut best, *index, (quality, Reverse(0), Reverse(key.len())));
+        }
+
+        for fuzzy in [false, true] {
+            if best.len() >= limit {
+                break;
+            }
+            for (key, index) in &tree.keys {
+                match match_key(key, &query) {
+                    Some((quality, skipped)) if fuzzy == (quality == MatchQuality::Fuzzy) => {
+                        consider(
+                            &mut best,
+                            *index,
+                            (quality, Reverse(skipped), Reverse(key.len())),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut ranked = best
+            .into_iter()
+            .map(|(index, rank)| (rank, &tree.entries[index]))
+            .collect::<Vec<_>>();
+        ranked.sort_by(|(a_rank, a), (b_rank, b)| {
+            b_rank
+                .cmp(a_rank)
+                .then_with(|| a.designator.cmp(&b.designator))
+        });
+
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|((quality, _, _), entry)| SearchResult {
+                kind: entry.kind,
+                designator: entry.designator.clone(),
+                name: entry.name.clone(),
+                coordinate: entry.coordinate,
+                quality,
+            })
+            .collect()
+    }
+}
+
+impl Adaptation {
+    /// Typeahead search over fixes, navaids, airports, airlines and aircraft types by
+    /// designator, name, callsign and manufacturer, see `SearchIndex::search`.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        self.search_index.search(self, query, limit)
+    }
+
+    /// Rebuilds the search index, only needed after mutating the adaptation once it has
+    /// been searched.
+    pub fn rebuild_search_index(&mut self) {
+        let mut index = std::mem::take(&mut self.search_index);
+        index.rebuild(self);
+        self.search_index = index;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::point;
+
+    use crate::adaptation::{
+        icao::{Aircraft, AircraftMap, AircraftType, Airline, EngineType, Wtc},
+        locations::{
+            index::LocationKind,
+            test_fixtures::{airport, fix_entry},
+            Locations,
+        },
+        Adaptation,
+    };
+
+    use super::{MatchQuality, SearchKind};
+
+    #[test]
+    fn test_search() {
+        let fix = |designator: &str| fix_entry(designator, 11.0, 48.0);
+        let mut eddm = airport("EDDM", 11.8, 48.4);
+        eddm.1.name = Some("Munich Intl".to_string());
+        let adaptation = Adaptation {
+            locations: Locations {
+                fixes: [fix("MUN"), fix("MUNAK"), fix("AMUNI"), fix("ROKIL")]
+                    .into_iter()
+                    .collect(),
+                airports: [eddm].into_iter().collect(),
+                ..Default::default()
+            },
+            airlines: [(
+                "DLH".to_string(),
+                Airline {
+                    designator: "DLH".to_string(),
+                    name: "Lufthansa".to_string(),
+                    callsign: "LUFTHANSA".to_string(),
+                    country: "Germany".to_string(),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            aircraft: AircraftMap(
+                [(
+                    "A320".to_string(),
+                    Aircraft {
+                        designator: "A320".to_string(),
+                        wtc: Wtc::MEDIUM,
+                        aircrafttype: AircraftType::LANDPLANE,
+                        num_engines: 2,
+                        enginetype: EngineType::JET,
+                        manufacturer: "AIRBUS".to_string(),
+                        name: "A-320".to_string(),
+                    },
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let found = |query: &str, limit: usize| {
+            adaptation
+                .search(query, limit)
+                .into_iter()
+                .map(|result| (result.designator, result.quality))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            found("mun", 10),
+            [
+                ("MUN".to_string(), MatchQuality::Exact),
+                ("MUNAK".to_string(), MatchQuality::Prefix),
+                ("EDDM".to_string(), MatchQuality::Prefix),
+                ("AMUNI".to_string(), MatchQuality::Substring),
+            ]
+        );
+        // prefix matches suffice, no scan for substrings
+        assert_eq!(found("mun", 2).len(), 2);
+        assert_eq!(
+            found("intl", 10),
+            [("EDDM".to_string(), MatchQuality::WordPrefix)]
+        );
+        assert_eq!(
+            found("lufth", 10),
+            [("DLH".to_string(), MatchQuality::Prefix)]
+        );
+        assert_eq!(
+            found("rkl", 10),
+            [("ROKIL".to_string(), MatchQuality::Fuzzy)]
+        );
+        assert!(found(" ", 10).is_empty());
+
+        let airbus = adaptation.search("airbus", 1);
+        assert_eq!(airbus[0].kind, SearchKind::Aircraft);
+        assert_eq!(airbus[0].name.as_deref(), Some("AIRBUS A-320"));
+        let eddm = adaptation.search("EDDM", 1);
+        assert_eq!(eddm[0].kind, SearchKind::Location(LocationKind::Airport));
+        assert_eq!(eddm[0].coordinate, Some(point! { x: 11.8, y: 48.4 }));
+    }
+}
@@ -1,13 +1,19 @@
 use std::collections::HashMap;
-use std::sync::OnceLock;
 
-use geo::{BoundingRect as _, Contains as _, Line, Point};
+use geo::{
+    line_intersection::line_intersection, BoundingRect as _, Contains as _, Line, LineIntersection,
+    Point,
+};
 use rstar::{
     primitives::{GeomWithData, Rectangle},
     RTree, AABB,
 };
+use serde::{Deserialize, Serialize};
+use uom::si::f32::Length;
 
+use super::lazy_index::LazyIndex;
 use super::sectors::{Sectors, Volume};
+use crate::level;
 
 type Entry = GeomWithData<Rectangle<[f64; 2]>, (String, Volume)>;
 
@@ -37,25 +43,64 @@ fn build_tree(sectors: &Sectors, volumes: &HashMap<String, Volume>) -> RTree<Ent
     RTree::bulk_load(entries)
 }
 
+/// Stretch of a path flown through one sector
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectorCrossing {
+    pub sector: String,
+    pub entry: Point,
+    #[serde(with = "level::as_feet")]
+    pub entry_level: Length,
+    pub exit: Point,
+    #[serde(with = "level::as_feet")]
+    pub exit_level: Length,
+}
+
+/// Fractions of `line` where it crosses the lateral border of `volume`
+fn lateral_crossings(line: Line, volume: &Volume) -> impl Iterator<Item = f64> + '_ {
+    let delta = line.delta();
+    let length_squared = delta.x * delta.x + delta.y * delta.y;
+
+    volume
+        .lateral_border
+        .exterior()
+        .lines()
+        .filter(move |_| length_squared > 0.0)
+        .filter_map(move |border| line_intersection(line, border))
+        .flat_map(|intersection| match intersection {
+            LineIntersection::SinglePoint { intersection, .. } => vec![intersection],
+            LineIntersection::Collinear { intersection } => {
+                vec![intersection.start, intersection.end]
+            }
+        })
+        .map(move |coord| {
+            let offset = coord - line.start;
+            (offset.x * delta.x + offset.y * delta.y) / length_squared
+        })
+}
+
+/// Fractions of a leg from `from_level` to `to_level` where it crosses the lower or
+/// upper level of `volume`
+fn vertical_crossings(
+    from_level: Length,
+    to_level: Length,
+    volume: &Volume,
+) -> impl Iterator<Item = f64> {
+    let climb = to_level - from_level;
+
+    [volume.lower_level, volume.upper_level]
+        .into_iter()
+        .filter(move |_| climb.value.abs() > f32::EPSILON)
+        .map(move |level| f64::from((level - from_level).value) / f64::from(climb.value))
+}
+
 /// Spatial index over sector volumes, keyed by each volume's lateral bounding box.
 ///
 /// Only sectors with a non-empty `position_priority` are indexed.
 ///
-/// If `sectors`/`volumes` are mutated *after* the index has already been queried once,
-/// the cached tree goes stale, call `Adaptation::rebuild_sector_index` explicitly after
-/// such a mutation.
-#[derive(Debug, Default)]
-pub struct SectorVolumeIndex(OnceLock<RTree<Entry>>);
-
-impl Clone for SectorVolumeIndex {
-    fn clone(&self) -> Self {
-        let cell = OnceLock::new();
-        if let Some(tree) = self.0.get() {
-            cell.set(tree.clone()).ok();
-        }
-        Self(cell)
-    }
-}
+/// A `LazyIndex`, call `Adaptation::rebuild_sector_index` after mutating `sectors` or
+/// `volumes`.
+#[derive(Clone, Debug, Default)]
+pub struct SectorVolumeIndex(LazyIndex<RTree<Entry>>);
 
 impl SectorVolumeIndex {
     fn get_or_build<'a>(
@@ -63,13 +108,12 @@ impl SectorVolumeIndex {
         sectors: &Sectors,
         volumes: &HashMap<String, Volume>,
     ) -> &'a RTree<Entry> {
-        self.0.get_or_init(|| build_tree(sectors, volumes))
+        self.0.get_or_build(|| build_tree(sectors, volumes))
     }
 
     /// Forces a rebuild, overwriting any already-cached tree.
     pub(crate) fn rebuild(&mut self, sectors: &Sectors, volumes: &HashMap<String, Volume>) {
-        self.0 = OnceLock::new();
-        self.0.set(build_tree(sectors, volumes)).ok();
+        self.0.rebuild(build_tree(sectors, volumes));
     }
 
     /// All volumes whose lateral border contains `coordinate`, regardless of level --
@@ -104,23 +148,111 @@ impl SectorVolumeIndex {
             .map(|entry| &entry.data)
     }
 
-    /// Level-aware lookup of the sector containing `coordinate` at `level_ft`.
+    /// Level-aware lookup of the sector containing `coordinate` at `level`.
     pub fn find_sector(
         &self,
         sectors: &Sectors,
         volumes: &HashMap<String, Volume>,
         coordinate: Point,
-        // TODO uom?
-        level_ft: f32,
+        level: Length,
+    ) -> Option<&str> {
+        self.find_sector_by(sectors, volumes, coordinate, |_, volume| {
+            level >= volume.lower_level && level < volume.upper_level
+        })
+    }
+
+    /// Lookup of the sector containing `coordinate` with a volume whose levels match
+    /// `contains_level`, called with the sector id and volume.
+    pub fn find_sector_by(
+        &self,
+        sectors: &Sectors,
+        volumes: &HashMap<String, Volume>,
+        coordinate: Point,
+        contains_level: impl Fn(&str, &Volume) -> bool,
     ) -> Option<&str> {
         self.volumes_at(sectors, volumes, coordinate)
-            .filter(|(_, volume)| {
-                level_ft >= volume.lower_level as f32 && level_ft < volume.upper_level as f32
-            })
+            .filter(|(id, volume)| contains_level(id, volume))
             // stable return value in case of overlapping data
             .min_by_key(|(id, _)| id.as_str())
             .map(|(id, _)| id.as_str())
     }
+
+    /// Sectors flown through along `path` of positions and levels, with levels
+    /// interpolated linearly along each leg. Parts of the path outside of all sectors
+    /// are left out.
+    pub fn sector_sequence(
+        &self,
+        sectors: &Sectors,
+        volumes: &HashMap<String, Volume>,
+        path: &[(Point, Length)],
+    ) -> Vec<SectorCrossing> {
+        const EPSILON: f64 = 1e-9;
+
+        let mut sequence: Vec<SectorCrossing> = Vec::new();
+        // whether the last crossing extends up to the current part of the path
+        let mut open = false;
+        let mut visit = |sector: Option<&str>, (entry, entry_level), (exit, exit_level)| {
+            match sector {
+                Some(sector)
+                    if open && sequence.last().is_some_and(|last| last.sector == sector) =>
+                {
+                    if let Some(last) = sequence.last_mut() {
+                        last.exit = exit;
+                        last.exit_level = exit_level;
+                    }
+                }
+                Some(sector) => sequence.push(SectorCrossing {
+                    sector: sector.to_string(),
+                    entry,
+                    entry_level,
+                    exit,
+                    exit_level,
+                }),
+                None => (),
+            }
+            open = sector.is_some();
+        };
+
+        if let [(point, level)] = path {
+            visit(
+                self.find_sector(sectors, volumes, *point, *level),
+                (*point, *level),
+                (*point, *level),
+            );
+        }
+
+        for leg in path.windows(2) {
+            let ((from, from_level), (to, to_level)) = (leg[0], leg[1]);
+            let line = Line::new(from, to);
+            let at = |fraction: f64| {
+                (
+                    Point::from(line.start + line.delta() * fraction),
+                    from_level + (to_level - from_level) * fraction as f32,
+                )
+            };
+
+            let mut fractions = vec![0.0, 1.0];
+            for (_, volume) in self.volumes_near_line(sectors, volumes, line) {
+                fractions.extend(lateral_crossings(line, volume));
+                fractions.extend(vertical_crossings(from_level, to_level, volume));
+            }
+            fractions.retain(|fraction| (0.0..=1.0).contains(fraction));
+            fractions.sort_by(f64::total_cmp);
+            fractions.dedup_by(|a, b| (*a - *b).abs() < EPSILON);
+
+            for part in fractions.windows(2) {
+                let (start, end) = (part[0], part[1]);
+                let (point, level) = at(f64::midpoint(start, end));
+                visit(
+                    self.find_sector(sectors, volumes, point, level),
+                    at(start),
+                    at(end),
+                );
+            }
+        }
+
+        sequence
+    }
 }
 
 #[cfg(test)]
@@ -129,8 +261,9 @@ mod tests {
 
     use super::*;
     use crate::adaptation::sectors::Sector;
+    use crate::level::feet;
 
-    fn square_volume(id: &str, min: (f64, f64), max: (f64, f64), lower: u32, upper: u32) -> Volume {
+    fn square_volume(id: &str, min: (f64, f64), max: (f64, f64), lower: f32, upper: f32) -> Volume {
         let border = line_string![
             (x: min.0, y: min.1),
             (x: max.0, y: min.1),
@@ -138,7 +271,7 @@ mod tests {
             (x: min.0, y: max.1),
             (x: min.0, y: min.1),
         ];
-        Volume::new(id.to_string(), lower, upper, border)
+        Volume::new(id.to_string(), feet(lower), feet(upper), border)
     }
 
     fn sector(id: &str, volumes: &[&str]) -> Sector {
@@ -166,62 +299,77 @@ mod tests {
 
     #[test]
     fn point_inside_volume_returns_sector() {
-        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 0, 20_000);
+        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 0.0, 20_000.0);
         let (sectors, volumes, index) = built(vec![(sector("SEC1", &["VOL1"]), vol)]);
 
         assert_eq!(
-            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, 5000.0),
+            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, feet(5000.0)),
             Some("SEC1")
         );
     }
 
     #[test]
     fn point_outside_volume_returns_none() {
-        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 0, 20_000);
+        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 0.0, 20_000.0);
         let (sectors, volumes, index) = built(vec![(sector("SEC1", &["VOL1"]), vol)]);
 
         assert_eq!(
-            index.find_sector(&sectors, &volumes, point! { x: 50.0, y: 50.0 }, 5000.0),
+            index.find_sector(
+                &sectors,
+                &volumes,
+                point! { x: 50.0, y: 50.0 },
+                feet(5000.0)
+            ),
             None
         );
     }
 
     #[test]
     fn level_outside_range_returns_none() {
-        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 10_000, 20_000);
+        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 10_000.0, 20_000.0);
         let (sectors, volumes, index) = built(vec![(sector("SEC1", &["VOL1"]), vol)]);
 
         assert_eq!(
-            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, 5000.0),
+            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, feet(5000.0)),
             None
         );
         assert_eq!(
-            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, 15_000.0),
+            index.find_sector(
+                &sectors,
+                &volumes,
+                point! { x: 5.0, y: 5.0 },
+                feet(15_000.0)
+            ),
             Some("SEC1")
         );
         // upper_level is exclusive
         assert_eq!(
-            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, 20_000.0),
+            index.find_sector(
+                &sectors,
+                &volumes,
+                point! { x: 5.0, y: 5.0 },
+                feet(20_000.0)
+            ),
             None
         );
     }
 
     #[test]
     fn sector_without_position_priority_is_not_indexed() {
-        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 0, 20_000);
+        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 0.0, 20_000.0);
         let mut sec = sector("SEC1", &["VOL1"]);
         sec.position_priority = vec![];
         let (sectors, volumes, index) = built(vec![(sec, vol)]);
 
         assert_eq!(
-            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, 5000.0),
+            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, feet(5000.0)),
             None
         );
     }
 
     #[test]
     fn volumes_near_line_finds_crossing_candidate() {
-        let vol = square_volume("VOL1", (5.0, -5.0), (15.0, 5.0), 0, 20_000);
+        let vol = square_volume("VOL1", (5.0, -5.0), (15.0, 5.0), 0.0, 20_000.0);
         let (sectors, volumes, index) = built(vec![(sector("SEC1", &["VOL1"]), vol)]);
 
         let crossing = Line::new(point! { x: 0.0, y: 0.0 }, point! { x: 20.0, y: 0.0 });
@@ -240,21 +388,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sector_sequence_crosses_lateral_and_vertical_borders() {
+        let (sectors, volumes, index) = built(vec![
+            (
+                sector("LOWER", &["VOL1"]),
+                square_volume("VOL1", (0.0, -5.0), (10.0, 5.0), 0.0, 20_000.0),
+            ),
+            (
+                sector("UPPER", &["VOL2"]),
+                square_volume("VOL2", (0.0, -5.0), (10.0, 5.0), 20_000.0, 40_000.0),
+            ),
+            (
+                sector("EAST", &["VOL3"]),
+                square_volume("VOL3", (10.0, -5.0), (20.0, 5.0), 0.0, 40_000.0),
+            ),
+        ]);
+
+        let sequence = index.sector_sequence(
+            &sectors,
+            &volumes,
+            &[
+                (point! { x: -5.0, y: 0.0 }, feet(10_000.0)),
+                (point! { x: 4.0, y: 0.0 }, feet(10_000.0)),
+                (point! { x: 16.0, y: 0.0 }, feet(40_000.0)),
+            ],
+        );
+
+        assert_eq!(
+            sequence
+                .iter()
+                .map(|crossing| (
+                    crossing.sector.as_str(),
+                    crossing.entry,
+                    crossing.entry_level,
+                    crossing.exit,
+                    crossing.exit_level
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "LOWER",
+                    point! { x: 0.0, y: 0.0 },
+                    feet(10_000.0),
+                    point! { x: 8.0, y: 0.0 },
+                    feet(20_000.0),
+                ),
+                (
+                    "UPPER",
+                    point! { x: 8.0, y: 0.0 },
+                    feet(20_000.0),
+                    point! { x: 10.0, y: 0.0 },
+                    feet(25_000.0),
+                ),
+                (
+                    "EAST",
+                    point! { x: 10.0, y: 0.0 },
+                    feet(25_000.0),
+                    point! { x: 16.0, y: 0.0 },
+                    feet(40_000.0),
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn rebuild_overwrites_a_stale_cached_tree() {
-        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 0, 20_000);
+        let vol = square_volume("VOL1", (0.0, 0.0), (10.0, 10.0), 0.0, 20_000.0);
         let (sectors, volumes, mut index) = built(vec![(sector("SEC1", &["VOL1"]), vol)]);
         assert_eq!(
-            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, 5000.0),
+            index.find_sector(&sectors, &volumes, point! { x: 5.0, y: 5.0 }, feet(5000.0)),
             Some("SEC1")
         );
 
-        let vol2 = square_volume("VOL2", (0.0, 0.0), (10.0, 10.0), 0, 20_000);
+        let vol2 = square_volume("VOL2", (0.0, 0.0), (10.0, 10.0), 0.0, 20_000.0);
         let (sectors2, volumes2, _) = built(vec![(sector("SEC2", &["VOL2"]), vol2)]);
         index.rebuild(&sectors2, &volumes2);
 
         assert_eq!(
-            index.find_sector(&sectors2, &volumes2, point! { x: 5.0, y: 5.0 }, 5000.0),
+            index.find_sector(
+                &sectors2,
+                &volumes2,
+                point! { x: 5.0, y: 5.0 },
+                feet(5000.0)
+            ),
             Some("SEC2")
         );
     }
@@ -8,11 +8,12 @@ use geo::{Coord, Line, LineString, Polygon, Winding};
 use multimap::MultiMap;
 use serde::{Deserialize, Serialize};
 use tracing::warn;
+use uom::si::f32::Length;
 
 use crate::{
     adaptation::Quantize as _,
     ese::{self, Ese},
-    TwoKeyMultiMap,
+    level, TwoKeyMultiMap,
 };
 
 use super::maps::active::RunwayIdentifier;
@@ -32,16 +33,18 @@ impl<'a> IntoIterator for &'a Sectors {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Volume {
     pub id: String,
-    pub lower_level: u32,
-    pub upper_level: u32,
+    #[serde(with = "level::as_feet")]
+    pub lower_level: Length,
+    #[serde(with = "level::as_feet")]
+    pub upper_level: Length,
     pub lateral_border: Polygon,
     private: PhantomData<()>,
 }
 impl Volume {
     pub fn new(
         id: String,
-        lower_level: u32,
-        upper_level: u32,
+        lower_level: Length,
+        upper_level: Length,
         mut lateral_border: LineString,
     ) -> Self {
         lateral_border.make_ccw_winding();
@@ -55,6 +58,11 @@ impl Volume {
     }
 }
 
+/// FIR of an ESE sector id, i.e. `EDMM` for `EDMM·EDMMALB·000·105`
+pub fn sector_fir(sector: &str) -> &str {
+    sector.split('\u{b7}').next().unwrap_or(sector)
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Sector {
     pub id: String,
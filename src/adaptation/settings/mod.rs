@@ -1,4 +1,5 @@
 pub mod track;
+pub mod transition;
 
 use std::{collections::HashMap, path::PathBuf};
 
@@ -7,9 +8,15 @@ use tracing::warn;
 use uom::si::time::minute;
 use uom::si::{f32::Time, time::second};
 
-use crate::{prf::Prf, squawks::SquawksJson, symbology::Symbology, topsky::Topsky};
+use crate::{
+    prf::Prf,
+    squawks::SquawksJson,
+    symbology::{Symbology, SymbologyAttribute, SymbologyFolder},
+    topsky::Topsky,
+};
 
 use self::track::TrackSettings;
+use self::transition::TransitionSettings;
 
 use super::{line_styles::LineStyle, Alignment};
 
@@ -57,33 +64,17 @@ impl LineSettings {
     const DEFAULT_LOW_AIRWAY_STYLE: &'static str = LineStyle::SOLID;
 
     pub fn from_euroscope(symbology: &Symbology) -> Self {
-        let runway_centreline = symbology
-            .items
-            .get(&("Runways".to_string(), "centerline".to_string()));
-        let geo = symbology
-            .items
-            .get(&("Geo".to_string(), "line".to_string()));
-        let sid = symbology
-            .items
-            .get(&("Sids".to_string(), "line".to_string()));
-        let star = symbology
-            .items
-            .get(&("Stars".to_string(), "line".to_string()));
-        let high_airways = symbology
-            .items
-            .get(&("High airways".to_string(), "line".to_string()));
-        let low_airways = symbology
-            .items
-            .get(&("Low airways".to_string(), "line".to_string()));
-        let artcc_high = symbology
-            .items
-            .get(&("ARTCC high boundary".to_string(), "line".to_string()));
-        let artcc = symbology
-            .items
-            .get(&("ARTCC boundary".to_string(), "line".to_string()));
-        let artcc_low = symbology
-            .items
-            .get(&("ARTCC low boundary".to_string(), "line".to_string()));
+        let runway_centreline =
+            symbology.item(SymbologyFolder::Runways, SymbologyAttribute::Centerline);
+        let geo = symbology.item(SymbologyFolder::Geo, SymbologyAttribute::Line);
+        let sid = symbology.item(SymbologyFolder::Sids, SymbologyAttribute::Line);
+        let star = symbology.item(SymbologyFolder::Stars, SymbologyAttribute::Line);
+        let high_airways = symbology.item(SymbologyFolder::HighAirways, SymbologyAttribute::Line);
+        let low_airways = symbology.item(SymbologyFolder::LowAirways, SymbologyAttribute::Line);
+        let artcc_high =
+            symbology.item(SymbologyFolder::ArtccHighBoundary, SymbologyAttribute::Line);
+        let artcc = symbology.item(SymbologyFolder::ArtccBoundary, SymbologyAttribute::Line);
+        let artcc_low = symbology.item(SymbologyFolder::ArtccLowBoundary, SymbologyAttribute::Line);
         Self {
             runway_centreline: runway_centreline.map_or(
                 LineStyle {
@@ -240,21 +231,11 @@ impl LabelSettings {
     const DEFAULT_FONT_SIZE: f32 = 3.0;
 
     pub fn from_euroscope(symbology: &Symbology) -> Self {
-        let runway = symbology
-            .items
-            .get(&("Runways".to_string(), "name".to_string()));
-        let fix = symbology
-            .items
-            .get(&("Fixes".to_string(), "name".to_string()));
-        let vor = symbology
-            .items
-            .get(&("VORs".to_string(), "name".to_string()));
-        let ndb = symbology
-            .items
-            .get(&("NDBs".to_string(), "name".to_string()));
-        let airport = symbology
-            .items
-            .get(&("Airports".to_string(), "name".to_string()));
+        let runway = symbology.item(SymbologyFolder::Runways, SymbologyAttribute::Name);
+        let fix = symbology.item(SymbologyFolder::Fixes, SymbologyAttribute::Name);
+        let vor = symbology.item(SymbologyFolder::Vors, SymbologyAttribute::Name);
+        let ndb = symbology.item(SymbologyFolder::Ndbs, SymbologyAttribute::Name);
+        let airport = symbology.item(SymbologyFolder::Airports, SymbologyAttribute::Name);
         Self {
             fix_alignment: fix.map(|item| item.text_alignment).unwrap_or_default(),
             fix_font_size: fix.map_or(Self::DEFAULT_FONT_SIZE, |item| item.font_size_symbol_scale)
@@ -459,6 +440,8 @@ pub struct Settings {
     pub ssr: SsrSettings,
     pub asr_files: Vec<PathBuf>,
     pub coordination: CoordinationSettings,
+    #[serde(default)]
+    pub transitions: TransitionSettings,
 }
 
 impl Settings {
@@ -480,6 +463,7 @@ impl Settings {
                 .unwrap_or_default(),
             asr_files: (1..10).filter_map(|i| prf.recent_path(i)).collect(),
             coordination: CoordinationSettings::default(),
+            transitions: TransitionSettings::default(),
         }
     }
 }
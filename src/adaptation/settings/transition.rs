@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uom::si::f32::{Length, Pressure};
+
+use crate::level::{self, altitude, flight_level, pressure_altitude, IndicatedLevel};
+
+/// Transition altitude and level of an airport or FIR
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transition {
+    #[serde(with = "level::as_feet")]
+    pub altitude: Length,
+    /// lowest usable flight level
+    #[serde(with = "level::as_feet")]
+    pub level: Length,
+}
+
+impl Transition {
+    /// Level of an aircraft at `pressure_altitude`, below the transition level as an
+    /// altitude corrected with `qnh` if known.
+    pub fn indicated_level(
+        &self,
+        pressure_altitude: Length,
+        qnh: Option<Pressure>,
+    ) -> IndicatedLevel {
+        if pressure_altitude >= self.level {
+            IndicatedLevel::FlightLevel(flight_level(pressure_altitude))
+        } else {
+            IndicatedLevel::Altitude(
+                qnh.map_or(pressure_altitude, |qnh| altitude(pressure_altitude, qnh)),
+            )
+        }
+    }
+
+    /// Published level, i.e. of a sector boundary or COPX, which is an altitude at or
+    /// below the transition altitude.
+    pub fn indicated_limit(&self, limit: Length) -> IndicatedLevel {
+        if limit <= self.altitude {
+            IndicatedLevel::Altitude(limit)
+        } else {
+            IndicatedLevel::FlightLevel(flight_level(limit))
+        }
+    }
+
+    /// Pressure altitude of a published level given `qnh`.
+    pub fn limit_pressure_altitude(&self, limit: Length, qnh: Pressure) -> Length {
+        match self.indicated_limit(limit) {
+            IndicatedLevel::Altitude(altitude) => pressure_altitude(altitude, qnh),
+            IndicatedLevel::FlightLevel(_) => limit,
+        }
+    }
+}
+
+/// Transition altitudes and levels, not part of the EuroScope files and thus only set
+/// by overlays
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TransitionSettings {
+    /// used where neither the airport nor the FIR define one
+    #[serde(default)]
+    pub default: Option<Transition>,
+    /// keyed by FIR, i.e. `EDMM`
+    #[serde(default)]
+    pub firs: HashMap<String, Transition>,
+    /// keyed by airport designator
+    #[serde(default)]
+    pub airports: HashMap<String, Transition>,
+}
+
+impl TransitionSettings {
+    /// Transition of `airport`, falling back to the one of `fir` and then the default.
+    pub fn transition(&self, airport: Option<&str>, fir: Option<&str>) -> Option<&Transition> {
+        airport
+            .and_then(|airport| self.airports.get(airport))
+            .or_else(|| fir.and_then(|fir| self.firs.get(fir)))
+            .or(self.default.as_ref())
+    }
+
+    /// Published level as displayed, flight levels where no transition is known.
+    pub fn indicated_limit(
+        &self,
+        limit: Length,
+        airport: Option<&str>,
+        fir: Option<&str>,
+    ) -> IndicatedLevel {
+        self.transition(airport, fir).map_or_else(
+            || IndicatedLevel::FlightLevel(flight_level(limit)),
+            |transition| transition.indicated_limit(limit),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uom::si::{f32::Pressure, length::foot, pressure::hectopascal};
+
+    use crate::level::{feet, IndicatedLevel};
+
+    use super::{Transition, TransitionSettings};
+
+    #[test]
+    fn test_transition() {
+        let edmm = Transition {
+            altitude: feet(5_000.0),
+            level: feet(7_000.0),
+        };
+        let transitions = TransitionSettings {
+            default: None,
+            firs: [("EDMM".to_string(), edmm)].into_iter().collect(),
+            airports: [(
+                "EDDM".to_string(),
+                Transition {
+                    altitude: feet(6_000.0),
+                    level: feet(8_000.0),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let qnh = Pressure::new::<hectopascal>(1003.25);
+
+        assert_eq!(
+            transitions.transition(Some("EDDN"), Some("EDMM")),
+            Some(&edmm)
+        );
+        assert_eq!(
+            transitions
+                .transition(Some("EDDM"), Some("EDMM"))
+                .map(|transition| transition.altitude),
+            Some(feet(6_000.0))
+        );
+        assert_eq!(transitions.transition(None, Some("LOVV")), None);
+        assert_eq!(
+            transitions.indicated_limit(feet(5_000.0), None, Some("EDMM")),
+            IndicatedLevel::Altitude(feet(5_000.0))
+        );
+        assert_eq!(
+            transitions.indicated_limit(feet(5_000.0), None, Some("LOVV")),
+            IndicatedLevel::FlightLevel(50)
+        );
+        assert_eq!(
+            edmm.indicated_level(feet(7_000.0), Some(qnh)),
+            IndicatedLevel::FlightLevel(70)
+        );
+        let IndicatedLevel::Altitude(altitude) = edmm.indicated_level(feet(5_000.0), Some(qnh))
+        else {
+            panic!("expected an altitude below the transition level");
+        };
+        assert_eq!(altitude.get::<foot>().round() as i32, 4_735);
+        assert_eq!(
+            edmm.limit_pressure_altitude(feet(10_000.0), qnh),
+            feet(10_000.0)
+        );
+        assert_eq!(
+            edmm.limit_pressure_altitude(feet(4_735.0), qnh)
+                .get::<foot>()
+                .round() as i32,
+            5_000
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! A* search shared by the airway and taxiway graphs.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+    hash::Hash,
+};
+
+/// Open A* node ordered by its estimated total distance
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct OpenNode<N> {
+    estimate: f64,
+    node: N,
+}
+
+impl<N: Eq> Eq for OpenNode<N> {}
+
+impl<N: Ord> PartialOrd for OpenNode<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N: Ord> Ord for OpenNode<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.estimate
+            .total_cmp(&other.estimate)
+            .then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+/// Shortest path from `start` to `end` by A*, as each node after `start` with the
+/// edge taken to reach it. `neighbours` yields the node, edge and distance of the
+/// edges leaving a node, `remaining` must not overestimate the distance to `end`.
+/// `None` if `end` cannot be reached.
+pub(crate) fn shortest_path<N, E, I>(
+    start: N,
+    end: N,
+    mut neighbours: impl FnMut(N) -> I,
+    remaining: impl Fn(N) -> f64,
+) -> Option<Vec<(N, E)>>
+where
+    N: Copy + Hash + Ord,
+    E: Clone,
+    I: IntoIterator<Item = (N, E, f64)>,
+{
+    let mut distances = HashMap::from([(start, 0.0)]);
+    let mut previous: HashMap<N, (N, E)> = HashMap::new();
+    let mut open = BinaryHeap::from([Reverse(OpenNode {
+        estimate: remaining(start),
+        node: start,
+    })]);
+    let mut closed = HashSet::new();
+
+    while let Some(Reverse(OpenNode { node: current, .. })) = open.pop() {
+        if current == end {
+            let mut path = vec![];
+            let mut node = end;
+            while let Some((prev, edge)) = previous.get(&node) {
+                path.push((node, edge.clone()));
+                node = *prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if !closed.insert(current) {
+            continue;
+        }
+
+        for (to, edge, length) in neighbours(current) {
+            if closed.contains(&to) {
+                continue;
+            }
+            let distance = distances[&current] + length;
+            if distances.get(&to).is_none_or(|&known| distance < known) {
+                distances.insert(to, distance);
+                previous.insert(to, (current, edge));
+                open.push(Reverse(OpenNode {
+                    estimate: distance + remaining(to),
+                    node: to,
+                }));
+            }
+        }
+    }
+
+    None
+}
@@ -55,6 +55,8 @@ impl AirwayFix {
                     },
                     valid_direction,
                     minimum_level,
+                    // airway.txt only carries minimum levels
+                    maximum_level: None,
                 })
             }
             rule => {
@@ -79,7 +81,6 @@ impl AirwayType {
 pub type AirwayGraphResult = Result<AirwayGraph, AirwayError>;
 
 pub fn parse_airway_txt(content: &[u8]) -> AirwayGraphResult {
-    tracing::debug!("PARSING 2");
     let unparsed_file = read_to_string(content)?;
     let airways_parse = AirwayParser::parse(Rule::airways, &unparsed_file);
 
@@ -96,7 +97,6 @@ pub fn parse_airway_txt(content: &[u8]) -> AirwayGraphResult {
 
                     let fix = GraphPosition(coordinate);
                     let airway = airway_line.next().unwrap().as_str();
-                    // TODO: parse airway type
                     let airway_type = AirwayType::parse(&airway_line.next().unwrap());
 
                     // parse 2 neighbour segments
@@ -117,3 +117,54 @@ pub fn parse_airway_txt(content: &[u8]) -> AirwayGraphResult {
             })
     })?)
 }
+
+#[cfg(test)]
+mod test {
+    use super::parse_airway_txt;
+    use crate::adaptation::locations::airways::{AirwaySegment, AirwayType};
+
+    #[test]
+    fn test_airway_txt() {
+        let graph = parse_airway_txt(
+            b"GIVMI\t48.701094\t11.364803\t14\tY101\tB\tERNAS\t48.844669\t11.219353\t\tY\t\t\t\t\tN
+ERNAS\t48.844669\t11.219353\t14\tY101\tB\tBAMUR\t49.0\t11.0\t05000\tY\tGIVMI\t48.701094\t11.364803\t\tN
+BAMUR\t49.0\t11.0\t14\tY101\tB\t\t\t\t\tN\tERNAS\t48.844669\t11.219353\t05000\tY
+",
+        )
+        .unwrap();
+
+        assert_eq!(graph.airways().collect::<Vec<_>>(), vec!["Y101"]);
+        let fixes = graph.airway_fixes("Y101").unwrap();
+        assert_eq!(
+            fixes
+                .iter()
+                .map(|part| part.iter().map(|fix| fix.designator.as_str()).collect())
+                .collect::<Vec<Vec<_>>>(),
+            vec![vec!["GIVMI", "ERNAS", "BAMUR"]]
+        );
+        let segment = graph.segment("Y101", "BAMUR", "ERNAS").unwrap();
+        assert_eq!(
+            (
+                segment.minimum_level,
+                segment.maximum_level,
+                segment.airway_type,
+                segment.valid_direction,
+                segment.valid_reverse_direction,
+            ),
+            (Some(5000), None, AirwayType::Both, true, true)
+        );
+        let segments = graph.airway_segments("Y101").unwrap();
+        assert!(matches!(
+            segments.first(),
+            Some(AirwaySegment {
+                minimum_level: None,
+                valid_direction: true,
+                valid_reverse_direction: false,
+                ..
+            })
+        ));
+        assert_eq!(segments.len(), 2);
+        assert_eq!(graph.segment("Y101", "GIVMI", "BAMUR"), None);
+        assert_eq!(graph.airway_fixes("Y102"), None);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::io;
+
+use bevy_derive::Deref;
+use pest::Parser;
+use pest_derive::Parser;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use super::read_to_string;
+
+#[derive(Parser)]
+#[grammar = "pest/aliases.pest"]
+pub struct AliasesParser;
+
+#[derive(Error, Debug)]
+pub enum AliasesError {
+    #[error("failed to parse alias file: {0}")]
+    Parse(#[from] pest::error::Error<Rule>),
+    #[error("failed to read alias file: {0}")]
+    FileRead(#[from] io::Error),
+}
+
+/// EuroScope built-in variables usable in aliases, function-like variables carry
+/// their (already expanded) argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AliasVariable {
+    /// `$aircraft`, callsign of the selected aircraft
+    Aircraft,
+    /// `$type`
+    AircraftType,
+    /// `$dep`
+    Departure,
+    /// `$arr`
+    Arrival,
+    /// `$route`
+    Route,
+    /// `$sid`
+    Sid,
+    /// `$star`
+    Star,
+    /// `$alt`, final altitude of the flight plan
+    Altitude,
+    /// `$temp`, temporary altitude
+    TemporaryAltitude,
+    /// `$squawk`, assigned squawk
+    Squawk,
+    /// `$asquawk`, squawk set by the pilot
+    ActualSquawk,
+    /// `$freq`, own primary frequency
+    Frequency,
+    /// `$callsign`, own callsign
+    Callsign,
+    /// `$myrealname`
+    RealName,
+    /// `$atiscode`
+    AtisCode,
+    /// `$time`
+    Time,
+    /// `$runway` or `$runway(ICAO)`
+    Runway(Option<String>),
+    /// `$deprwy(ICAO)`
+    DepartureRunway(String),
+    /// `$arrrwy(ICAO)`
+    ArrivalRunway(String),
+    /// `$metar(ICAO)`
+    Metar(String),
+    /// `$wind(ICAO)`
+    Wind(String),
+    /// `$altim(ICAO)`
+    Altimeter(String),
+    /// any other variable and its argument
+    Other(String, Option<String>),
+}
+
+impl AliasVariable {
+    fn new(name: &str, argument: Option<String>) -> Self {
+        match (name, argument) {
+            ("aircraft", None) => Self::Aircraft,
+            ("type", None) => Self::AircraftType,
+            ("dep", None) => Self::Departure,
+            ("arr", None) => Self::Arrival,
+            ("route", None) => Self::Route,
+            ("sid", None) => Self::Sid,
+            ("star", None) => Self::Star,
+            ("alt", None) => Self::Altitude,
+            ("temp", None) => Self::TemporaryAltitude,
+            ("squawk", None) => Self::Squawk,
+            ("asquawk", None) => Self::ActualSquawk,
+            ("freq", None) => Self::Frequency,
+            ("callsign", None) => Self::Callsign,
+            ("myrealname", None) => Self::RealName,
+            ("atiscode", None) => Self::AtisCode,
+            ("time", None) => Self::Time,
+            ("runway", argument) => Self::Runway(argument),
+            ("deprwy", Some(argument)) => Self::DepartureRunway(argument),
+            ("arrrwy", Some(argument)) => Self::ArrivalRunway(argument),
+            ("metar", Some(argument)) => Self::Metar(argument),
+            ("wind", Some(argument)) => Self::Wind(argument),
+            ("altim", Some(argument)) => Self::Altimeter(argument),
+            (name, argument) => Self::Other(name.to_string(), argument),
+        }
+    }
+}
+
+/// Provides the values of variables when expanding aliases, i.e. from the selected
+/// flight plan and the controller's own station.
+pub trait AliasContext {
+    /// Value of `variable`, `None` keeps the variable unexpanded.
+    fn value(&self, variable: &AliasVariable) -> Option<String>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alias {
+    /// name without the leading `.`
+    pub name: String,
+    pub text: String,
+}
+
+impl Alias {
+    /// Expands all variables in the alias text, `$1`-`$9` are replaced by `args`.
+    pub fn expand(&self, args: &[&str], context: &impl AliasContext) -> String {
+        expand_text(&self.text, args, context)
+    }
+}
+
+/// Index of the `)` closing the `(` at the start of `text`.
+fn closing_parenthesis(text: &str) -> Option<usize> {
+    let mut depth = 0_usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+fn expand_text(text: &str, args: &[&str], context: &impl AliasContext) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let variable = &rest[dollar + 1..];
+
+        if let Some(digit) = variable.chars().next().filter(char::is_ascii_digit) {
+            let index = digit.to_digit(10).unwrap() as usize;
+            match index.checked_sub(1).and_then(|i| args.get(i)) {
+                Some(arg) => expanded.push_str(arg),
+                None => expanded.push_str(&rest[dollar..=dollar + 1]),
+            }
+            rest = &variable[1..];
+            continue;
+        }
+
+        let name_len = variable
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(variable.len());
+        if name_len == 0 {
+            expanded.push('$');
+            rest = variable;
+            continue;
+        }
+        let (name, after_name) = variable.split_at(name_len);
+        let (argument, after_variable) = match after_name
+            .starts_with('(')
+            .then(|| closing_parenthesis(after_name))
+            .flatten()
+        {
+            Some(close) => (
+                Some(expand_text(&after_name[1..close], args, context)),
+                &after_name[close + 1..],
+            ),
+            None => (None, after_name),
+        };
+
+        match context.value(&AliasVariable::new(&name.to_lowercase(), argument)) {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(&rest[dollar..rest.len() - after_variable.len()]),
+        }
+        rest = after_variable;
+    }
+    expanded.push_str(rest);
+
+    expanded
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Deref)]
+pub struct Aliases(pub HashMap<String, Alias>);
+
+impl Aliases {
+    /// Expands a command line as typed by the controller, i.e. `.fc DLH123 Langen`.
+    /// `None` if the command is no known alias.
+    pub fn expand(&self, command: &str, context: &impl AliasContext) -> Option<String> {
+        let mut words = command.split_whitespace();
+        let alias = self.get(words.next()?.strip_prefix('.')?)?;
+        let args = words.collect::<Vec<_>>();
+
+        Some(alias.expand(&args, context))
+    }
+}
+
+pub type AliasesResult = Result<Aliases, AliasesError>;
+
+pub fn parse_aliases(content: &[u8]) -> AliasesResult {
+    let unparsed_file = read_to_string(content)?;
+    let aliases_parse = AliasesParser::parse(Rule::aliases, &unparsed_file);
+
+    Ok(Aliases(aliases_parse.map(|mut pairs| {
+        pairs
+            .next()
+            .unwrap()
+            .into_inner()
+            .fold(HashMap::new(), |mut acc, pair| {
+                if matches!(pair.as_rule(), Rule::alias) {
+                    let mut alias = pair.into_inner();
+                    let name = alias.next().unwrap().as_str().to_string();
+                    let text = alias.next().unwrap().as_str().trim_end().to_string();
+
+                    if let Some(overwritten) = acc.insert(name.clone(), Alias { name, text }) {
+                        warn!("duplicate alias: {}", overwritten.name);
+                    }
+                }
+
+                acc
+            })
+    })?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_aliases, Alias, AliasContext, AliasVariable};
+
+    struct Context;
+
+    impl AliasContext for Context {
+        fn value(&self, variable: &AliasVariable) -> Option<String> {
+            match variable {
+                AliasVariable::Aircraft => Some("DLH4RM".to_string()),
+                AliasVariable::Departure => Some("EDDM".to_string()),
+                AliasVariable::Squawk => Some("1000".to_string()),
+                AliasVariable::Frequency => Some("129.100".to_string()),
+                AliasVariable::DepartureRunway(airport) if airport == "EDDM" => {
+                    Some("26R".to_string())
+                }
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_aliases() {
+        let aliases = parse_aliases(
+            b"; comment
+.sq squawk $squawk
+.rwy $aircraft, runway $deprwy($dep)
+
+.fc $1, contact $2 on $freq
+no alias
+.unknown $unknown $metar($arr) $3 costs $",
+        )
+        .unwrap();
+
+        assert_eq!(aliases.len(), 4);
+        assert_eq!(
+            aliases.get("sq"),
+            Some(&Alias {
+                name: "sq".to_string(),
+                text: "squawk $squawk".to_string()
+            })
+        );
+        assert_eq!(
+            aliases.expand(".sq", &Context),
+            Some("squawk 1000".to_string())
+        );
+        assert_eq!(
+            aliases.expand(".rwy", &Context),
+            Some("DLH4RM, runway 26R".to_string())
+        );
+        assert_eq!(
+            aliases.expand(".fc DLH4RM Langen", &Context),
+            Some("DLH4RM, contact Langen on 129.100".to_string())
+        );
+        assert_eq!(
+            aliases.expand(".unknown", &Context),
+            Some("$unknown $metar($arr) $3 costs $".to_string())
+        );
+        assert_eq!(aliases.expand(".none", &Context), None);
+        assert_eq!(aliases.expand("sq", &Context), None);
+    }
+}
@@ -3,7 +3,7 @@ use std::io;
 use geo::Coord;
 use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::warn;
 
@@ -103,7 +103,7 @@ pub enum AsrMapAirwayType {
     Name,
     Line,
 }
-#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum AsrMapGroundNetworkType {
     Exit,
     Taxiway,
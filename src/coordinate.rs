@@ -0,0 +1,402 @@
+//! Notations of coordinates as used by EuroScope, TopSky, flight plans and NAT tracks
+
+use std::sync::OnceLock;
+
+use geo::{point, Coord, Point};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{DegMinSecExt as _, Sign};
+
+/// Notation for parsing user input and displaying coordinates. The file writers only
+/// use the notations their readers accept, i.e. .sct files are always written in
+/// `Euroscope` notation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CoordinateFormat {
+    /// `N048.40.00.000 E010.58.00.500`, as in .sct and .ese files
+    #[default]
+    Euroscope,
+    /// `48.666667 10.966806`, latitude first
+    DecimalDegrees,
+    /// `N48 40.000 E010 58.008`
+    DegreesDecimalMinutes,
+    /// `4840N01058E`, also in whole degrees (`48N010E`) or with seconds
+    /// (`484000N0105800E`) when parsing
+    Icao,
+    /// ARINC 424 codes of whole degrees, the letter giving the quadrant (`N` north-west,
+    /// `E` north-east, `S` south-east, `W` south-west), i.e. `4810N` for 48°N 010°W and
+    /// `48N50` for 48°N 150°W
+    Arinc,
+    /// half degrees of latitude on North Atlantic tracks, `H4530` for 45°30'N 030°W
+    HalfDegree,
+}
+
+fn euroscope_regex() -> &'static Regex {
+    static EUROSCOPE_RE: OnceLock<Regex> = OnceLock::new();
+    EUROSCOPE_RE.get_or_init(|| {
+        Regex::new(
+            r"^([NS])(\d{3})\.(\d{2})\.(\d{2}(?:\.\d+)?)\s+([EW])(\d{3})\.(\d{2})\.(\d{2}(?:\.\d+)?)$",
+        )
+        .unwrap()
+    })
+}
+
+fn decimal_degrees_regex() -> &'static Regex {
+    static DECIMAL_DEGREES_RE: OnceLock<Regex> = OnceLock::new();
+    DECIMAL_DEGREES_RE.get_or_init(|| {
+        Regex::new(r"^([+-]?\d{1,2}(?:\.\d+)?)[,\s]\s*([+-]?\d{1,3}(?:\.\d+)?)$").unwrap()
+    })
+}
+
+fn degrees_decimal_minutes_regex() -> &'static Regex {
+    static DDM_RE: OnceLock<Regex> = OnceLock::new();
+    DDM_RE.get_or_init(|| {
+        Regex::new(
+            r"^([NS])\s*(\d{1,2})°?\s+(\d{1,2}(?:\.\d+)?)'?\s+([EW])\s*(\d{1,3})°?\s+(\d{1,2}(?:\.\d+)?)'?$",
+        )
+        .unwrap()
+    })
+}
+
+fn icao_regex() -> &'static Regex {
+    static ICAO_RE: OnceLock<Regex> = OnceLock::new();
+    ICAO_RE.get_or_init(|| Regex::new(r"^(\d{1,6})(N|S)(\d{2,7})(E|W)$").unwrap())
+}
+
+fn arinc_regex() -> &'static Regex {
+    static ARINC_RE: OnceLock<Regex> = OnceLock::new();
+    ARINC_RE.get_or_init(|| Regex::new(r"^(\d{2})(?:(\d{2})([NESW])|([NESW])(\d{2}))$").unwrap())
+}
+
+fn half_degree_regex() -> &'static Regex {
+    static HALF_DEGREE_RE: OnceLock<Regex> = OnceLock::new();
+    HALF_DEGREE_RE.get_or_init(|| Regex::new(r"^H(\d{2})(\d{2})$").unwrap())
+}
+
+fn valid(point: Point) -> Option<Point> {
+    (point.y().abs() <= 90.0 && point.x().abs() <= 180.0).then_some(point)
+}
+
+fn hemisphere(value: f64, positive: char, negative: char) -> char {
+    if value.is_sign_negative() {
+        negative
+    } else {
+        positive
+    }
+}
+
+/// Signs of latitude and longitude of an ARINC quadrant letter
+fn arinc_quadrant(quadrant: &str) -> (f64, f64) {
+    match quadrant {
+        "N" => (1.0, -1.0),
+        "E" => (1.0, 1.0),
+        "S" => (-1.0, 1.0),
+        _ => (-1.0, -1.0),
+    }
+}
+
+/// ICAO flight plan coordinate, i.e. `4620N05805W`, with its designator normalised to
+/// two digit latitude and three digit longitude degrees.
+pub(crate) fn parse_icao(designator: &str) -> Option<(String, Point)> {
+    let captures = icao_regex().captures(designator)?;
+    let lat_str = &captures[1];
+    let lng_str = &captures[3];
+    let normalised_lat_str = if matches!(lat_str.len(), 1 | 3 | 5) {
+        // invalid syntax
+        if lat_str.starts_with('0') {
+            warn!("Coordinate waypoints must not be abbreviated and start with a 0: {designator} (lat_str)");
+            return None;
+        }
+        format!("0{lat_str}")
+    } else {
+        lat_str.to_string()
+    };
+    let normalised_lng_str = if matches!(lng_str.len(), 2 | 4 | 6) {
+        // invalid syntax
+        if lng_str.starts_with('0') {
+            warn!("Coordinate waypoints must not be abbreviated and start with a 0: {designator} (lng_str)");
+            return None;
+        }
+        format!("0{lng_str}")
+    } else {
+        lng_str.to_string()
+    };
+    if normalised_lng_str
+        .len()
+        .saturating_sub(normalised_lat_str.len())
+        != 1
+    {
+        warn!("Coordinate waypoints must have the same precision in lat/lon: {designator}");
+        return None;
+    }
+    let sexagesimal = |digits: &str, degree_digits: usize| {
+        let (degrees, rest) = digits.split_at(degree_digits);
+        let (minutes, seconds) = rest.split_at(rest.len().min(2));
+        [degrees, minutes, seconds]
+            .into_iter()
+            .zip([1.0, 60.0, 3600.0])
+            .filter(|(part, _)| !part.is_empty())
+            .map(|(part, divisor)| part.parse::<f64>().unwrap() / divisor)
+            .sum::<f64>()
+    };
+    let lat = sexagesimal(&normalised_lat_str, 2);
+    let lng = sexagesimal(&normalised_lng_str, 3);
+    let n_s = &captures[2];
+    let w_e = &captures[4];
+
+    Some((
+        format!("{normalised_lat_str}{n_s}{normalised_lng_str}{w_e}"),
+        point! {
+            x: if w_e == "E" { 1.0 } else { -1.0 } * lng,
+            y: if n_s == "N" { 1.0 } else { -1.0 } * lat,
+        },
+    ))
+}
+
+impl CoordinateFormat {
+    pub const ALL: [Self; 6] = [
+        Self::Euroscope,
+        Self::DecimalDegrees,
+        Self::DegreesDecimalMinutes,
+        Self::Icao,
+        Self::Arinc,
+        Self::HalfDegree,
+    ];
+
+    /// Coordinate in this notation, surrounding whitespace and case are ignored.
+    pub fn parse(self, s: &str) -> Option<Point> {
+        let s = s.trim().to_uppercase();
+        let number = |s: &str| s.parse::<f64>().unwrap();
+
+        match self {
+            Self::Euroscope => euroscope_regex().captures(&s).and_then(|captures| {
+                let part = |hemisphere: usize| {
+                    (
+                        Sign::from(&captures[hemisphere]),
+                        captures[hemisphere + 1].parse().unwrap(),
+                        captures[hemisphere + 2].parse().unwrap(),
+                        number(&captures[hemisphere + 3]),
+                    )
+                };
+                valid(Coord::from_deg_min_sec(part(1), part(5)).into())
+            }),
+            Self::DecimalDegrees => decimal_degrees_regex().captures(&s).and_then(|captures| {
+                valid(point! { x: number(&captures[2]), y: number(&captures[1]) })
+            }),
+            Self::DegreesDecimalMinutes => {
+                degrees_decimal_minutes_regex()
+                    .captures(&s)
+                    .and_then(|captures| {
+                        let part = |hemisphere: usize| {
+                            let minutes = number(&captures[hemisphere + 2]);
+                            (minutes < 60.0).then(|| {
+                                f64::from(Sign::from(&captures[hemisphere]).sign())
+                                    * (number(&captures[hemisphere + 1]) + minutes / 60.0)
+                            })
+                        };
+                        valid(point! { x: part(4)?, y: part(1)? })
+                    })
+            }
+            Self::Icao => parse_icao(&s).and_then(|(_, point)| valid(point)),
+            Self::Arinc => arinc_regex().captures(&s).and_then(|captures| {
+                let latitude = number(&captures[1]);
+                let (longitude, quadrant) = match (captures.get(2), captures.get(4)) {
+                    (Some(longitude), _) => (number(longitude.as_str()), &captures[3]),
+                    (None, Some(quadrant)) => (100.0 + number(&captures[5]), quadrant.as_str()),
+                    (None, None) => return None,
+                };
+                let (lat_sign, lng_sign) = arinc_quadrant(quadrant);
+                valid(point! { x: lng_sign * longitude, y: lat_sign * latitude })
+            }),
+            Self::HalfDegree => half_degree_regex().captures(&s).and_then(|captures| {
+                valid(point! { x: -number(&captures[2]), y: number(&captures[1]) + 0.5 })
+            }),
+        }
+    }
+
+    /// Notation and position of `s` in the first notation that parses it.
+    pub fn detect(s: &str) -> Option<(Self, Point)> {
+        Self::ALL
+            .into_iter()
+            .find_map(|format| format.parse(s).map(|point| (format, point)))
+    }
+
+    /// `point` in this notation, `None` if it cannot be represented exactly, i.e. ARINC
+    /// codes of positions off whole degrees.
+    pub fn format(self, point: Point) -> Option<String> {
+        let (lat, lng) = (point.y(), point.x());
+
+        match self {
+            Self::Euroscope => Some(point.deg_min_sec_fmt()),
+            Self::DecimalDegrees => Some(format!("{lat:.6} {lng:.6}")),
+            Self::DegreesDecimalMinutes => {
+                // in thousandths of minutes to carry rounding into the degrees
+                let ddm = |value: f64, degree_digits: usize| {
+                    let thousandths = (value.abs() * 60_000.0).round() as u64;
+                    format!(
+                        "{:0degree_digits$} {:02}.{:03}",
+                        thousandths / 60_000,
+                        thousandths % 60_000 / 1000,
+                        thousandths % 1000,
+                    )
+                };
+                Some(format!(
+                    "{}{} {}{}",
+                    hemisphere(lat, 'N', 'S'),
+                    ddm(lat, 2),
+                    hemisphere(lng, 'E', 'W'),
+                    ddm(lng, 3),
+                ))
+            }
+            Self::Icao => {
+                let seconds = |value: f64| (value.abs() * 3600.0).round() as u64;
+                let with_seconds = seconds(lat) % 60 != 0 || seconds(lng) % 60 != 0;
+                let dms = |value: f64, degree_digits: usize| {
+                    let seconds = seconds(value);
+                    let degrees_minutes = format!(
+                        "{:0degree_digits$}{:02}",
+                        seconds / 3600,
+                        seconds % 3600 / 60
+                    );
+                    if with_seconds {
+                        format!("{degrees_minutes}{:02}", seconds % 60)
+                    } else {
+                        degrees_minutes
+                    }
+                };
+                Some(format!(
+                    "{}{}{}{}",
+                    dms(lat, 2),
+                    hemisphere(lat, 'N', 'S'),
+                    dms(lng, 3),
+                    hemisphere(lng, 'E', 'W'),
+                ))
+            }
+            Self::Arinc => {
+                let whole = |value: f64| (value.fract() == 0.0).then_some(value.abs() as u32);
+                let (latitude, longitude) = (whole(lat)?, whole(lng)?);
+                let quadrant = match (lat.is_sign_negative(), lng.is_sign_negative()) {
+                    (false, true) => 'N',
+                    (false, false) => 'E',
+                    (true, false) => 'S',
+                    (true, true) => 'W',
+                };
+                Some(if longitude >= 100 {
+                    format!("{latitude:02}{quadrant}{:02}", longitude - 100)
+                } else {
+                    format!("{latitude:02}{longitude:02}{quadrant}")
+                })
+            }
+            Self::HalfDegree => {
+                let half_degree = lat >= 0.0 && (lat.fract() - 0.5).abs() < f64::EPSILON;
+                (half_degree && lng <= 0.0 && lng > -100.0 && lng.fract() == 0.0)
+                    .then(|| format!("H{:02}{:02}", lat.floor() as u32, -lng as u32))
+            }
+        }
+    }
+
+    /// `point` in this notation, falling back to the EuroScope notation if it cannot be
+    /// represented.
+    pub fn format_or_euroscope(self, point: Point) -> String {
+        self.format(point)
+            .unwrap_or_else(|| point.deg_min_sec_fmt())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{point, Point};
+
+    use super::CoordinateFormat;
+
+    fn rounded(point: Point) -> (i64, i64) {
+        (
+            (point.x() * 1e6).round() as i64,
+            (point.y() * 1e6).round() as i64,
+        )
+    }
+
+    #[test]
+    fn test_coordinate_formats() {
+        let cases = [
+            (
+                CoordinateFormat::Euroscope,
+                "N048.30.00.000 W010.45.00.000",
+                point! { x: -10.75, y: 48.5 },
+            ),
+            (
+                CoordinateFormat::DecimalDegrees,
+                "-48.500000 10.750000",
+                point! { x: 10.75, y: -48.5 },
+            ),
+            (
+                CoordinateFormat::DegreesDecimalMinutes,
+                "N48 30.500 E010 45.200",
+                point! { x: 10.753_333_333, y: 48.508_333_333 },
+            ),
+            (
+                CoordinateFormat::Icao,
+                "4830N01045E",
+                point! { x: 10.75, y: 48.5 },
+            ),
+            (
+                CoordinateFormat::Icao,
+                "483015N0104512E",
+                point! { x: 10.753_333_333, y: 48.504_166_667 },
+            ),
+            (
+                CoordinateFormat::Arinc,
+                "4810N",
+                point! { x: -10.0, y: 48.0 },
+            ),
+            (
+                CoordinateFormat::Arinc,
+                "48S50",
+                point! { x: 150.0, y: -48.0 },
+            ),
+            (
+                CoordinateFormat::HalfDegree,
+                "H4530",
+                point! { x: -30.0, y: 45.5 },
+            ),
+        ];
+
+        for (format, s, expected) in cases {
+            let parsed = format.parse(s).unwrap();
+            assert_eq!(rounded(parsed), rounded(expected), "{format:?} {s}");
+            assert_eq!(CoordinateFormat::detect(s).map(|(f, _)| f), Some(format));
+            assert_eq!(format.format(parsed).as_deref(), Some(s), "{format:?}");
+        }
+
+        assert_eq!(
+            rounded(
+                CoordinateFormat::DegreesDecimalMinutes
+                    .parse("n48° 30.5' e10° 45.2'")
+                    .unwrap()
+            ),
+            rounded(point! { x: 10.753_333_333, y: 48.508_333_333 })
+        );
+        assert_eq!(
+            CoordinateFormat::Icao.parse("46N078W").map(rounded),
+            Some(rounded(point! { x: -78.0, y: 46.0 }))
+        );
+        assert_eq!(CoordinateFormat::DecimalDegrees.parse("91.0 10.0"), None);
+        assert_eq!(
+            CoordinateFormat::Arinc.format(point! { x: 10.5, y: 48.0 }),
+            None
+        );
+        assert_eq!(
+            CoordinateFormat::Arinc.format_or_euroscope(point! { x: 10.5, y: 48.0 }),
+            "N048.00.00.000 E010.30.00.000"
+        );
+        // rounding carries into the degrees
+        assert_eq!(
+            CoordinateFormat::DegreesDecimalMinutes
+                .format(point! { x: 10.999_999_9, y: 48.0 })
+                .as_deref(),
+            Some("N48 00.000 E011 00.000")
+        );
+        assert_eq!(CoordinateFormat::detect("EDDM"), None);
+    }
+}
@@ -9,13 +9,16 @@ use pest_derive::Parser;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::warn;
+use uom::si::f32::Length;
 
 use crate::{
-    adaptation::maps::active::RunwayIdentifier, adaptation::settings::CoordinationTiming,
-    DegMinSec, DegMinSecExt as _, Sign,
+    adaptation::locations::approach::{ApproachFixRole, ApproachType, TurnDirection},
+    adaptation::maps::active::RunwayIdentifier,
+    adaptation::settings::CoordinationTiming,
+    level::{self, feet, flight_level},
 };
 
-use super::read_to_string;
+use super::{parse_coordinate, read_to_string};
 
 #[derive(Parser)]
 #[grammar = "pest/base.pest"]
@@ -116,8 +119,10 @@ enum SectorSubsetting {
 #[derive(Clone, Debug, Serialize, PartialEq)]
 pub struct Sector {
     pub id: String,
-    pub bottom: u32,
-    pub top: u32,
+    #[serde(with = "level::as_feet")]
+    pub bottom: Length,
+    #[serde(with = "level::as_feet")]
+    pub top: Length,
     pub border: Vec<SectorLine>,
     pub owner_priority: Vec<String>,
     pub departure_airports: Vec<String>,
@@ -132,8 +137,8 @@ impl Sector {
     fn parse(pair: Pair<Rule>) -> (Vec<String>, Self) {
         let mut sector = pair.into_inner();
         let id = sector.next().unwrap().as_str().to_string();
-        let bottom = sector.next().unwrap().as_str().parse().unwrap();
-        let top = sector.next().unwrap().as_str().parse().unwrap();
+        let bottom = feet(sector.next().unwrap().as_str().parse().unwrap());
+        let top = feet(sector.next().unwrap().as_str().parse().unwrap());
         let subsettings = sector.map(Self::parse_subsettings).collect::<Vec<_>>();
         let owner_priority = subsettings
             .iter()
@@ -255,10 +260,10 @@ fn parse_wildcard_string(pair: &Pair<Rule>) -> Option<String> {
     }
 }
 
-fn parse_wildcard_u32(pair: &Pair<Rule>) -> Option<u32> {
+fn parse_wildcard_level(pair: &Pair<Rule>) -> Option<Length> {
     match pair.as_rule() {
         Rule::wildcard => None,
-        Rule::integer => Some(pair.as_str().parse().unwrap()),
+        Rule::integer => Some(feet(pair.as_str().parse().unwrap())),
         rule => unreachable!("{rule:?}"),
     }
 }
@@ -272,8 +277,12 @@ pub struct Constraint {
     pub fix: Option<String>,
     pub exit_sector: String,
     pub entry_sector: String,
-    pub climb_level: Option<u32>,
-    pub descent_level: Option<u32>,
+    #[serde(with = "level::option_as_feet")]
+    #[reflect(ignore)]
+    pub climb_level: Option<Length>,
+    #[serde(with = "level::option_as_feet")]
+    #[reflect(ignore)]
+    pub descent_level: Option<Length>,
     pub description: String,
     /// OLDI timing override.
     #[serde(default)]
@@ -289,10 +298,10 @@ impl fmt::Display for Constraint {
             self.exit_sector, self.entry_sector
         )?;
         if let Some(cl) = self.climb_level {
-            write!(f, "  FL{}", cl / 100)?;
+            write!(f, "  FL{}", flight_level(cl))?;
         }
         if let Some(dl) = self.descent_level {
-            write!(f, "  FL{}", dl / 100)?;
+            write!(f, "  FL{}", flight_level(dl))?;
         }
         if let Some(rwy) = self
             .departure_runway
@@ -315,8 +324,8 @@ impl Constraint {
         let arrival_runway = parse_wildcard_string(&cop.next().unwrap());
         let exit_sector = cop.next().unwrap().as_str().to_string();
         let entry_sector = cop.next().unwrap().as_str().to_string();
-        let climb_level = parse_wildcard_u32(&cop.next().unwrap());
-        let descent_level = parse_wildcard_u32(&cop.next().unwrap());
+        let climb_level = parse_wildcard_level(&cop.next().unwrap());
+        let descent_level = parse_wildcard_level(&cop.next().unwrap());
         let description = cop.next().unwrap().as_str().to_string();
 
         Self {
@@ -371,6 +380,26 @@ pub struct STAR {
     pub waypoints: Vec<String>,
 }
 
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct Hold {
+    pub fix: String,
+    pub inbound_course: u32,
+    pub turn: TurnDirection,
+    pub leg_minutes: f64,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct Approach {
+    pub name: String,
+    pub airport: String,
+    pub runway: String,
+    pub approach_type: ApproachType,
+    pub glide_slope: Option<f64>,
+    pub waypoints: Vec<(String, Option<ApproachFixRole>)>,
+    pub missed_approach: Vec<String>,
+    pub hold: Option<Hold>,
+}
+
 #[derive(Debug, Default, Serialize, PartialEq)]
 pub struct Ese {
     pub positions: HashMap<String, Position>,
@@ -397,31 +426,6 @@ enum SectionName {
     Unsupported,
 }
 
-fn parse_coordinate_part(pair: Pair<Rule>) -> DegMinSec {
-    let mut coordinate_part = pair.into_inner().next().unwrap().into_inner();
-    let hemi = coordinate_part.next().unwrap().as_str();
-    let sign = Sign::from(hemi);
-    let degrees = coordinate_part
-        .next()
-        .unwrap()
-        .as_str()
-        .parse::<u16>()
-        .unwrap();
-
-    let min = coordinate_part.next().unwrap().as_str().parse().unwrap();
-    let sec = coordinate_part.next().unwrap().as_str().parse().unwrap();
-
-    (sign, degrees, min, sec)
-}
-
-// TODO generalise this and other similar into trait
-fn parse_coordinate(pair: Pair<Rule>) -> Coord {
-    let mut coordinate = pair.into_inner();
-    let lat = parse_coordinate_part(coordinate.next().unwrap());
-    let lng = parse_coordinate_part(coordinate.next().unwrap());
-    Coord::from_deg_min_sec(lat, lng)
-}
-
 enum SectorRule {
     Sector((Vec<String>, Sector)),
     SectorLine((String, SectorLine)),
@@ -561,6 +565,7 @@ fn combine_sectors_with_borders(
 pub enum SidStar {
     Sid(SID),
     Star(STAR),
+    Approach(Approach),
 }
 fn parse_sid_star(pair: Pair<Rule>) -> SidStar {
     let rule = pair.as_rule();
@@ -596,6 +601,86 @@ fn parse_sid_star(pair: Pair<Rule>) -> SidStar {
     }
 }
 
+fn parse_hold(pair: Pair<Rule>) -> Hold {
+    let mut hold = pair.into_inner();
+    let fix = hold.next().unwrap().as_str().to_string();
+    let inbound_course = hold.next().unwrap().as_str().parse().unwrap();
+    let turn = match hold.next().unwrap().as_str() {
+        "L" => TurnDirection::Left,
+        "R" => TurnDirection::Right,
+        turn => unreachable!("{turn}"),
+    };
+    let leg_minutes = hold.next().unwrap().as_str().parse().unwrap();
+
+    Hold {
+        fix,
+        inbound_course,
+        turn,
+        leg_minutes,
+    }
+}
+
+fn parse_approach(pair: Pair<Rule>) -> Approach {
+    let mut approach = pair.into_inner();
+    let airport = approach.next().unwrap().as_str().to_string();
+    let runway = approach.next().unwrap().as_str().to_string();
+    let name = approach.next().unwrap().as_str().to_string();
+    let approach_type = match approach.next().unwrap().as_str() {
+        "ILS" => ApproachType::Ils,
+        "LOC" => ApproachType::Loc,
+        "RNP" => ApproachType::Rnp,
+        "VOR" => ApproachType::Vor,
+        "NDB" => ApproachType::Ndb,
+        approach_type => unreachable!("{approach_type}"),
+    };
+    let glide_slope_pair = approach.next().unwrap();
+    let glide_slope = match glide_slope_pair.as_rule() {
+        Rule::decimal => Some(glide_slope_pair.as_str().parse().unwrap()),
+        Rule::none => None,
+        rule => unreachable!("{rule:?}"),
+    };
+    let waypoints = approach
+        .next()
+        .unwrap()
+        .into_inner()
+        .map(|wpt| {
+            let mut wpt = wpt.into_inner();
+            let designator = wpt.next().unwrap().as_str().to_string();
+            let role = wpt.next().map(|role| match role.as_str() {
+                "IAF" => ApproachFixRole::Iaf,
+                "IF" => ApproachFixRole::If,
+                "FAF" => ApproachFixRole::Faf,
+                "MAPT" => ApproachFixRole::Mapt,
+                role => unreachable!("{role}"),
+            });
+            (designator, role)
+        })
+        .collect();
+    let missed_approach = approach
+        .next()
+        .unwrap()
+        .into_inner()
+        .map(|wpt| wpt.as_str().to_string())
+        .collect();
+    let hold_pair = approach.next().unwrap();
+    let hold = match hold_pair.as_rule() {
+        Rule::hold => Some(parse_hold(hold_pair)),
+        Rule::none => None,
+        rule => unreachable!("{rule:?}"),
+    };
+
+    Approach {
+        name,
+        airport,
+        runway,
+        approach_type,
+        glide_slope,
+        waypoints,
+        missed_approach,
+        hold,
+    }
+}
+
 fn parse_section(pair: Pair<Rule>) -> (SectionName, Section) {
     match pair.as_rule() {
         Rule::position_section => (
@@ -624,7 +709,14 @@ fn parse_section(pair: Pair<Rule>) -> (SectionName, Section) {
         ),
         Rule::sidsstars_section => (
             SectionName::SidsStars,
-            Section::SidsStars(pair.into_inner().map(parse_sid_star).collect()),
+            Section::SidsStars(
+                pair.into_inner()
+                    .map(|pair| match pair.as_rule() {
+                        Rule::approach => SidStar::Approach(parse_approach(pair)),
+                        _ => parse_sid_star(pair),
+                    })
+                    .collect(),
+            ),
         ),
         _ => (SectionName::Unsupported, Section::Unsupported),
     }
@@ -671,6 +763,7 @@ mod test {
 
     use crate::{
         ese::{Constraint, Ese, Position, SectorLine, SidStar, SID, STAR},
+        level::feet,
         Coord,
     };
 
@@ -685,7 +778,7 @@ mod test {
             exit_sector: "EDMM\u{b7}EDMMALB\u{b7}105\u{b7}135".to_string(),
             entry_sector: "EDMM\u{b7}EDMMFRK\u{b7}000\u{b7}135".to_string(),
             climb_level: None,
-            descent_level: Some(13000),
+            descent_level: Some(feet(13_000.0)),
             description: "UPALA".to_string(),
             coord_timing: None,
         };
@@ -693,7 +786,7 @@ mod test {
             subsequent_fix: Some("EDDE".to_string()),
             exit_sector: "EDMM\u{b7}EDMMALB\u{b7}245\u{b7}315".to_string(),
             entry_sector: "EDMM\u{b7}EDMMBBG\u{b7}245\u{b7}295".to_string(),
-            descent_level: Some(25000),
+            descent_level: Some(feet(25_000.0)),
             ..base.clone()
         };
 
@@ -1071,8 +1164,8 @@ COPX:*:*:ERNAS:EDDF:*:EDMM\xb7EDUUDON14\xb7315\xb7355:EDMM\xb7EDMMALB\xb7245\xb7
             alb_0_105.id,
             "EDMM\u{b7}EDMMALB\u{b7}000\u{b7}105".to_string()
         );
-        assert_eq!(alb_0_105.top, 10500);
-        assert_eq!(alb_0_105.bottom, 0);
+        assert_eq!(alb_0_105.top, feet(10_500.0));
+        assert_eq!(alb_0_105.bottom, feet(0.0));
         assert_eq!(
             ese.constraints
                 .iter()
@@ -1090,7 +1183,7 @@ COPX:*:*:ERNAS:EDDF:*:EDMM\xb7EDUUDON14\xb7315\xb7355:EDMM\xb7EDMMALB\xb7245\xb7
                     exit_sector: "EDMM\u{b7}EDMMRDG\u{b7}000\u{b7}135".to_string(),
                     entry_sector: "EDMM\u{b7}EDMMALB\u{b7}000\u{b7}105".to_string(),
                     climb_level: None,
-                    descent_level: Some(9000),
+                    descent_level: Some(feet(9_000.0)),
                     description: "RUDNO".to_string(),
                     coord_timing: None,
                 },
@@ -1103,7 +1196,7 @@ COPX:*:*:ERNAS:EDDF:*:EDMM\xb7EDUUDON14\xb7315\xb7355:EDMM\xb7EDMMALB\xb7245\xb7
                     exit_sector: "EDMM\u{b7}EDMMRDG\u{b7}000\u{b7}135".to_string(),
                     entry_sector: "EDMM\u{b7}EDMMALB\u{b7}000\u{b7}105".to_string(),
                     climb_level: None,
-                    descent_level: Some(10000),
+                    descent_level: Some(feet(10_000.0)),
                     description: "STAUB".to_string(),
                     coord_timing: None,
                 },
@@ -1126,7 +1219,7 @@ COPX:*:*:ERNAS:EDDF:*:EDMM\xb7EDUUDON14\xb7315\xb7355:EDMM\xb7EDMMALB\xb7245\xb7
                     exit_sector: "EDMM\u{b7}EDMMALB\u{b7}000\u{b7}105".to_string(),
                     entry_sector: "EDMM\u{b7}EDMMTMANL\u{b7}000\u{b7}095".to_string(),
                     climb_level: None,
-                    descent_level: Some(9300),
+                    descent_level: Some(feet(9_300.0)),
                     description: "INDIV".to_string(),
                     coord_timing: None,
                 },
@@ -1139,7 +1232,7 @@ COPX:*:*:ERNAS:EDDF:*:EDMM\xb7EDUUDON14\xb7315\xb7355:EDMM\xb7EDMMALB\xb7245\xb7
                     exit_sector: "EDMM\u{b7}EDMMALB\u{b7}000\u{b7}105".to_string(),
                     entry_sector: "EDMM\u{b7}EDMMTMANL\u{b7}000\u{b7}095".to_string(),
                     climb_level: None,
-                    descent_level: Some(8000),
+                    descent_level: Some(feet(8_000.0)),
                     description: "MIQ".to_string(),
                     coord_timing: None,
                 },
@@ -0,0 +1,257 @@
+//! EuroScope ground network (.txt) as used for ground radar displays. Supported are
+//! `STAND`, `EXIT`, `TAXI` and `TERMINAL` lines, the latter three followed by their
+//! `COORD` lines, and `HOLD` lines for runway holding points. Other lines and
+//! trailing fields are skipped.
+
+use std::io;
+
+use geo::{Distance as _, Geodesic, LineString, Point};
+use pest::{iterators::Pair, Parser};
+use pest_derive::Parser;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::adaptation::ground::{HoldingPoint, Stand, Taxiway};
+use crate::adaptation::locations::approach::TurnDirection;
+use crate::asr::AsrMapGroundNetworkType;
+
+use super::{parse_coordinate, read_to_string};
+
+#[derive(Parser)]
+#[grammar = "pest/base.pest"]
+#[grammar = "pest/ground.pest"]
+pub struct GroundParser;
+
+#[derive(Error, Debug)]
+pub enum GroundError {
+    #[error("failed to parse ground network: {0}")]
+    Parse(#[from] pest::error::Error<Rule>),
+    #[error("failed to read ground network: {0}")]
+    FileRead(#[from] io::Error),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GroundNetwork {
+    pub stands: Vec<Stand>,
+    pub taxiways: Vec<Taxiway>,
+    pub holding_points: Vec<HoldingPoint>,
+}
+
+pub type GroundResult = Result<GroundNetwork, GroundError>;
+
+fn parse_max_speed(pair: &Pair<Rule>) -> Option<u32> {
+    match pair.as_rule() {
+        Rule::pos_integer => Some(pair.as_str().parse().unwrap()),
+        Rule::none => None,
+        rule => unreachable!("{rule:?}"),
+    }
+}
+
+fn parse_coords(pair: Pair<Rule>) -> LineString {
+    pair.into_inner().map(parse_coordinate).collect()
+}
+
+fn parse_stand(pair: Pair<Rule>) -> Stand {
+    let mut stand = pair.into_inner();
+    let airport = stand.next().unwrap().as_str().to_string();
+    let name = stand.next().unwrap().as_str().trim().to_string();
+    let coordinate = parse_coordinate(stand.next().unwrap()).into();
+    let radius = stand.next().map(|radius| radius.as_str().parse().unwrap());
+
+    Stand {
+        airport,
+        name,
+        coordinate,
+        radius,
+    }
+}
+
+fn parse_exit(pair: Pair<Rule>) -> Taxiway {
+    let mut exit = pair.into_inner();
+    let runway = exit.next().unwrap().as_str().to_string();
+    let name = exit.next().unwrap().as_str().trim().to_string();
+    let direction = match exit.next().unwrap().as_str() {
+        "LEFT" => TurnDirection::Left,
+        "RIGHT" => TurnDirection::Right,
+        direction => unreachable!("{direction}"),
+    };
+    let max_speed = parse_max_speed(&exit.next().unwrap());
+    let points = parse_coords(exit.next().unwrap());
+
+    Taxiway {
+        airport: String::new(),
+        kind: AsrMapGroundNetworkType::Exit,
+        name,
+        runway: Some(runway),
+        direction: Some(direction),
+        max_speed,
+        points,
+    }
+}
+
+fn parse_taxiway(pair: Pair<Rule>) -> Taxiway {
+    let mut taxiway = pair.into_inner();
+    let kind = match taxiway.next().unwrap().as_str() {
+        "TAXI:" => AsrMapGroundNetworkType::Taxiway,
+        "TERMINAL:" => AsrMapGroundNetworkType::TerminalTaxiway,
+        kind => unreachable!("{kind}"),
+    };
+    let name = taxiway.next().unwrap().as_str().trim().to_string();
+    let max_speed = parse_max_speed(&taxiway.next().unwrap());
+    let points = parse_coords(taxiway.next().unwrap());
+
+    Taxiway {
+        airport: String::new(),
+        kind,
+        name,
+        runway: None,
+        direction: None,
+        max_speed,
+        points,
+    }
+}
+
+fn parse_holding_point(pair: Pair<Rule>) -> HoldingPoint {
+    let mut holding_point = pair.into_inner();
+    let airport = holding_point.next().unwrap().as_str().to_string();
+    let runway = holding_point.next().unwrap().as_str().to_string();
+    let name = holding_point.next().unwrap().as_str().trim().to_string();
+    let coordinate: Point = parse_coordinate(holding_point.next().unwrap()).into();
+
+    HoldingPoint {
+        airport,
+        runway,
+        name,
+        coordinate,
+    }
+}
+
+/// Airport of the stand or holding point of `network` closest to `position`
+fn closest_airport(network: &GroundNetwork, position: Point) -> Option<String> {
+    network
+        .stands
+        .iter()
+        .map(|stand| (&stand.airport, stand.coordinate))
+        .chain(
+            network
+                .holding_points
+                .iter()
+                .map(|holding_point| (&holding_point.airport, holding_point.coordinate)),
+        )
+        .min_by(|(_, a), (_, b)| {
+            Geodesic
+                .distance(position, *a)
+                .total_cmp(&Geodesic.distance(position, *b))
+        })
+        .map(|(airport, _)| airport.clone())
+}
+
+pub fn parse_ground_network(content: &[u8]) -> GroundResult {
+    let unparsed_file = read_to_string(content)?;
+    let ground = GroundParser::parse(Rule::ground, &unparsed_file)?
+        .next()
+        .unwrap();
+
+    let mut network = ground
+        .into_inner()
+        .fold(GroundNetwork::default(), |mut network, pair| {
+            match pair.as_rule() {
+                Rule::stand => network.stands.push(parse_stand(pair)),
+                Rule::exit => network.taxiways.push(parse_exit(pair)),
+                Rule::taxiway => network.taxiways.push(parse_taxiway(pair)),
+                Rule::holding_point => network.holding_points.push(parse_holding_point(pair)),
+                Rule::unsupported => warn!("ground network: skipped line {}", pair.as_str()),
+                _ => {}
+            }
+            network
+        });
+
+    // taxiway lines do not name their airport
+    for index in 0..network.taxiways.len() {
+        if let Some(airport) = network.taxiways[index]
+            .points
+            .points()
+            .next()
+            .and_then(|position| closest_airport(&network, position))
+        {
+            network.taxiways[index].airport = airport;
+        }
+    }
+
+    Ok(network)
+}
+
+#[cfg(test)]
+mod test {
+    use geo::point;
+
+    use crate::adaptation::locations::approach::TurnDirection;
+    use crate::asr::AsrMapGroundNetworkType;
+
+    use super::parse_ground_network;
+
+    #[test]
+    fn test_ground_network() {
+        let content = b"; apron
+STAND:EDDM:101:N048.21.00.000:E011.47.00.000:30
+STAND:EDDM:G 12:N048.21.10.000:E011.47.00.000
+STAND:EDDM:102:48.35:11.78
+
+TAXI:A:25
+COORD:N048.21.00.000:E011.47.10.000
+COORD:N048.21.10.000:E011.47.10.000
+TERMINAL:T1:
+COORD:N048.21.10.000:E011.47.10.000
+COORD:N048.21.20.000:E011.47.10.000
+EXIT:26R:B5:LEFT:40
+COORD:N048.21.30.000:E011.47.10.000
+COORD:N048.21.20.000:E011.47.10.000
+HOLD:EDDM:26R:A1:N048.21.20.000:E011.47.15.000
+";
+
+        let network = parse_ground_network(content).unwrap();
+        assert_eq!(network.stands.len(), 3);
+        assert_eq!(network.stands[0].radius, Some(30));
+        assert_eq!(network.stands[1].name, "G 12");
+        assert_eq!(network.stands[1].radius, None);
+        assert_eq!(network.stands[2].coordinate, point! { x: 11.78, y: 48.35 });
+
+        let kinds = network
+            .taxiways
+            .iter()
+            .map(|taxiway| (taxiway.kind, taxiway.name.as_str(), taxiway.max_speed))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (AsrMapGroundNetworkType::Taxiway, "A", Some(25)),
+                (AsrMapGroundNetworkType::TerminalTaxiway, "T1", None),
+                (AsrMapGroundNetworkType::Exit, "B5", Some(40)),
+            ]
+        );
+        assert_eq!(network.taxiways[2].runway.as_deref(), Some("26R"));
+        assert_eq!(network.taxiways[2].direction, Some(TurnDirection::Left));
+        assert_eq!(network.taxiways[0].points.0.len(), 2);
+        assert_eq!(network.taxiways[0].airport, "EDDM");
+        assert_eq!(network.holding_points[0].runway, "26R");
+    }
+
+    #[test]
+    fn test_ground_network_unknown_fields_and_lines() {
+        let content = b"TAXI:A:25:1:
+COORD:N048.21.00.000:E011.47.10.000:0
+COORD:N048.21.10.000:E011.47.10.000
+GATE:EDDM:101:N048.21.00.000:E011.47.00.000
+STAND:EDDM:101:N048.21.00.000:E011.47.00.000:30:A320
+HOLD:EDDM:26R:A1:N048.21.20.000:E011.47.15.000:CAT III
+";
+
+        let network = parse_ground_network(content).unwrap();
+        assert_eq!(network.taxiways.len(), 1);
+        assert_eq!(network.taxiways[0].max_speed, Some(25));
+        assert_eq!(network.taxiways[0].points.0.len(), 2);
+        assert_eq!(network.stands.len(), 1);
+        assert_eq!(network.stands[0].radius, Some(30));
+        assert_eq!(network.holding_points.len(), 1);
+    }
+}
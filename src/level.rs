@@ -0,0 +1,129 @@
+//! Levels are `uom` lengths in the adaptation, but stay in feet in the .ese and in
+//! serialised adaptations (and thus jsonnet overlays).
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uom::si::{
+    f32::{Length, Pressure},
+    length::foot,
+    pressure::hectopascal,
+};
+
+/// ISA pressure at mean sea level, the altimeter setting for flight levels
+pub const STANDARD_PRESSURE_HPA: f32 = 1013.25;
+/// height of the ISA troposphere model in feet
+const ISA_HEIGHT_FT: f32 = 145_366.45;
+/// exponent of the ISA pressure to altitude relation
+const ISA_EXPONENT: f32 = 0.190_263;
+
+/// Level from feet, i.e. `feet(24_500.0)` for FL245
+pub fn feet(feet: f32) -> Length {
+    Length::new::<foot>(feet)
+}
+
+/// `#[serde(with = "crate::level::as_feet")]` for `Length` levels
+pub mod as_feet {
+    use super::{feet, Deserialize, Deserializer, Length, Serialize, Serializer};
+    use uom::si::length::foot;
+
+    pub fn serialize<S: Serializer>(level: &Length, serializer: S) -> Result<S::Ok, S::Error> {
+        level.get::<foot>().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Length, D::Error> {
+        f32::deserialize(deserializer).map(feet)
+    }
+}
+
+/// `#[serde(with = "crate::level::option_as_feet")]` for optional `Length` levels
+pub mod option_as_feet {
+    use super::{feet, Deserialize, Deserializer, Length, Serialize, Serializer};
+    use uom::si::length::foot;
+
+    #[allow(clippy::ref_option)]
+    pub fn serialize<S: Serializer>(
+        level: &Option<Length>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        level.map(|level| level.get::<foot>()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Length>, D::Error> {
+        Option::<f32>::deserialize(deserializer).map(|level| level.map(feet))
+    }
+}
+
+/// Flight level number of `level`, i.e. 245 for 24 500 ft
+pub fn flight_level(level: Length) -> u32 {
+    (level.get::<foot>() / 100.0).round() as u32
+}
+
+/// Altitude above mean sea level of an aircraft at `pressure_altitude` given `qnh`
+pub fn altitude(pressure_altitude: Length, qnh: Pressure) -> Length {
+    let pressure_ratio = (1.0 - pressure_altitude.get::<foot>() / ISA_HEIGHT_FT)
+        .powf(ISA_EXPONENT.recip())
+        * STANDARD_PRESSURE_HPA
+        / qnh.get::<hectopascal>();
+
+    feet(ISA_HEIGHT_FT * (1.0 - pressure_ratio.powf(ISA_EXPONENT)))
+}
+
+/// Pressure altitude of an aircraft at `altitude` above mean sea level given `qnh`
+pub fn pressure_altitude(altitude: Length, qnh: Pressure) -> Length {
+    let pressure_ratio = (1.0 - altitude.get::<foot>() / ISA_HEIGHT_FT).powf(ISA_EXPONENT.recip())
+        * qnh.get::<hectopascal>()
+        / STANDARD_PRESSURE_HPA;
+
+    feet(ISA_HEIGHT_FT * (1.0 - pressure_ratio.powf(ISA_EXPONENT)))
+}
+
+/// Level as displayed to controllers, an altitude below the transition level and a
+/// flight level above
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum IndicatedLevel {
+    /// altitude above mean sea level
+    Altitude(#[serde(with = "as_feet")] Length),
+    FlightLevel(u32),
+}
+
+impl Display for IndicatedLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Altitude(altitude) => write!(f, "A{:03}", flight_level(*altitude)),
+            Self::FlightLevel(level) => write!(f, "FL{level:03}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uom::si::{f32::Pressure, length::foot, pressure::hectopascal};
+
+    use super::{altitude, feet, pressure_altitude, IndicatedLevel, STANDARD_PRESSURE_HPA};
+
+    #[test]
+    fn test_qnh_conversion() {
+        let hpa = Pressure::new::<hectopascal>;
+        let rounded = |level: uom::si::f32::Length| level.get::<foot>().round() as i32;
+
+        assert_eq!(
+            rounded(altitude(feet(5_000.0), hpa(STANDARD_PRESSURE_HPA))),
+            5_000
+        );
+        // roughly 27 ft per hPa close to the ground
+        assert_eq!(rounded(altitude(feet(0.0), hpa(1023.25))), 271);
+        assert_eq!(rounded(altitude(feet(5_000.0), hpa(1003.25))), 4_735);
+        assert_eq!(
+            rounded(pressure_altitude(
+                altitude(feet(5_000.0), hpa(990.0)),
+                hpa(990.0)
+            )),
+            5_000
+        );
+        assert_eq!(IndicatedLevel::Altitude(feet(5_000.0)).to_string(), "A050");
+        assert_eq!(IndicatedLevel::FlightLevel(75).to_string(), "FL075");
+    }
+}
@@ -4,23 +4,29 @@ use bevy_derive::{Deref, DerefMut};
 use bevy_reflect::Reflect;
 use geo::{Coord, Point};
 use multimap::MultiMap;
+use pest::{iterators::Pair, RuleType};
 use serde::{Deserialize, Serialize, Serializer};
 use tracing::warn;
 
 pub mod adaptation;
 pub mod airway;
+pub mod aliases;
 pub mod asr;
+pub mod coordinate;
 pub mod ese;
+pub mod ground;
 pub mod icao_aircraft;
 pub mod icao_airlines;
 pub mod icao_airports;
 pub mod isec;
+pub mod level;
 pub mod navdata_airports;
 pub mod prf;
 pub mod sct;
 pub mod squawks;
 pub mod symbology;
 pub mod topsky;
+pub mod voice_channels;
 
 fn read_to_string(contents: &[u8]) -> Result<String, io::Error> {
     String::from_utf8(contents.to_vec()).or_else(|_| {
@@ -61,6 +67,45 @@ impl From<&str> for Sign {
 
 type DegMinSec = (Sign, u16, u8, f64);
 
+fn deg_min_sec_to_decimal((sign, deg, min, sec): DegMinSec) -> f64 {
+    (f64::from(deg) + f64::from(min) / 60.0 + sec / 3600.0) * f64::from(sign.sign())
+}
+
+/// Parts of a `sct_coord_part` pair of any grammar built on base.pest
+fn parse_sct_coord_part<R: RuleType>(pair: Pair<R>) -> DegMinSec {
+    let mut coordinate_part = pair.into_inner();
+    let hemi = coordinate_part.next().unwrap().as_str();
+    let sign = Sign::from(hemi);
+    let degrees_str = coordinate_part.next().unwrap().as_str();
+    let degrees = degrees_str
+        .parse::<u16>()
+        .inspect_err(|e| warn!("Could not parse coordinate, {e}: {degrees_str}"))
+        .unwrap();
+    let min = coordinate_part.next().unwrap().as_str().parse().unwrap();
+    let sec = coordinate_part.next().unwrap().as_str().parse().unwrap();
+
+    (sign, degrees, min, sec)
+}
+
+/// Degrees of a base.pest `coord_part` pair, either in EuroScope notation or decimal
+fn parse_coord_part<R: RuleType>(pair: Pair<R>) -> f64 {
+    let coordinate_part = pair.into_inner().next().unwrap();
+    // only `sct_coord_part` has inner pairs, `decimal` is atomic
+    if coordinate_part.clone().into_inner().next().is_some() {
+        deg_min_sec_to_decimal(parse_sct_coord_part(coordinate_part))
+    } else {
+        coordinate_part.as_str().parse().unwrap()
+    }
+}
+
+/// Coordinate of a base.pest `coordinate` pair, latitude first
+fn parse_coordinate<R: RuleType>(pair: Pair<R>) -> Coord {
+    let mut coordinate = pair.into_inner();
+    let y = parse_coord_part(coordinate.next().unwrap());
+    let x = parse_coord_part(coordinate.next().unwrap());
+    Coord { x, y }
+}
+
 fn decimal_to_dms(decimal: f64, is_latitude: bool) -> (u8, u8, f64, char) {
     let is_negative = decimal.is_sign_negative();
     let abs = decimal.abs();
@@ -95,13 +140,9 @@ pub trait DegMinSecExt {
 
 impl DegMinSecExt for Coord {
     fn from_deg_min_sec(lat: DegMinSec, lng: DegMinSec) -> Self {
-        let lat_deg = f64::from(lat.1);
-        let lat_min = f64::from(lat.2);
-        let lng_deg = f64::from(lng.1);
-        let lng_min = f64::from(lng.2);
         Self {
-            y: (lat_deg + lat_min / 60.0 + lat.3 / 3600.0) * f64::from(lat.0.sign()),
-            x: (lng_deg + lng_min / 60.0 + lng.3 / 3600.0) * f64::from(lng.0.sign()),
+            y: deg_min_sec_to_decimal(lat),
+            x: deg_min_sec_to_decimal(lng),
         }
     }
 
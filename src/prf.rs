@@ -1,9 +1,12 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::env::current_dir;
 use std::ffi::OsStr;
+use std::fmt::{self, Display};
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::{self, Component, Path, PathBuf};
 
+use itertools::Itertools as _;
 use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
 use serde::Serialize;
@@ -20,13 +23,15 @@ pub enum PrfError {
     FileRead(#[from] io::Error),
     #[error("failed to parse .prf file: {0}")]
     Parse(#[from] pest::error::Error<Rule>),
+    #[error("failed to write .prf file: {0}")]
+    FileWrite(io::Error),
 }
 
 #[derive(Parser)]
 #[grammar = "pest/prf.pest"]
 pub struct PrfParser;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, PartialEq)]
 pub struct Prf {
     settings: TwoKeyMap<String, String, String>,
     path: PathBuf,
@@ -34,6 +39,82 @@ pub struct Prf {
 
 pub type PrfResult = Result<Prf, PrfError>;
 
+/// Order in which EuroScope writes the categories of a .prf, unknown categories are
+/// written after these (alphabetically), `LastSession` always comes last.
+const CATEGORY_ORDER: [&str; 6] = [
+    "ASRFastKeys",
+    "Plugins",
+    "RecentFiles",
+    "Settings",
+    "Sounds",
+    "TeamSpeakVccs",
+];
+const LAST_CATEGORY: &str = "LastSession";
+
+fn category_rank(category: &str) -> usize {
+    if category == LAST_CATEGORY {
+        usize::MAX
+    } else {
+        CATEGORY_ORDER
+            .iter()
+            .position(|c| *c == category)
+            .unwrap_or(CATEGORY_ORDER.len())
+    }
+}
+
+/// Compares keys with embedded numbers numerically, i.e. `Plugin9` < `Plugin10`.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let chunks = |s: &str| {
+        s.chars()
+            .chunk_by(char::is_ascii_digit)
+            .into_iter()
+            .map(|(_, chunk)| chunk.collect::<String>())
+            .collect::<Vec<_>>()
+    };
+    chunks(a)
+        .iter()
+        .zip(chunks(b).iter())
+        .map(|(a, b)| match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num).then_with(|| a.cmp(b)),
+            _ => a.cmp(b),
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+fn absolute_path(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .or_else(|_| path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Inverse of `Prf::join_settings_path`: expresses `path` relative to `dir` in
+/// EuroScope's `\dir\file` notation.
+fn relative_settings_path(dir: &Path, path: &Path) -> String {
+    let dir_components = dir
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect::<Vec<_>>();
+    let path_components = path
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect::<Vec<_>>();
+    let common = dir_components
+        .iter()
+        .zip(&path_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let relative = std::iter::repeat_n("..".into(), dir_components.len() - common)
+        .chain(
+            path_components[common..]
+                .iter()
+                .map(|c| c.as_os_str().to_string_lossy()),
+        )
+        .join("\\");
+
+    format!("\\{relative}")
+}
+
 impl Prf {
     fn join_settings_path(&self, path: &str) -> PathBuf {
         let normalised_path = path.trim_end().replace('\\', "/");
@@ -43,6 +124,52 @@ impl Prf {
             current_dir().unwrap().join(normalised_path)
         }
     }
+
+    fn is_settings_path(value: &str) -> bool {
+        value.starts_with(['\\', '/'])
+    }
+
+    /// Value for a path setting, relative to this profile's location.
+    fn settings_path_value(&self, path: &Path) -> String {
+        let absolute = absolute_path(&current_dir().unwrap().join(path));
+        relative_settings_path(self.path.parent().unwrap(), &absolute)
+    }
+
+    /// The profile as if it was located at `path`, relative path settings are rewritten
+    /// so they keep pointing to the same files.
+    #[must_use]
+    pub fn relocate(&self, path: &Path) -> Self {
+        let path = absolute_path(path);
+        let out_dir = path.parent().unwrap();
+        let settings = self
+            .settings
+            .iter()
+            .map(|(key, value)| {
+                let value = if Self::is_settings_path(value) {
+                    relative_settings_path(out_dir, &self.join_settings_path(value))
+                } else {
+                    value.clone()
+                };
+                (key.clone(), value)
+            })
+            .collect();
+
+        Self {
+            settings: TwoKeyMap(settings),
+            path,
+        }
+    }
+
+    /// Writes the profile to `path`, rewriting relative paths, see `Prf::relocate`.
+    pub fn write(&self, path: &Path) -> Result<(), PrfError> {
+        fs_err::write(path, self.relocate(path).to_string()).map_err(PrfError::FileWrite)
+    }
+
+    #[must_use]
+    pub fn builder(&self) -> PrfBuilder {
+        PrfBuilder(self.clone())
+    }
+
     pub fn sct_path(&self) -> PathBuf {
         self.join_settings_path(&self.settings.0[&("Settings".to_string(), "sector".to_string())])
     }
@@ -85,6 +212,18 @@ impl Prf {
         )
     }
 
+    pub fn alias_path(&self) -> Option<PathBuf> {
+        self.settings
+            .get(&("Settings".to_string(), "alias".to_string()))
+            .map(|alias_path| self.join_settings_path(alias_path))
+    }
+
+    pub fn voice_channels_path(&self) -> Option<PathBuf> {
+        self.settings
+            .get(&("Settings".to_string(), "SettingsfileVOICE".to_string()))
+            .map(|voice_path| self.join_settings_path(voice_path))
+    }
+
     pub fn squawks_path(&self) -> Option<PathBuf> {
         self.settings
             .0
@@ -142,6 +281,123 @@ impl Prf {
     }
 }
 
+impl Display for Prf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.settings
+            .iter()
+            .sorted_by(|((cat_a, key_a), _), ((cat_b, key_b), _)| {
+                category_rank(cat_a)
+                    .cmp(&category_rank(cat_b))
+                    .then_with(|| cat_a.cmp(cat_b))
+                    .then_with(|| natural_cmp(key_a, key_b))
+            })
+            .try_for_each(|((category, key), value)| writeln!(f, "{category}\t{key}\t{value}"))
+    }
+}
+
+/// Splits `Plugin3Display1` into `(3, "Display1")`.
+fn split_plugin_key(key: &str) -> Option<(u32, &str)> {
+    let rest = key.strip_prefix("Plugin")?;
+    let digits_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    Some((rest[..digits_end].parse().ok()?, &rest[digits_end..]))
+}
+
+/// Common edits for generating profiles from a template, paths passed in are
+/// relative to the current directory or absolute.
+#[derive(Clone, Debug)]
+pub struct PrfBuilder(Prf);
+
+impl PrfBuilder {
+    fn set(&mut self, category: &str, key: &str, value: String) {
+        self.0
+            .settings
+            .insert((category.to_string(), key.to_string()), value);
+    }
+
+    fn plugin_indices(&self) -> Vec<u32> {
+        self.0
+            .settings
+            .keys()
+            .filter(|(category, _)| category == "Plugins")
+            .filter_map(|(_, key)| match split_plugin_key(key)? {
+                (index, "") => Some(index),
+                _ => None,
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[must_use]
+    pub fn sector(mut self, sct_path: &Path) -> Self {
+        let value = self.0.settings_path_value(sct_path);
+        self.set("Settings", "sector", value);
+        self
+    }
+
+    /// Appends a plugin after the already loaded ones.
+    #[must_use]
+    pub fn add_plugin(mut self, dll_path: &Path) -> Self {
+        let index = self.plugin_indices().last().map_or(0, |last| last + 1);
+        let value = self.0.settings_path_value(dll_path);
+        self.set("Plugins", &format!("Plugin{index}"), value);
+        self
+    }
+
+    /// Removes all plugins with the file name `dll_name` together with their display
+    /// registrations, renumbering the remaining plugins as EuroScope stops loading at
+    /// the first gap.
+    #[must_use]
+    pub fn remove_plugin(mut self, dll_name: &str) -> Self {
+        let (plugins, mut settings): (HashMap<_, _>, HashMap<_, _>) = self
+            .0
+            .settings
+            .drain()
+            .partition(|((category, _), _)| category == "Plugins");
+        let removed = plugins
+            .iter()
+            .filter_map(|((_, key), value)| {
+                let (index, "") = split_plugin_key(key)? else {
+                    return None;
+                };
+                (self.0.join_settings_path(value).file_name() == Some(OsStr::new(dll_name)))
+                    .then_some(index)
+            })
+            .collect::<Vec<_>>();
+        let renumbered = |index: u32| index - removed.iter().filter(|r| **r < index).count() as u32;
+
+        settings.extend(plugins.into_iter().filter_map(|((category, key), value)| {
+            let Some((index, suffix)) = split_plugin_key(&key) else {
+                return Some(((category, key), value));
+            };
+            (!removed.contains(&index)).then(|| {
+                let key = format!("Plugin{}{suffix}", renumbered(index));
+                ((category, key), value)
+            })
+        }));
+        self.0.settings = TwoKeyMap(settings);
+        self
+    }
+
+    /// Replaces the recently opened .asr files.
+    #[must_use]
+    pub fn recent_asrs(mut self, asr_paths: &[PathBuf]) -> Self {
+        self.0
+            .settings
+            .retain(|(category, _), _| category != "RecentFiles");
+        for (i, path) in asr_paths.iter().enumerate() {
+            let value = self.0.settings_path_value(path);
+            self.set("RecentFiles", &format!("Recent{}", i + 1), value);
+        }
+        self
+    }
+
+    pub fn build(self) -> Prf {
+        self.0
+    }
+}
+
 fn parse_setting(pair: Pair<Rule>) -> Option<((String, String), String)> {
     match pair.as_rule() {
         Rule::setting => {
@@ -158,10 +414,16 @@ fn parse_setting(pair: Pair<Rule>) -> Option<((String, String), String)> {
 
 #[cfg(test)]
 mod test {
-    use std::{fs, path::PathBuf};
+    use std::{env::temp_dir, fs, path::PathBuf};
 
     use super::Prf;
 
+    fn fixture() -> Prf {
+        let prf_path = PathBuf::from("./fixtures/iCAS2.prf");
+        let prf_contents = fs::read(&prf_path).unwrap();
+        Prf::parse(&prf_path, &prf_contents).unwrap()
+    }
+
     #[test]
     fn test_basic_paths() {
         let prf_path = PathBuf::from("./fixtures/iCAS2.prf");
@@ -210,4 +472,98 @@ mod test {
         );
         assert_eq!(prf.recent_path(2), None);
     }
+
+    #[test]
+    fn test_write_section_order() {
+        let written = fixture().to_string();
+        let lines = written.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 80);
+        assert_eq!(
+            lines[0],
+            "ASRFastKeys\t1\t\\EDMM\\ASR\\iCAS2\\EDDM_GND_GR.asr"
+        );
+        assert!(lines[79].starts_with("LastSession\tatis_url3\t"));
+        let plugins = lines
+            .iter()
+            .filter(|line| line.starts_with("Plugins"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            plugins[0],
+            &"Plugins\tPlugin0\t\\EDMM\\Plugins\\AfvEuroScopeBridge.dll"
+        );
+        assert_eq!(
+            plugins[26],
+            &"Plugins\tPlugin10\t\\EDMM\\Plugins\\IASsure\\IASsure.dll"
+        );
+    }
+
+    #[test]
+    fn test_write_relocated() {
+        let prf = fixture();
+        assert_eq!(prf.relocate(&prf.path), prf);
+
+        let out_dir = temp_dir().join("vatsim-parser-test-write-relocated");
+        fs::create_dir_all(&out_dir).unwrap();
+        let out_path = out_dir.join("relocated.prf");
+        prf.write(&out_path).unwrap();
+        let relocated = Prf::parse(&out_path, &fs::read(&out_path).unwrap()).unwrap();
+
+        assert_eq!(
+            relocated.sct_path().canonicalize().unwrap(),
+            prf.sct_path().canonicalize().unwrap()
+        );
+        assert_eq!(
+            relocated.symbology_path().canonicalize().unwrap(),
+            prf.symbology_path().canonicalize().unwrap()
+        );
+        assert_eq!(
+            relocated.settings[&("LastSession".to_string(), "atis_url0".to_string())],
+            prf.settings[&("LastSession".to_string(), "atis_url0".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let prf = fixture()
+            .builder()
+            .sector(&PathBuf::from("./fixtures/EDMM/EDMM_2.sct"))
+            .remove_plugin("TopSky.dll")
+            .add_plugin(&PathBuf::from("./fixtures/EDMM/Plugins/vSMR.dll"))
+            .recent_asrs(&[
+                PathBuf::from("./fixtures/EDDM_APP.asr"),
+                PathBuf::from("./fixtures/EDMM/ASR/EDDN_APP.asr"),
+            ])
+            .build();
+        let setting = |category: &str, key: &str| {
+            prf.settings
+                .get(&(category.to_string(), key.to_string()))
+                .map(String::as_str)
+        };
+
+        assert_eq!(setting("Settings", "sector"), Some("\\EDMM\\EDMM_2.sct"));
+        assert_eq!(prf.topsky_path(), None);
+        assert_eq!(
+            setting("Plugins", "Plugin1"),
+            Some("\\EDMM\\Plugins\\GRPlugin\\iCAS2\\GRplugin.dll")
+        );
+        assert_eq!(
+            setting("Plugins", "Plugin1Display0"),
+            Some("Ground Radar display")
+        );
+        assert_eq!(
+            setting("Plugins", "Plugin9"),
+            Some("\\EDMM\\Plugins\\IASsure\\IASsure.dll")
+        );
+        assert_eq!(
+            setting("Plugins", "Plugin10"),
+            Some("\\EDMM\\Plugins\\vSMR.dll")
+        );
+        assert_eq!(setting("Plugins", "Plugin11"), None);
+        assert_eq!(setting("RecentFiles", "Recent1"), Some("\\EDDM_APP.asr"));
+        assert_eq!(
+            setting("RecentFiles", "Recent2"),
+            Some("\\EDMM\\ASR\\EDDN_APP.asr")
+        );
+    }
 }
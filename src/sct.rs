@@ -12,13 +12,12 @@ use thiserror::Error;
 use tracing::warn;
 
 use crate::topsky::map::MapLine;
-use crate::Sign;
 use crate::{
     adaptation::{
         colours::Colour,
         locations::{Fix, Runway, NDB, VOR},
     },
-    DegMinSec, DegMinSecExt, Location,
+    parse_sct_coord_part, DegMinSec, DegMinSecExt, Location,
 };
 
 use super::read_to_string;
@@ -525,25 +524,10 @@ enum SectionName {
     Unsupported,
 }
 
-fn parse_coordinate_part(pair: Pair<Rule>) -> DegMinSec {
-    let mut coordinate_part = pair.into_inner();
-    let hemi = coordinate_part.next().unwrap().as_str();
-    let sign = Sign::from(hemi);
-    let degrees_str = coordinate_part.next().unwrap().as_str();
-    let degrees = degrees_str
-        .parse::<u16>()
-        .inspect_err(|e| warn!("Could not parse coordinate, {e}: {degrees_str}"))
-        .unwrap();
-    let min = coordinate_part.next().unwrap().as_str().parse().unwrap();
-    let sec = coordinate_part.next().unwrap().as_str().parse().unwrap();
-
-    (sign, degrees, min, sec)
-}
-
 fn parse_coordinate(pair: Pair<Rule>) -> Coord {
     let mut coordinate = pair.into_inner();
-    let y = parse_coordinate_part(coordinate.next().unwrap());
-    let x = parse_coordinate_part(coordinate.next().unwrap());
+    let y = parse_sct_coord_part(coordinate.next().unwrap());
+    let x = parse_sct_coord_part(coordinate.next().unwrap());
     Coord::from_deg_min_sec(y, x)
 }
 
@@ -757,6 +741,8 @@ fn parse_runway(pair: Pair<Rule>) -> Runway {
         headings: (heading1, heading2),
         location: (loc1, loc2),
         aerodrome,
+        width: None,
+        elevation: None,
     }
 }
 
@@ -773,9 +759,9 @@ fn parse_info_section(pair: Pair<Rule>, colours: &mut HashMap<String, Colour>) -
                 0 => sct_info.name = pair.as_str().to_string(),
                 1 => sct_info.default_callsign = pair.as_str().to_string(),
                 2 => sct_info.default_airport = pair.as_str().to_string(),
-                3 => y = parse_coordinate_part(pair),
+                3 => y = parse_sct_coord_part(pair),
                 4 => {
-                    let x = parse_coordinate_part(pair);
+                    let x = parse_sct_coord_part(pair);
                     sct_info.centre_point = Point::from_deg_min_sec(y, x);
                 }
                 5 => sct_info.miles_per_deg_lat = pair.as_str().parse().unwrap(),
@@ -1795,7 +1781,9 @@ A5         RTT RTT NUB NUB
                             y: 48.344_796_944_444_45,
                         }
                     ),
-                    aerodrome: "EDDM".to_string()
+                    aerodrome: "EDDM".to_string(),
+                    width: None,
+                    elevation: None,
                 },
                 Runway {
                     designators: ("08L".to_string(), "26R".to_string()),
@@ -1810,7 +1798,9 @@ A5         RTT RTT NUB NUB
                             y: 48.366_885_833_333_335,
                         }
                     ),
-                    aerodrome: "EDDM".to_string()
+                    aerodrome: "EDDM".to_string(),
+                    width: None,
+                    elevation: None,
                 },
                 Runway {
                     designators: ("07".to_string(), "25".to_string()),
@@ -1825,7 +1815,9 @@ A5         RTT RTT NUB NUB
                             y: 48.240_107_777_777_78,
                         }
                     ),
-                    aerodrome: "EDNX".to_string()
+                    aerodrome: "EDNX".to_string(),
+                    width: None,
+                    elevation: None,
                 },
                 Runway {
                     designators: ("04".to_string(), "22".to_string()),
@@ -1840,7 +1832,9 @@ A5         RTT RTT NUB NUB
                             y: 54.624_855_555_555_555,
                         }
                     ),
-                    aerodrome: "EGAC".to_string()
+                    aerodrome: "EGAC".to_string(),
+                    width: None,
+                    elevation: None,
                 }
             ]
         );
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
+use std::fmt::{self, Display};
 use std::io;
+use std::path::Path;
 
-use num_derive::FromPrimitive;
-use num_traits::FromPrimitive;
+use itertools::Itertools;
+use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::{FromPrimitive, ToPrimitive};
 use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 use thiserror::Error;
 use tracing::warn;
 
@@ -30,12 +33,370 @@ pub enum SymbologyError {
     Parse(#[from] pest::error::Error<Rule>),
     #[error("failed to read Symbology.txt file: {0}")]
     FileRead(#[from] io::Error),
+    #[error("failed to write Symbology.txt file: {0}")]
+    FileWrite(io::Error),
+}
+
+/// Enum of the names EuroScope uses, with a fallback for names unknown to this crate.
+/// Names are serialised and displayed as written in Symbology.txt.
+macro_rules! symbology_names {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $text:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+            /// kept verbatim
+            Unknown(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $text,)*
+                    Self::Unknown(name) => name,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(name: &str) -> Self {
+                match name {
+                    $($text => Self::$variant,)*
+                    name => Self::Unknown(name.to_string()),
+                }
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+    };
+}
+
+symbology_names! {
+    /// folder of a Symbology.txt item
+    SymbologyFolder {
+        Airports => "Airports",
+        LowAirways => "Low airways",
+        HighAirways => "High airways",
+        Fixes => "Fixes",
+        Sids => "Sids",
+        Stars => "Stars",
+        ArtccHighBoundary => "ARTCC high boundary",
+        ArtccBoundary => "ARTCC boundary",
+        ArtccLowBoundary => "ARTCC low boundary",
+        Geo => "Geo",
+        Vors => "VORs",
+        Ndbs => "NDBs",
+        Runways => "Runways",
+        Datablock => "Datablock",
+        Controller => "Controller",
+        Metar => "Metar",
+        Other => "Other",
+        Transitions => "Transitions",
+        Chat => "Chat",
+        GroundNetwork => "Ground Network",
+        Sector => "Sector",
+    }
+}
+
+symbology_names! {
+    /// attribute of a Symbology.txt item within its folder
+    SymbologyAttribute {
+        Symbol => "symbol",
+        Name => "name",
+        Line => "line",
+        Frequency => "frequency",
+        Centerline => "centerline",
+        ExtendedCenterline => "extended centerline",
+        NonConcerned => "non concerned",
+        Notified => "notified",
+        Assumed => "assumed",
+        TransferToMeInitiated => "transfer to me initiated",
+        Redundant => "redundant",
+        Information => "information",
+        EvenFlightLevel => "even flight level",
+        OddFlightLevel => "odd flight level",
+        Arrivals => "arrivals",
+        Departures => "departures",
+        User1 => "user 1",
+        User2 => "user 2",
+        User3 => "user 3",
+        User4 => "user 4",
+        User5 => "user 5",
+        User6 => "user 6",
+        Emergency => "emergency",
+        OngoingRequestedByMe => "ongoing requested by me",
+        OngoingRequestedByOther => "ongoing requested by other",
+        OngoingAccepted => "ongoing accepted",
+        OngoingRefused => "ongoing refused",
+        DetailedBackground => "detailed background",
+        ActiveItemBackground => "active item background",
+        ArrivalBackground => "arrival background",
+        DepartureBackground => "departure background",
+        AcListBackground => "AC list background",
+        AcListSelectedBackground => "AC list selected background",
+        DimmedAtConflictDetection => "dimmed at conflict detection",
+        SctaWarningRectangle => "SCTA warning rectangle",
+        SctaCflUsedWarningRectangle => "SCTA CFL used warning rectangle",
+        SctaWarningLeader => "SCTA warning leader",
+        SctaCflUsedWarningLeader => "SCTA CFL used warning leader",
+        Normal => "normal",
+        Breaking => "breaking",
+        Timeout => "timeout",
+        Modified => "modified",
+        Wait => "wait",
+        DistanceLine => "distance line",
+        DistanceValues => "distance values",
+        DistanceAnnotation => "distance annotation",
+        SeparationLeader => "separation leader",
+        SeparationLeader2 => "separation leader2",
+        Find => "find",
+        ValidAirway => "valid airway",
+        BadDirectionAirway => "bad direction airway",
+        UnconnectedAirway => "unconnected airway",
+        DirectNoAirway => "direct no airway",
+        FreeOfConflict => "free of conflict",
+        ConflictWarning => "conflict warning",
+        ConflictDetected => "conflict detected",
+        RouteAnnotation => "route annotation",
+        Freetext => "freetext",
+        RangeRings => "range rings",
+        OffAntennaRangeRings => "off antenna range rings",
+        PlaneRangeRings => "plane range rings",
+        ManualTaxiLine => "manual taxi line",
+        ManualTaxiLineEndsAtPredefinedPoint => "manual taxi line ends at predefined point",
+        PredefinedTaxiLine => "predefined taxi line",
+        TerminalTaxiLine => "terminal taxi line",
+        ListHeader => "list header",
+        NormalMenuItem => "normal menu item",
+        DisabledMenuItem => "disabled menu item",
+        TransitionGrid => "transition grid",
+        Text => "text",
+        Background => "background",
+        NameNormal => "name normal",
+        NameUnread => "name unread",
+        Exit => "exit",
+        Taxiway => "taxiway",
+        TerminalTaxiway => "terminal taxiway",
+        Msaw => "msaw",
+        ActiveSectorBackground => "active sector background",
+        InactiveSectorBackground => "inactive sector background",
+    }
+}
+
+/// Items in the order EuroScope writes them, other items are written afterwards.
+const ITEM_ORDER: &[(SymbologyFolder, SymbologyAttribute)] = &[
+    (SymbologyFolder::Airports, SymbologyAttribute::Symbol),
+    (SymbologyFolder::Airports, SymbologyAttribute::Name),
+    (SymbologyFolder::LowAirways, SymbologyAttribute::Line),
+    (SymbologyFolder::LowAirways, SymbologyAttribute::Name),
+    (SymbologyFolder::HighAirways, SymbologyAttribute::Line),
+    (SymbologyFolder::HighAirways, SymbologyAttribute::Name),
+    (SymbologyFolder::Fixes, SymbologyAttribute::Symbol),
+    (SymbologyFolder::Fixes, SymbologyAttribute::Name),
+    (SymbologyFolder::Sids, SymbologyAttribute::Line),
+    (SymbologyFolder::Stars, SymbologyAttribute::Line),
+    (SymbologyFolder::ArtccHighBoundary, SymbologyAttribute::Line),
+    (SymbologyFolder::ArtccBoundary, SymbologyAttribute::Line),
+    (SymbologyFolder::ArtccLowBoundary, SymbologyAttribute::Line),
+    (SymbologyFolder::Geo, SymbologyAttribute::Line),
+    (SymbologyFolder::Vors, SymbologyAttribute::Symbol),
+    (SymbologyFolder::Vors, SymbologyAttribute::Name),
+    (SymbologyFolder::Vors, SymbologyAttribute::Frequency),
+    (SymbologyFolder::Ndbs, SymbologyAttribute::Symbol),
+    (SymbologyFolder::Ndbs, SymbologyAttribute::Name),
+    (SymbologyFolder::Ndbs, SymbologyAttribute::Frequency),
+    (SymbologyFolder::Runways, SymbologyAttribute::Centerline),
+    (
+        SymbologyFolder::Runways,
+        SymbologyAttribute::ExtendedCenterline,
+    ),
+    (SymbologyFolder::Runways, SymbologyAttribute::Name),
+    (SymbologyFolder::Datablock, SymbologyAttribute::NonConcerned),
+    (SymbologyFolder::Datablock, SymbologyAttribute::Notified),
+    (SymbologyFolder::Datablock, SymbologyAttribute::Assumed),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::TransferToMeInitiated,
+    ),
+    (SymbologyFolder::Datablock, SymbologyAttribute::Redundant),
+    (SymbologyFolder::Datablock, SymbologyAttribute::Information),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::EvenFlightLevel,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::OddFlightLevel,
+    ),
+    (SymbologyFolder::Datablock, SymbologyAttribute::Arrivals),
+    (SymbologyFolder::Datablock, SymbologyAttribute::Departures),
+    (SymbologyFolder::Datablock, SymbologyAttribute::User1),
+    (SymbologyFolder::Datablock, SymbologyAttribute::User2),
+    (SymbologyFolder::Datablock, SymbologyAttribute::User3),
+    (SymbologyFolder::Datablock, SymbologyAttribute::User4),
+    (SymbologyFolder::Datablock, SymbologyAttribute::User5),
+    (SymbologyFolder::Datablock, SymbologyAttribute::User6),
+    (SymbologyFolder::Datablock, SymbologyAttribute::Emergency),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::OngoingRequestedByMe,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::OngoingRequestedByOther,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::OngoingAccepted,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::OngoingRefused,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::DetailedBackground,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::ActiveItemBackground,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::ArrivalBackground,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::DepartureBackground,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::AcListBackground,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::AcListSelectedBackground,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::DimmedAtConflictDetection,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::SctaWarningRectangle,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::SctaCflUsedWarningRectangle,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::SctaWarningLeader,
+    ),
+    (
+        SymbologyFolder::Datablock,
+        SymbologyAttribute::SctaCflUsedWarningLeader,
+    ),
+    (SymbologyFolder::Controller, SymbologyAttribute::Normal),
+    (SymbologyFolder::Controller, SymbologyAttribute::Breaking),
+    (SymbologyFolder::Controller, SymbologyAttribute::Timeout),
+    (SymbologyFolder::Metar, SymbologyAttribute::Normal),
+    (SymbologyFolder::Metar, SymbologyAttribute::Modified),
+    (SymbologyFolder::Metar, SymbologyAttribute::Timeout),
+    (SymbologyFolder::Other, SymbologyAttribute::Wait),
+    (SymbologyFolder::Other, SymbologyAttribute::DistanceLine),
+    (SymbologyFolder::Other, SymbologyAttribute::DistanceValues),
+    (
+        SymbologyFolder::Other,
+        SymbologyAttribute::DistanceAnnotation,
+    ),
+    (SymbologyFolder::Other, SymbologyAttribute::SeparationLeader),
+    (
+        SymbologyFolder::Other,
+        SymbologyAttribute::SeparationLeader2,
+    ),
+    (SymbologyFolder::Other, SymbologyAttribute::Find),
+    (SymbologyFolder::Other, SymbologyAttribute::ValidAirway),
+    (
+        SymbologyFolder::Other,
+        SymbologyAttribute::BadDirectionAirway,
+    ),
+    (
+        SymbologyFolder::Other,
+        SymbologyAttribute::UnconnectedAirway,
+    ),
+    (SymbologyFolder::Other, SymbologyAttribute::DirectNoAirway),
+    (SymbologyFolder::Other, SymbologyAttribute::FreeOfConflict),
+    (SymbologyFolder::Other, SymbologyAttribute::ConflictWarning),
+    (SymbologyFolder::Other, SymbologyAttribute::ConflictDetected),
+    (SymbologyFolder::Other, SymbologyAttribute::RouteAnnotation),
+    (SymbologyFolder::Other, SymbologyAttribute::Freetext),
+    (SymbologyFolder::Other, SymbologyAttribute::RangeRings),
+    (
+        SymbologyFolder::Other,
+        SymbologyAttribute::OffAntennaRangeRings,
+    ),
+    (SymbologyFolder::Other, SymbologyAttribute::PlaneRangeRings),
+    (SymbologyFolder::Other, SymbologyAttribute::ManualTaxiLine),
+    (
+        SymbologyFolder::Other,
+        SymbologyAttribute::ManualTaxiLineEndsAtPredefinedPoint,
+    ),
+    (
+        SymbologyFolder::Other,
+        SymbologyAttribute::PredefinedTaxiLine,
+    ),
+    (SymbologyFolder::Other, SymbologyAttribute::TerminalTaxiLine),
+    (SymbologyFolder::Other, SymbologyAttribute::ListHeader),
+    (SymbologyFolder::Other, SymbologyAttribute::NormalMenuItem),
+    (SymbologyFolder::Other, SymbologyAttribute::DisabledMenuItem),
+    (
+        SymbologyFolder::Transitions,
+        SymbologyAttribute::TransitionGrid,
+    ),
+    (SymbologyFolder::Chat, SymbologyAttribute::Text),
+    (SymbologyFolder::Chat, SymbologyAttribute::Background),
+    (SymbologyFolder::Chat, SymbologyAttribute::NameNormal),
+    (SymbologyFolder::Chat, SymbologyAttribute::NameUnread),
+    (SymbologyFolder::GroundNetwork, SymbologyAttribute::Exit),
+    (SymbologyFolder::GroundNetwork, SymbologyAttribute::Taxiway),
+    (
+        SymbologyFolder::GroundNetwork,
+        SymbologyAttribute::TerminalTaxiway,
+    ),
+    (SymbologyFolder::Sector, SymbologyAttribute::Line),
+    (SymbologyFolder::Sector, SymbologyAttribute::Msaw),
+    (
+        SymbologyFolder::Sector,
+        SymbologyAttribute::ActiveSectorBackground,
+    ),
+    (
+        SymbologyFolder::Sector,
+        SymbologyAttribute::InactiveSectorBackground,
+    ),
+];
+
+fn item_rank(item: &Item) -> usize {
+    ITEM_ORDER
+        .iter()
+        .position(|(folder, attribute)| *folder == item.folder && *attribute == item.attribute)
+        .unwrap_or(ITEM_ORDER.len())
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Item {
-    pub folder: String,
-    pub name: String,
+    pub folder: SymbologyFolder,
+    pub attribute: SymbologyAttribute,
     pub colour: Colour,
     pub font_size_symbol_scale: f32,
     pub line_style: String,
@@ -54,6 +415,16 @@ fn parse_linestyle(pair: &Pair<Rule>) -> String {
     }
 }
 
+fn linestyle_to_euroscope(line_style: &str) -> u8 {
+    match line_style {
+        LineStyle::DASH => 1,
+        LineStyle::DOT => 2,
+        LineStyle::DASHDOT => 3,
+        LineStyle::DASHDOTDOT => 4,
+        _ => 0,
+    }
+}
+
 fn parse_alignment(pair: &Pair<Rule>) -> Alignment {
     let alignment_val = pair.as_str().parse::<u8>().unwrap();
 
@@ -76,7 +447,22 @@ fn parse_alignment(pair: &Pair<Rule>) -> Alignment {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, FromPrimitive, Serialize)]
+fn alignment_to_euroscope(alignment: Alignment) -> u8 {
+    let horizontal = match alignment.horizontal {
+        HorizontalAlignment::Left => 0,
+        HorizontalAlignment::Center => 6,
+        HorizontalAlignment::Right => 12,
+    };
+    let vertical = match alignment.vertical {
+        VerticalAlignment::Top => 0,
+        VerticalAlignment::Center => 1,
+        VerticalAlignment::Bottom => 2,
+    };
+
+    horizontal + vertical
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive, Serialize)]
 pub enum SymbolType {
     Airport,
     NDB,
@@ -106,8 +492,48 @@ pub enum SymbolType {
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Symbology {
-    pub items: TwoKeyMap<String, String, Item>,
+    pub items: TwoKeyMap<SymbologyFolder, SymbologyAttribute, Item>,
     pub symbols: HashMap<SymbolType, Vec<SymbolRule>>,
+    /// `m_ClipArea`
+    pub clip_area: i32,
+    /// further `m_` settings following the clip area, i.e. the tag font, in file order.
+    /// The iCAS2 profile writes none of them.
+    pub settings: Vec<SymbologySetting>,
+}
+
+/// `m_` setting following the clip area
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum SymbologySetting {
+    /// `m_TagFontName`
+    TagFontName(String),
+    /// `m_TagFontBold`, written as `0` or `1`
+    TagFontBold(bool),
+    /// any other setting or an unreadable value, kept as written
+    Unknown { name: String, value: String },
+}
+
+impl SymbologySetting {
+    fn parse(name: &str, value: &str) -> Self {
+        match (name, value) {
+            ("TagFontName", _) => Self::TagFontName(value.to_string()),
+            ("TagFontBold", "0") => Self::TagFontBold(false),
+            ("TagFontBold", "1") => Self::TagFontBold(true),
+            _ => Self::Unknown {
+                name: name.to_string(),
+                value: value.to_string(),
+            },
+        }
+    }
+}
+
+impl Display for SymbologySetting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TagFontName(name) => write!(f, "m_TagFontName:{name}"),
+            Self::TagFontBold(bold) => write!(f, "m_TagFontBold:{}", u8::from(*bold)),
+            Self::Unknown { name, value } => write!(f, "m_{name}:{value}"),
+        }
+    }
 }
 
 pub type SymbologyResult = Result<Symbology, SymbologyError>;
@@ -115,8 +541,8 @@ pub type SymbologyResult = Result<Symbology, SymbologyError>;
 impl Item {
     fn parse(pair: Pair<Rule>) -> Option<Self> {
         let mut item = pair.into_inner();
-        let folder = item.next().unwrap().as_str().to_string();
-        let name = item.next().unwrap().as_str().to_string();
+        let folder = SymbologyFolder::from(item.next().unwrap().as_str());
+        let attribute = SymbologyAttribute::from(item.next().unwrap().as_str());
         let colour_str = item.next().unwrap().as_str();
         let colour_num = colour_str.parse::<i32>().unwrap();
         let font_size_symbol_scale = item.next().unwrap().as_str().parse().unwrap();
@@ -127,7 +553,7 @@ impl Item {
         match Colour::from_euroscope(colour_num) {
             Ok(colour) => Some(Self {
                 folder,
-                name,
+                attribute,
                 colour,
                 font_size_symbol_scale,
                 line_style,
@@ -135,7 +561,7 @@ impl Item {
                 text_alignment,
             }),
             Err(e) => {
-                warn!("Could not parse colour {folder}.{name}={colour_num}: {e}");
+                warn!("Could not parse colour {folder}.{attribute}={colour_num}: {e}");
                 None
             }
         }
@@ -237,18 +663,160 @@ fn parse_symbol_rules(pair: Pair<Rule>) -> Option<(SymbolType, Vec<SymbolRule>)>
     maybe_symbol_type.map(|symbol_type| (symbol_type, symbol_rules))
 }
 
+fn parse_footer(pair: Pair<Rule>) -> (i32, Vec<SymbologySetting>) {
+    let mut footer = pair.into_inner();
+    let clip_area = footer
+        .next()
+        .unwrap()
+        .into_inner()
+        .next()
+        .unwrap()
+        .as_str()
+        .parse()
+        .unwrap();
+    let settings = footer
+        .map(|pair| {
+            let mut setting = pair.into_inner();
+            let name = setting.next().unwrap().as_str();
+            let value = setting.next().unwrap().as_str();
+            SymbologySetting::parse(name, value)
+        })
+        .collect();
+
+    (clip_area, settings)
+}
+
+fn write_point(f: &mut fmt::Formatter<'_>, (x, y): (f64, f64)) -> fmt::Result {
+    write!(f, " {x} {y}")
+}
+
+fn write_symbol_rule(f: &mut fmt::Formatter<'_>, rule: &SymbolRule) -> fmt::Result {
+    write!(f, "SYMBOLITEM:")?;
+    match rule {
+        SymbolRule::Move(point) => {
+            write!(f, "MOVETO")?;
+            write_point(f, *point)?;
+        }
+        SymbolRule::Line(point) => {
+            write!(f, "LINETO")?;
+            write_point(f, *point)?;
+        }
+        SymbolRule::Pixel(point) => {
+            write!(f, "SETPIXEL")?;
+            write_point(f, *point)?;
+        }
+        SymbolRule::Arc(point, radius, start, end) => {
+            write!(f, "ARC")?;
+            write_point(f, *point)?;
+            write!(f, " {radius} {start} {end}")?;
+        }
+        SymbolRule::EllipticArc(point, radius_x, radius_y, start, end) => {
+            write!(f, "ARC")?;
+            write_point(f, *point)?;
+            write!(f, " {radius_x} {radius_y} {start} {end}")?;
+        }
+        SymbolRule::FilledArc(point, radius, start, end) => {
+            write!(f, "FILLARC")?;
+            write_point(f, *point)?;
+            write!(f, " {radius} {start} {end}")?;
+        }
+        SymbolRule::FilledEllipticArc(point, radius_x, radius_y, start, end) => {
+            write!(f, "FILLARC")?;
+            write_point(f, *point)?;
+            write!(f, " {radius_x} {radius_y} {start} {end}")?;
+        }
+        SymbolRule::Polygon(points) => {
+            write!(f, "POLYGON")?;
+            points.iter().try_for_each(|point| write_point(f, *point))?;
+        }
+    }
+    writeln!(f)
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{:?}:{}:{}:{}",
+            self.folder,
+            self.attribute,
+            self.colour.to_euroscope(),
+            self.font_size_symbol_scale,
+            linestyle_to_euroscope(&self.line_style),
+            self.line_weight,
+            alignment_to_euroscope(self.text_alignment)
+        )
+    }
+}
+
+impl Display for Symbology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SYMBOLOGY")?;
+        writeln!(f, "SYMBOLSIZE")?;
+        self.items
+            .0
+            .values()
+            .sorted_by(|a, b| {
+                item_rank(a).cmp(&item_rank(b)).then_with(|| {
+                    (a.folder.as_str(), a.attribute.as_str())
+                        .cmp(&(b.folder.as_str(), b.attribute.as_str()))
+                })
+            })
+            .try_for_each(|item| writeln!(f, "{item}"))?;
+        self.symbols
+            .iter()
+            .sorted_by_key(|(symbol_type, _)| symbol_type.to_u32())
+            .try_for_each(|(symbol_type, symbol_rules)| {
+                writeln!(f, "SYMBOL:{}", symbol_type.to_u32().unwrap())?;
+                symbol_rules
+                    .iter()
+                    .try_for_each(|rule| write_symbol_rule(f, rule))
+            })?;
+        writeln!(f, "m_ClipArea:{}", self.clip_area)?;
+        self.settings
+            .iter()
+            .try_for_each(|setting| writeln!(f, "{setting}"))?;
+        writeln!(f, "END")
+    }
+}
+
 impl Symbology {
+    pub fn item(&self, folder: SymbologyFolder, attribute: SymbologyAttribute) -> Option<&Item> {
+        self.items.get(&(folder, attribute))
+    }
+
+    pub fn tag_font_name(&self) -> Option<&str> {
+        self.settings.iter().find_map(|setting| match setting {
+            SymbologySetting::TagFontName(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn tag_font_bold(&self) -> Option<bool> {
+        self.settings.iter().find_map(|setting| match setting {
+            SymbologySetting::TagFontBold(bold) => Some(*bold),
+            _ => None,
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SymbologyError> {
+        fs_err::write(path, self.to_string()).map_err(SymbologyError::FileWrite)
+    }
+
     pub fn parse(content: &[u8]) -> SymbologyResult {
         let unparsed_file = read_to_string(content)?;
-        let (items, symbols) =
-            SymbologyParser::parse(Rule::symbology, &unparsed_file).map(|mut pairs| {
+        let (items, symbols, footer) = SymbologyParser::parse(Rule::symbology, &unparsed_file)
+            .map(|mut pairs| {
                 pairs.next().unwrap().into_inner().fold(
-                    (HashMap::new(), HashMap::new()),
-                    |(mut items, mut symbols), pair| {
+                    (HashMap::new(), HashMap::new(), None),
+                    |(mut items, mut symbols, mut footer), pair| {
                         match pair.as_rule() {
                             Rule::item => {
                                 if let Some(item) = Item::parse(pair) {
-                                    items.insert((item.folder.clone(), item.name.clone()), item);
+                                    items.insert(
+                                        (item.folder.clone(), item.attribute.clone()),
+                                        item,
+                                    );
                                 }
                             }
                             Rule::symbol => {
@@ -257,17 +825,21 @@ impl Symbology {
                                     symbols.insert(symbol_type, symbol_rules);
                                 }
                             }
-                            Rule::header | Rule::footer | Rule::EOI => (),
+                            Rule::footer => footer = Some(parse_footer(pair)),
+                            Rule::header | Rule::EOI => (),
                             rule => unreachable!("unhandled {rule:?}"),
                         }
-                        (items, symbols)
+                        (items, symbols, footer)
                     },
                 )
             })?;
+        let (clip_area, settings) = footer.unwrap_or_default();
 
         Ok(Symbology {
             items: TwoKeyMap(items),
             symbols,
+            clip_area,
+            settings,
         })
     }
 }
@@ -280,7 +852,9 @@ mod test {
             colours::Colour, line_styles::LineStyle, symbols::SymbolRule, Alignment,
             HorizontalAlignment, VerticalAlignment,
         },
-        symbology::{Item, SymbolType, Symbology},
+        symbology::{
+            Item, SymbolType, Symbology, SymbologyAttribute, SymbologyFolder, SymbologySetting,
+        },
     };
 
     #[test]
@@ -290,6 +864,7 @@ SYMBOLOGY
 SYMBOLSIZE
 Sector:msaw:32768:2.0:0:2:7
 Sector:inactive sector background:13158600:3.5:0:0:7
+Plugin:custom:255:1.0:0:1:0
 SYMBOL:0
 SYMBOLITEM:MOVETO -3 -3
 SYMBOLITEM:LINETO 3 -3
@@ -306,6 +881,9 @@ SYMBOLITEM:LINETO 0 -4
 SYMBOLITEM:LINETO 4 3
 SYMBOLITEM:LINETO -4 3
 m_ClipArea:0
+m_TagFontName:Euroscope
+m_TagFontBold:1
+m_TagFontShadow:2
 END
         ";
         let symbology = Symbology::parse(symbology_bytes);
@@ -313,12 +891,10 @@ END
             symbology
                 .as_ref()
                 .unwrap()
-                .items
-                .0
-                .get(&("Sector".to_string(), "msaw".to_string())),
+                .item(SymbologyFolder::Sector, SymbologyAttribute::Msaw),
             Some(&Item {
-                folder: "Sector".to_string(),
-                name: "msaw".to_string(),
+                folder: SymbologyFolder::Sector,
+                attribute: SymbologyAttribute::Msaw,
                 colour: Colour::from_rgb(0, 128, 0),
                 font_size_symbol_scale: 2.0,
                 line_style: LineStyle::SOLID.to_string(),
@@ -330,13 +906,13 @@ END
             })
         );
         assert_eq!(
-            symbology.as_ref().unwrap().items.0.get(&(
-                "Sector".to_string(),
-                "inactive sector background".to_string()
-            )),
+            symbology.as_ref().unwrap().item(
+                SymbologyFolder::Sector,
+                SymbologyAttribute::InactiveSectorBackground
+            ),
             Some(&Item {
-                folder: "Sector".to_string(),
-                name: "inactive sector background".to_string(),
+                folder: SymbologyFolder::Sector,
+                attribute: SymbologyAttribute::InactiveSectorBackground,
                 colour: Colour::from_rgb(200, 200, 200),
                 font_size_symbol_scale: 3.5,
                 line_style: LineStyle::SOLID.to_string(),
@@ -374,5 +950,51 @@ END
                 SymbolRule::Line((-4.0, 3.0)),
             ])
         );
+        assert_eq!(symbology.as_ref().unwrap().clip_area, 0);
+        assert_eq!(
+            symbology.as_ref().unwrap().settings,
+            vec![
+                SymbologySetting::TagFontName("Euroscope".to_string()),
+                SymbologySetting::TagFontBold(true),
+                SymbologySetting::Unknown {
+                    name: "TagFontShadow".to_string(),
+                    value: "2".to_string(),
+                },
+            ]
+        );
+        // unknown items are kept and written back
+        let custom = symbology.as_ref().unwrap().item(
+            SymbologyFolder::Unknown("Plugin".to_string()),
+            SymbologyAttribute::Unknown("custom".to_string()),
+        );
+        assert_eq!(
+            custom.map(|item| item.colour),
+            Some(Colour::from_rgb(255, 0, 0))
+        );
+        let written = symbology.as_ref().unwrap().to_string();
+        assert!(written.contains("Plugin:custom:255:1.0:0:1:0\n"));
+        assert!(written.contains("m_TagFontBold:1\nm_TagFontShadow:2\n"));
+        assert_eq!(
+            symbology.as_ref().unwrap().tag_font_name(),
+            Some("Euroscope")
+        );
+        assert_eq!(symbology.as_ref().unwrap().tag_font_bold(), Some(true));
+    }
+
+    #[test]
+    fn test_symbology_round_trip() {
+        let symbology_bytes = include_bytes!("../fixtures/EDMM/Settings/iCAS2/Symbology.txt");
+        let symbology = Symbology::parse(symbology_bytes).unwrap();
+
+        assert_eq!(
+            symbology.to_string(),
+            String::from_utf8_lossy(symbology_bytes)
+        );
+        assert_eq!(
+            Symbology::parse(symbology.to_string().as_bytes())
+                .unwrap()
+                .to_string(),
+            symbology.to_string()
+        );
     }
 }
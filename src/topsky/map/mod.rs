@@ -13,7 +13,7 @@ use tracing::warn;
 
 use crate::{
     adaptation::{colours::Colour, line_styles::LineStyle, maps::active::Active, Alignment},
-    read_to_string, DegMinSec, DegMinSecExt as _, Location, Sign,
+    parse_sct_coord_part, read_to_string, DegMinSec, DegMinSecExt as _, Location,
 };
 
 use super::{
@@ -31,21 +31,7 @@ impl CoordinatePart {
         let coordinate_part = pair.into_inner().next().unwrap();
         match coordinate_part.as_rule() {
             Rule::decimal => Self::Decimal(coordinate_part.as_str().parse().unwrap()),
-            Rule::sct_coord_part => {
-                let mut sct_coord_part = coordinate_part.into_inner();
-                let hemi = sct_coord_part.next().unwrap().as_str();
-                let sign = Sign::from(hemi);
-                let degrees = sct_coord_part
-                    .next()
-                    .unwrap()
-                    .as_str()
-                    .parse::<u16>()
-                    .unwrap();
-                let min = sct_coord_part.next().unwrap().as_str().parse().unwrap();
-                let sec = sct_coord_part.next().unwrap().as_str().parse().unwrap();
-
-                Self::DegMinSec((sign, degrees, min, sec))
-            }
+            Rule::sct_coord_part => Self::DegMinSec(parse_sct_coord_part(coordinate_part)),
             rule => unreachable!("{rule:?}"),
         }
     }
@@ -292,7 +278,8 @@ impl MapRule {
                     Rule::coordpoly => Some(MapRule::CoordPoly(
                         pair.into_inner().next().unwrap().as_str().to_string(),
                     )),
-                    // TODO
+                    // TODO, the course based COORD_HM and COORD_PBD need the magnetic
+                    // variation of `Locations` once implemented
                     rule @ (Rule::circle
                     | Rule::fontstyle
                     | Rule::textalign
@@ -246,4 +246,14 @@ impl Topsky {
             overrides,
         })
     }
+
+    /// Copy of this setup using the colours of an alternative TopSky settings file,
+    /// i.e. a night colour set. Colours missing from that file are kept.
+    pub fn with_colours_from(&self, settings_path: &Path) -> TopskyResult {
+        let (colours, _) = parse_topsky_settings(&read_to_string(&fs_err::read(settings_path)?)?)?;
+        let mut topsky = self.clone();
+        topsky.colours.extend(colours);
+
+        Ok(topsky)
+    }
 }
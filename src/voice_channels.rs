@@ -0,0 +1,103 @@
+use std::io;
+
+use pest::Parser;
+use pest_derive::Parser;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::read_to_string;
+
+#[derive(Parser)]
+#[grammar = "pest/base.pest"]
+#[grammar = "pest/voice_channels.pest"]
+pub struct VoiceChannelsParser;
+
+#[derive(Error, Debug)]
+pub enum VoiceChannelsError {
+    #[error("failed to parse voice channels: {0}")]
+    Parse(#[from] pest::error::Error<Rule>),
+    #[error("failed to read voice channels: {0}")]
+    FileRead(#[from] io::Error),
+}
+
+/// Voice channel definition of the profile's voice settings, one per line as
+/// `name:frequency:voice server:channel`.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct VoiceChannel {
+    pub name: String,
+    pub frequency: String,
+    pub server: Option<String>,
+    pub channel: Option<String>,
+}
+
+pub type VoiceChannelsResult = Result<Vec<VoiceChannel>, VoiceChannelsError>;
+
+fn non_empty(s: &str) -> Option<String> {
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+pub fn parse_voice_channels(content: &[u8]) -> VoiceChannelsResult {
+    let unparsed_file = read_to_string(content)?;
+    let voice_channels_parse = VoiceChannelsParser::parse(Rule::voice_channels, &unparsed_file);
+
+    Ok(voice_channels_parse.map(|mut pairs| {
+        pairs
+            .next()
+            .unwrap()
+            .into_inner()
+            .filter(|pair| matches!(pair.as_rule(), Rule::voice_channel))
+            .map(|pair| {
+                let mut voice_channel = pair.into_inner();
+                let name = voice_channel.next().unwrap().as_str().to_string();
+                let frequency = voice_channel.next().unwrap().as_str().to_string();
+                let server = voice_channel.next().and_then(|p| non_empty(p.as_str()));
+                let channel = voice_channel.next().and_then(|p| non_empty(p.as_str()));
+
+                VoiceChannel {
+                    name,
+                    frequency,
+                    server,
+                    channel,
+                }
+            })
+            .collect()
+    })?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_voice_channels, VoiceChannel};
+
+    #[test]
+    fn test_voice_channels() {
+        let content = b"; comment
+EDMM_ALB_CTR:129.100:afv.vatsim.net:EDMM_ALB_CTR
+EDDM_ATIS:123.13
+no channel
+EDMM_WLD_CTR:132.235::";
+
+        assert_eq!(
+            parse_voice_channels(content).unwrap(),
+            vec![
+                VoiceChannel {
+                    name: "EDMM_ALB_CTR".to_string(),
+                    frequency: "129.100".to_string(),
+                    server: Some("afv.vatsim.net".to_string()),
+                    channel: Some("EDMM_ALB_CTR".to_string()),
+                },
+                VoiceChannel {
+                    name: "EDDM_ATIS".to_string(),
+                    frequency: "123.13".to_string(),
+                    server: None,
+                    channel: None,
+                },
+                VoiceChannel {
+                    name: "EDMM_WLD_CTR".to_string(),
+                    frequency: "132.235".to_string(),
+                    server: None,
+                    channel: None,
+                },
+            ]
+        );
+    }
+}